@@ -0,0 +1,106 @@
+//! Instructions per second on a loop of ALU operations, with the opcode dispatch tables and
+//! with the decode-then-execute path they replaced. Run with `cargo bench --features bench`.
+
+use std::time::{Duration, Instant};
+
+use oxi_boy::assembler::assemble;
+use oxi_boy::cartridge::header_checksum;
+use oxi_boy::{ExecutionError, GameBoy};
+
+/// Every ALU operation on registers, (HL) and immediates, some CB-prefixed ones and a
+/// taken jump back to the start.
+const PROGRAM: &str = r#"
+    SECTION "Header", ROM0[$0100]
+        nop
+        jp Start
+
+    SECTION "Main", ROM0[$0150]
+    Start:
+        ld hl, $C000
+        ld bc, $1234
+        ld de, $5678
+    Loop:
+        add a, b
+        adc a, c
+        sub d
+        sbc a, e
+        and h
+        xor l
+        or a
+        cp b
+        add a, [hl]
+        adc a, [hl]
+        sub [hl]
+        sbc a, [hl]
+        and [hl]
+        xor [hl]
+        or [hl]
+        cp [hl]
+        add a, $11
+        adc a, $22
+        sub $33
+        sbc a, $44
+        and $55
+        xor $66
+        or $77
+        cp $88
+        inc b
+        dec c
+        inc [hl]
+        dec [hl]
+        daa
+        cpl
+        scf
+        ccf
+        rlca
+        rra
+        swap a
+        rlc b
+        srl c
+        bit 7, d
+        set 3, e
+        res 3, e
+        add hl, de
+        inc de
+        jr Loop
+"#;
+
+const RUN_TIME: Duration = Duration::from_secs(3);
+
+/// Millions of instructions and of machine cycles per second `step` manages on `rom`.
+fn measure(rom: &[u8], step: fn(&mut GameBoy) -> Result<u8, ExecutionError>) -> (f64, f64) {
+    let mut gameboy = GameBoy::new();
+    gameboy.load_rom(rom.to_vec()).unwrap();
+
+    let start = Instant::now();
+    let mut instructions: u64 = 0;
+    while start.elapsed() < RUN_TIME {
+        for _ in 0..100_000 {
+            step(&mut gameboy).unwrap();
+        }
+        instructions += 100_000;
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+
+    (
+        instructions as f64 / elapsed / 1e6,
+        gameboy.cycles() as f64 / elapsed / 1e6,
+    )
+}
+
+fn main() {
+    let mut rom = assemble(PROGRAM).unwrap().image();
+    rom.resize(0x8000, 0x00);
+    rom[0x014D] = header_checksum(&rom);
+
+    let (decoded, decoded_cycles) = measure(&rom, GameBoy::step_decoded);
+    let (tables, table_cycles) = measure(&rom, GameBoy::step);
+
+    println!("alu mix, million instructions per second (million machine cycles):");
+    println!(
+        "  decode + execute: {:6.1} ({:.1})",
+        decoded, decoded_cycles
+    );
+    println!("  dispatch tables:  {:6.1} ({:.1})", tables, table_cycles);
+    println!("  speedup:          {:6.2}x", tables / decoded);
+}
@@ -0,0 +1,1542 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::instructions::{
+    ArithmeticRegisters, Indirect, Instruction, JumpCondition, StackRegisters, WideRegisters,
+};
+
+/// Assembles RGBDS style source into sections of machine code.
+///
+/// Each line holds an optional label (`name:`, or `.name:` for a label local to the last global
+/// one) followed by an instruction or a directive:
+///
+/// - `SECTION "name", ROM0[$0150]` starts a section at a fixed address. Lines before the first
+///   `SECTION` go into an unnamed section at $0000.
+/// - `name EQU expression` defines a constant.
+/// - `db`, `dw` and `ds` emit bytes, little endian words and blocks of filler.
+///
+/// Mnemonics, registers and directives are case insensitive. Memory operands can be written with
+/// parentheses or brackets, and comments start with `;`. Expressions support `$` hexadecimal
+/// and `%` binary numbers, `@` for the address of the current line, `HIGH()`, `LOW()` and the
+/// usual arithmetic, bitwise and shift operators. Jump targets are addresses, so `JR @+2` jumps
+/// to the next instruction, as printed by the disassembler.
+pub fn assemble(source: &str) -> Result<Assembly, AssembleError> {
+    let lines = parse_source(source)?;
+    let mut symbols = HashMap::new();
+
+    // The first pass only works out the size of every line, and with it the labels.
+    let mut address = 0u32;
+    for line in &lines {
+        let error = |kind| AssembleError {
+            line: line.number,
+            kind,
+        };
+
+        if let Some(Statement::Section { address: start, .. }) = &line.statement {
+            let context = Context::new(&symbols, address, true);
+            address = context.word(start).map_err(error)? as u32;
+        }
+        if let Some(label) = &line.label {
+            define(&mut symbols, label, address as i64).map_err(error)?;
+        }
+
+        match &line.statement {
+            Some(Statement::Equ { name, value }) => {
+                let context = Context::new(&symbols, address, true);
+                let value = context.value(value).map_err(error)?;
+                define(&mut symbols, name, value).map_err(error)?;
+            }
+            Some(statement) => {
+                let context = Context::new(&symbols, address, false);
+                address += statement.size(&context).map_err(error)? as u32;
+            }
+            None => {}
+        }
+    }
+
+    let mut sections: Vec<Section> = Vec::new();
+    let mut section_lines = Vec::new();
+    for line in &lines {
+        let error = |kind| AssembleError {
+            line: line.number,
+            kind,
+        };
+        let address = sections
+            .last()
+            .map(|section| section.address as u32 + section.bytes.len() as u32)
+            .unwrap_or(0);
+        let context = Context::new(&symbols, address, true);
+
+        match &line.statement {
+            Some(Statement::Section { name, address }) => {
+                sections.push(Section {
+                    name: name.clone(),
+                    address: context.word(address).map_err(error)?,
+                    bytes: Vec::new(),
+                });
+                section_lines.push(line.number);
+            }
+            Some(Statement::Equ { .. }) | None => {}
+            Some(statement) => {
+                if sections.is_empty() {
+                    sections.push(Section {
+                        name: String::new(),
+                        address: 0,
+                        bytes: Vec::new(),
+                    });
+                    section_lines.push(line.number);
+                }
+                let section = sections.last_mut().unwrap();
+                statement
+                    .emit(&context, &mut section.bytes)
+                    .map_err(error)?;
+                if section.address as usize + section.bytes.len() > 0x1_0000 {
+                    return Err(error(AssembleErrorKind::SectionOverflow(
+                        section.name.clone(),
+                    )));
+                }
+            }
+        }
+    }
+
+    check_overlaps(&sections, &section_lines)?;
+    Ok(Assembly { sections, symbols })
+}
+
+/// Assembles a single instruction, as printed by the disassembler, placed at address $0000.
+pub fn assemble_instruction(source: &str) -> Result<Instruction, AssembleError> {
+    let error = |kind| AssembleError { line: 1, kind };
+    let line = parse_line(1, source, &mut None)?;
+    let symbols = HashMap::new();
+    let context = Context::new(&symbols, 0, true);
+
+    match line.statement {
+        Some(Statement::Instruction { mnemonic, operands }) if line.label.is_none() => {
+            build(&mnemonic, &operands, &context).map_err(error)
+        }
+        _ => Err(error(AssembleErrorKind::Syntax(
+            "expected a single instruction".to_string(),
+        ))),
+    }
+}
+
+/// The output of `assemble`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assembly {
+    sections: Vec<Section>,
+    symbols: HashMap<String, i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub name: String,
+    pub address: u16,
+    pub bytes: Vec<u8>,
+}
+
+impl Assembly {
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// Value of a label or constant. Local labels are named `Global.local`.
+    pub fn symbol(&self, name: &str) -> Option<i64> {
+        self.symbols.get(name).copied()
+    }
+
+    /// All sections laid out in one buffer that starts at address $0000, gaps filled with
+    /// zeroes. Handy for loading a program as a ROM.
+    pub fn image(&self) -> Vec<u8> {
+        let end = self
+            .sections
+            .iter()
+            .map(|section| section.address as usize + section.bytes.len())
+            .max()
+            .unwrap_or(0);
+
+        let mut image = vec![0; end];
+        for section in &self.sections {
+            let start = section.address as usize;
+            image[start..start + section.bytes.len()].copy_from_slice(&section.bytes);
+        }
+        image
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssembleError {
+    /// Line of the source the error was found on, counting from 1.
+    pub line: usize,
+    pub kind: AssembleErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssembleErrorKind {
+    Syntax(String),
+    UnknownMnemonic(String),
+    /// The operands do not match any form of the mnemonic.
+    InvalidOperands(String),
+    UndefinedSymbol(String),
+    DuplicateSymbol(String),
+    ValueOutOfRange(i64),
+    DivisionByZero,
+    /// The section runs past $FFFF.
+    SectionOverflow(String),
+    OverlappingSections(String, String),
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            AssembleErrorKind::Syntax(message) => write!(f, "{}", message),
+            AssembleErrorKind::UnknownMnemonic(mnemonic) => {
+                write!(f, "unknown mnemonic {}", mnemonic)
+            }
+            AssembleErrorKind::InvalidOperands(mnemonic) => {
+                write!(f, "invalid operands for {}", mnemonic)
+            }
+            AssembleErrorKind::UndefinedSymbol(name) => write!(f, "undefined symbol {}", name),
+            AssembleErrorKind::DuplicateSymbol(name) => write!(f, "{} is already defined", name),
+            AssembleErrorKind::ValueOutOfRange(value) => write!(f, "{} is out of range", value),
+            AssembleErrorKind::DivisionByZero => write!(f, "division by zero"),
+            AssembleErrorKind::SectionOverflow(name) => {
+                write!(f, "section \"{}\" runs past $FFFF", name)
+            }
+            AssembleErrorKind::OverlappingSections(first, second) => {
+                write!(f, "sections \"{}\" and \"{}\" overlap", first, second)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AssembleError {}
+
+fn define(
+    symbols: &mut HashMap<String, i64>,
+    name: &str,
+    value: i64,
+) -> Result<(), AssembleErrorKind> {
+    if symbols.insert(name.to_string(), value).is_some() {
+        return Err(AssembleErrorKind::DuplicateSymbol(name.to_string()));
+    }
+    Ok(())
+}
+
+/// Reports the later of two overlapping sections, `lines` holding the line each section starts
+/// on.
+fn check_overlaps(sections: &[Section], lines: &[usize]) -> Result<(), AssembleError> {
+    let mut ranges: Vec<_> = sections
+        .iter()
+        .zip(lines)
+        .filter(|(section, _)| !section.bytes.is_empty())
+        .collect();
+    ranges.sort_by_key(|(section, _)| section.address);
+
+    for pair in ranges.windows(2) {
+        let ((first, _), (second, &line)) = (pair[0], pair[1]);
+        if first.address as usize + first.bytes.len() > second.address as usize {
+            return Err(AssembleError {
+                line,
+                kind: AssembleErrorKind::OverlappingSections(
+                    first.name.clone(),
+                    second.name.clone(),
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+struct Line {
+    number: usize,
+    label: Option<String>,
+    statement: Option<Statement>,
+}
+
+enum Statement {
+    Section {
+        name: String,
+        address: Expr,
+    },
+    Equ {
+        name: String,
+        value: Expr,
+    },
+    Db(Vec<Data>),
+    Dw(Vec<Expr>),
+    Ds {
+        count: Expr,
+        fill: Option<Expr>,
+    },
+    Instruction {
+        mnemonic: String,
+        operands: Vec<Operand>,
+    },
+}
+
+enum Data {
+    Byte(Expr),
+    Text(String),
+}
+
+impl Statement {
+    /// Bytes the statement emits. Instructions have the same size whatever their operand values,
+    /// so symbols defined further down do not need to be known yet.
+    fn size(&self, context: &Context) -> Result<usize, AssembleErrorKind> {
+        match self {
+            Statement::Section { .. } | Statement::Equ { .. } => Ok(0),
+            Statement::Db(data) => Ok(data
+                .iter()
+                .map(|item| match item {
+                    Data::Byte(_) => 1,
+                    Data::Text(text) => text.len(),
+                })
+                .sum()),
+            Statement::Dw(words) => Ok(words.len() * 2),
+            Statement::Ds { count, .. } => context.as_strict().count(count),
+            Statement::Instruction { mnemonic, operands } => {
+                Ok(build(mnemonic, operands, context)?.length())
+            }
+        }
+    }
+
+    fn emit(&self, context: &Context, bytes: &mut Vec<u8>) -> Result<(), AssembleErrorKind> {
+        match self {
+            Statement::Section { .. } | Statement::Equ { .. } => {}
+            Statement::Db(data) => {
+                for item in data {
+                    match item {
+                        Data::Byte(value) => bytes.push(context.byte(value)?),
+                        Data::Text(text) => bytes.extend_from_slice(text.as_bytes()),
+                    }
+                }
+            }
+            Statement::Dw(words) => {
+                for word in words {
+                    let word = context.word(word)?;
+                    bytes.extend_from_slice(&[word as u8, (word >> 8) as u8]);
+                }
+            }
+            Statement::Ds { count, fill } => {
+                let fill = match fill {
+                    Some(fill) => context.byte(fill)?,
+                    None => 0,
+                };
+                let count = context.count(count)?;
+                bytes.resize(bytes.len() + count, fill);
+            }
+            Statement::Instruction { mnemonic, operands } => {
+                bytes.extend(build(mnemonic, operands, context)?.encode());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Evaluates expressions for the line at `address`. Outside of `strict` mode undefined symbols
+/// and out of range values evaluate to zero, which is all the first pass needs.
+struct Context<'a> {
+    symbols: &'a HashMap<String, i64>,
+    address: u16,
+    strict: bool,
+}
+
+impl<'a> Context<'a> {
+    fn new(symbols: &'a HashMap<String, i64>, address: u32, strict: bool) -> Self {
+        Context {
+            symbols,
+            address: address as u16,
+            strict,
+        }
+    }
+
+    fn as_strict(&self) -> Context<'a> {
+        Context::new(self.symbols, self.address as u32, true)
+    }
+
+    fn value(&self, expr: &Expr) -> Result<i64, AssembleErrorKind> {
+        match expr.evaluate(self) {
+            Err(_) if !self.strict => Ok(0),
+            result => result,
+        }
+    }
+
+    fn ranged(&self, expr: &Expr, min: i64, max: i64) -> Result<i64, AssembleErrorKind> {
+        match self.value(expr)? {
+            value if (min..=max).contains(&value) => Ok(value),
+            _ if !self.strict => Ok(0),
+            value => Err(AssembleErrorKind::ValueOutOfRange(value)),
+        }
+    }
+
+    /// A byte, given either as unsigned or as two's complement.
+    fn byte(&self, expr: &Expr) -> Result<u8, AssembleErrorKind> {
+        Ok(self.ranged(expr, -0x80, 0xFF)? as u8)
+    }
+
+    fn word(&self, expr: &Expr) -> Result<u16, AssembleErrorKind> {
+        Ok(self.ranged(expr, -0x8000, 0xFFFF)? as u16)
+    }
+
+    fn signed(&self, expr: &Expr) -> Result<i8, AssembleErrorKind> {
+        Ok(self.ranged(expr, -0x80, 0x7F)? as i8)
+    }
+
+    fn count(&self, expr: &Expr) -> Result<usize, AssembleErrorKind> {
+        Ok(self.ranged(expr, 0, 0x1_0000)? as usize)
+    }
+
+    /// Offset of a JR to `target`, counted from the end of the two byte instruction.
+    fn relative(&self, target: &Expr) -> Result<i8, AssembleErrorKind> {
+        let offset = self.value(target)? - (self.address as i64 + 2);
+        match offset {
+            -0x80..=0x7F => Ok(offset as i8),
+            _ if !self.strict => Ok(0),
+            _ => Err(AssembleErrorKind::ValueOutOfRange(offset)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Number(i64),
+    Symbol(String),
+    /// `@`, the address of the current line.
+    Current,
+    Negate(Box<Expr>),
+    Complement(Box<Expr>),
+    High(Box<Expr>),
+    Low(Box<Expr>),
+    Binary(Operator, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    And,
+    Or,
+    Xor,
+    ShiftLeft,
+    ShiftRight,
+}
+
+impl Expr {
+    fn evaluate(&self, context: &Context) -> Result<i64, AssembleErrorKind> {
+        match self {
+            Expr::Number(value) => Ok(*value),
+            Expr::Symbol(name) => context
+                .symbols
+                .get(name)
+                .copied()
+                .ok_or_else(|| AssembleErrorKind::UndefinedSymbol(name.clone())),
+            Expr::Current => Ok(context.address as i64),
+            Expr::Negate(expr) => Ok(expr.evaluate(context)?.wrapping_neg()),
+            Expr::Complement(expr) => Ok(!expr.evaluate(context)?),
+            Expr::High(expr) => Ok((expr.evaluate(context)? >> 8) & 0xFF),
+            Expr::Low(expr) => Ok(expr.evaluate(context)? & 0xFF),
+            Expr::Binary(operator, left, right) => {
+                let left = left.evaluate(context)?;
+                let right = right.evaluate(context)?;
+                match operator {
+                    Operator::Add => Ok(left.wrapping_add(right)),
+                    Operator::Subtract => Ok(left.wrapping_sub(right)),
+                    Operator::Multiply => Ok(left.wrapping_mul(right)),
+                    Operator::Divide => left
+                        .checked_div(right)
+                        .ok_or(AssembleErrorKind::DivisionByZero),
+                    Operator::Remainder => left
+                        .checked_rem(right)
+                        .ok_or(AssembleErrorKind::DivisionByZero),
+                    Operator::And => Ok(left & right),
+                    Operator::Or => Ok(left | right),
+                    Operator::Xor => Ok(left ^ right),
+                    Operator::ShiftLeft => Ok(left.wrapping_shl(right as u32)),
+                    Operator::ShiftRight => Ok(left.wrapping_shr(right as u32)),
+                }
+            }
+        }
+    }
+}
+
+/// An operand as written, before it is matched against the forms of the mnemonic.
+enum Operand {
+    /// One of A, B, C, D, E, H, L, AF, BC, DE, HL, SP, NZ, Z or NC, in upper case. C doubles as
+    /// the carry condition.
+    Register(String),
+    Memory(Memory),
+    /// SP+e in LD HL,SP+e.
+    StackOffset(Expr),
+    Value(Expr),
+}
+
+enum Memory {
+    HL,
+    HLIncrement,
+    HLDecrement,
+    BC,
+    DE,
+    /// (C), or ($FF00+C) with the expression holding $FF00.
+    HighC(Option<Expr>),
+    Address(Expr),
+}
+
+/// Constructors of the instruction forms a mnemonic picks from.
+type WithRegister = fn(ArithmeticRegisters) -> Instruction;
+type WithValue = fn(u8) -> Instruction;
+type WithBitAndRegister = fn(u8, ArithmeticRegisters) -> Instruction;
+
+fn build(
+    mnemonic: &str,
+    operands: &[Operand],
+    context: &Context,
+) -> Result<Instruction, AssembleErrorKind> {
+    let invalid = || AssembleErrorKind::InvalidOperands(mnemonic.to_string());
+
+    let instruction = match (mnemonic, operands) {
+        ("NOP", []) => Instruction::NOP(),
+        ("STOP", []) => Instruction::STOP(),
+        ("HALT", []) => Instruction::HALT(),
+        ("DI", []) => Instruction::DI(),
+        ("EI", []) => Instruction::EI(),
+        ("DAA", []) => Instruction::DAA(),
+        ("CPL", []) => Instruction::CPL(),
+        ("SCF", []) => Instruction::SCF(),
+        ("CCF", []) => Instruction::CCF(),
+        ("RLCA", []) => Instruction::RLCA(),
+        ("RRCA", []) => Instruction::RRCA(),
+        ("RLA", []) => Instruction::RLA(),
+        ("RRA", []) => Instruction::RRA(),
+        ("RETI", []) => Instruction::RETI(),
+
+        ("ADD", [Operand::Register(hl), source]) if hl == "HL" => {
+            Instruction::ADDHL(wide_register(source).ok_or_else(invalid)?)
+        }
+        ("ADD", [Operand::Register(sp), Operand::Value(offset)]) if sp == "SP" => {
+            Instruction::ADDSP(context.signed(offset)?)
+        }
+        ("ADD", _)
+        | ("ADC", _)
+        | ("SUB", _)
+        | ("SBC", _)
+        | ("AND", _)
+        | ("XOR", _)
+        | ("OR", _)
+        | ("CP", _) => {
+            let source = match operands {
+                [source] => source,
+                [Operand::Register(a), source] if a == "A" => source,
+                _ => return Err(invalid()),
+            };
+            build_alu(mnemonic, source, context).ok_or_else(invalid)??
+        }
+
+        ("INC", [target]) | ("DEC", [target]) => {
+            let increment = mnemonic == "INC";
+            match (target, register(target), wide_register(target)) {
+                (Operand::Memory(Memory::HL), _, _) if increment => Instruction::INCR(),
+                (Operand::Memory(Memory::HL), _, _) => Instruction::DECR(),
+                (_, Some(reg), _) if increment => Instruction::INC(reg),
+                (_, Some(reg), _) => Instruction::DEC(reg),
+                (_, _, Some(reg)) if increment => Instruction::INCW(reg),
+                (_, _, Some(reg)) => Instruction::DECW(reg),
+                _ => return Err(invalid()),
+            }
+        }
+
+        ("RLC", [target])
+        | ("RRC", [target])
+        | ("RL", [target])
+        | ("RR", [target])
+        | ("SLA", [target])
+        | ("SRA", [target])
+        | ("SWAP", [target])
+        | ("SRL", [target]) => {
+            let (on_register, on_memory): (WithRegister, _) = match mnemonic {
+                "RLC" => (Instruction::RLC, Instruction::RLCR()),
+                "RRC" => (Instruction::RRC, Instruction::RRCR()),
+                "RL" => (Instruction::RL, Instruction::RLR()),
+                "RR" => (Instruction::RR, Instruction::RRR()),
+                "SLA" => (Instruction::SLA, Instruction::SLAR()),
+                "SRA" => (Instruction::SRA, Instruction::SRAR()),
+                "SWAP" => (Instruction::SWAP, Instruction::SWAPR()),
+                _ => (Instruction::SRL, Instruction::SRLR()),
+            };
+            match (target, register(target)) {
+                (Operand::Memory(Memory::HL), _) => on_memory,
+                (_, Some(reg)) => on_register(reg),
+                _ => return Err(invalid()),
+            }
+        }
+
+        ("BIT", [Operand::Value(bit), target])
+        | ("RES", [Operand::Value(bit), target])
+        | ("SET", [Operand::Value(bit), target]) => {
+            let (on_register, on_memory): (WithBitAndRegister, WithValue) = match mnemonic {
+                "BIT" => (Instruction::BIT, Instruction::BITR),
+                "RES" => (Instruction::RES, Instruction::RESR),
+                _ => (Instruction::SET, Instruction::SETR),
+            };
+            let bit = context.ranged(bit, 0, 7)? as u8;
+            match (target, register(target)) {
+                (Operand::Memory(Memory::HL), _) => on_memory(bit),
+                (_, Some(reg)) => on_register(bit, reg),
+                _ => return Err(invalid()),
+            }
+        }
+
+        ("LD", [target, source]) => build_ld(target, source, context)?.ok_or_else(invalid)?,
+        ("LDH", [target, source]) => build_ldh(target, source, context)?.ok_or_else(invalid)?,
+        ("LDI", [Operand::Register(a), Operand::Memory(Memory::HL)]) if a == "A" => {
+            Instruction::LDA(Indirect::HLIncrement)
+        }
+        ("LDI", [Operand::Memory(Memory::HL), Operand::Register(a)]) if a == "A" => {
+            Instruction::STA(Indirect::HLIncrement)
+        }
+        ("LDD", [Operand::Register(a), Operand::Memory(Memory::HL)]) if a == "A" => {
+            Instruction::LDA(Indirect::HLDecrement)
+        }
+        ("LDD", [Operand::Memory(Memory::HL), Operand::Register(a)]) if a == "A" => {
+            Instruction::STA(Indirect::HLDecrement)
+        }
+
+        ("JP", [target]) if is_hl(target) => Instruction::JPHL(),
+        ("JP", [Operand::Value(target)]) => {
+            Instruction::JP(JumpCondition::Always, context.word(target)?)
+        }
+        ("JP", [condition, Operand::Value(target)]) => Instruction::JP(
+            jump_condition(condition).ok_or_else(invalid)?,
+            context.word(target)?,
+        ),
+        ("JR", [Operand::Value(target)]) => {
+            Instruction::JR(JumpCondition::Always, context.relative(target)?)
+        }
+        ("JR", [condition, Operand::Value(target)]) => Instruction::JR(
+            jump_condition(condition).ok_or_else(invalid)?,
+            context.relative(target)?,
+        ),
+        ("CALL", [Operand::Value(target)]) => {
+            Instruction::CALL(JumpCondition::Always, context.word(target)?)
+        }
+        ("CALL", [condition, Operand::Value(target)]) => Instruction::CALL(
+            jump_condition(condition).ok_or_else(invalid)?,
+            context.word(target)?,
+        ),
+        ("RET", []) => Instruction::RET(JumpCondition::Always),
+        ("RET", [condition]) => Instruction::RET(jump_condition(condition).ok_or_else(invalid)?),
+        ("RST", [Operand::Value(vector)]) => {
+            let vector = context.ranged(vector, 0, 0x38)?;
+            if vector % 8 != 0 {
+                return Err(AssembleErrorKind::ValueOutOfRange(vector));
+            }
+            Instruction::RST(vector as u8)
+        }
+
+        ("PUSH", [source]) => Instruction::PUSH(stack_register(source).ok_or_else(invalid)?),
+        ("POP", [target]) => Instruction::POP(stack_register(target).ok_or_else(invalid)?),
+
+        _ if MNEMONICS.contains(&mnemonic) => return Err(invalid()),
+        _ => return Err(AssembleErrorKind::UnknownMnemonic(mnemonic.to_string())),
+    };
+
+    Ok(instruction)
+}
+
+const MNEMONICS: [&str; 46] = [
+    "NOP", "STOP", "HALT", "DI", "EI", "DAA", "CPL", "SCF", "CCF", "RLCA", "RRCA", "RLA", "RRA",
+    "RETI", "ADD", "ADC", "SUB", "SBC", "AND", "XOR", "OR", "CP", "INC", "DEC", "RLC", "RRC", "RL",
+    "RR", "SLA", "SRA", "SWAP", "SRL", "BIT", "RES", "SET", "LD", "LDH", "LDI", "LDD", "JP", "JR",
+    "CALL", "RET", "RST", "PUSH", "POP",
+];
+
+/// JP HL is also written JP (HL).
+fn is_hl(operand: &Operand) -> bool {
+    match operand {
+        Operand::Register(name) => name == "HL",
+        Operand::Memory(Memory::HL) => true,
+        _ => false,
+    }
+}
+
+/// ALU operation with an 8-bit source, `None` if the source is not one of the allowed forms.
+fn build_alu(
+    mnemonic: &str,
+    source: &Operand,
+    context: &Context,
+) -> Option<Result<Instruction, AssembleErrorKind>> {
+    let (on_register, on_immediate, on_memory): (WithRegister, WithValue, Instruction) =
+        match mnemonic {
+            "ADD" => (Instruction::ADD, Instruction::ADDI, Instruction::ADDR()),
+            "ADC" => (Instruction::ADC, Instruction::ADCI, Instruction::ADCR()),
+            "SUB" => (Instruction::SUB, Instruction::SUBI, Instruction::SUBR()),
+            "SBC" => (Instruction::SBC, Instruction::SBCI, Instruction::SBCR()),
+            "AND" => (Instruction::AND, Instruction::ANDI, Instruction::ANDR()),
+            "XOR" => (Instruction::XOR, Instruction::XORI, Instruction::XORR()),
+            "OR" => (Instruction::OR, Instruction::ORI, Instruction::ORR()),
+            _ => (Instruction::CP, Instruction::CPI, Instruction::CPR()),
+        };
+
+    match source {
+        Operand::Memory(Memory::HL) => Some(Ok(on_memory)),
+        Operand::Value(immediate) => Some(context.byte(immediate).map(on_immediate)),
+        _ => register(source).map(|reg| Ok(on_register(reg))),
+    }
+}
+
+fn build_ld(
+    target: &Operand,
+    source: &Operand,
+    context: &Context,
+) -> Result<Option<Instruction>, AssembleErrorKind> {
+    let instruction = match (target, source) {
+        (Operand::Memory(Memory::HL), Operand::Value(immediate)) => {
+            Instruction::STRI(context.byte(immediate)?)
+        }
+        (Operand::Memory(Memory::HL), _) => match register(source) {
+            Some(reg) => Instruction::STR(reg),
+            None => return Ok(None),
+        },
+        (Operand::Memory(Memory::Address(address)), Operand::Register(sp)) if sp == "SP" => {
+            Instruction::STSP(context.word(address)?)
+        }
+        (Operand::Register(sp), Operand::Register(hl)) if sp == "SP" && hl == "HL" => {
+            Instruction::LDSPHL()
+        }
+        (Operand::Register(hl), Operand::StackOffset(offset)) if hl == "HL" => {
+            Instruction::LDHLSP(context.signed(offset)?)
+        }
+        (Operand::Register(a), Operand::Memory(memory)) if a == "A" => {
+            match indirect(memory, context)? {
+                Some(source) => Instruction::LDA(source),
+                None => Instruction::LDR(ArithmeticRegisters::A),
+            }
+        }
+        (Operand::Memory(memory), Operand::Register(a)) if a == "A" => Instruction::STA(
+            indirect(memory, context)?
+                .ok_or(AssembleErrorKind::InvalidOperands("LD".to_string()))?,
+        ),
+        (_, Operand::Memory(Memory::HL)) => match register(target) {
+            Some(reg) => Instruction::LDR(reg),
+            None => return Ok(None),
+        },
+        (_, Operand::Value(immediate)) => match (register(target), wide_register(target)) {
+            (Some(reg), _) => Instruction::LDI(reg, context.byte(immediate)?),
+            (_, Some(reg)) => Instruction::LDW(reg, context.word(immediate)?),
+            _ => return Ok(None),
+        },
+        _ => match (register(target), register(source)) {
+            (Some(target), Some(source)) => Instruction::LD(target, source),
+            _ => return Ok(None),
+        },
+    };
+
+    Ok(Some(instruction))
+}
+
+fn build_ldh(
+    target: &Operand,
+    source: &Operand,
+    context: &Context,
+) -> Result<Option<Instruction>, AssembleErrorKind> {
+    let high = |memory: &Memory| -> Result<Option<Indirect>, AssembleErrorKind> {
+        match memory {
+            Memory::HighC(_) => indirect(memory, context),
+            Memory::Address(address) => match context.value(address)? {
+                offset @ 0x00..=0xFF | offset @ 0xFF00..=0xFFFF => {
+                    Ok(Some(Indirect::HighImmediate(offset as u8)))
+                }
+                _ if !context.strict => Ok(Some(Indirect::HighImmediate(0))),
+                offset => Err(AssembleErrorKind::ValueOutOfRange(offset)),
+            },
+            _ => Ok(None),
+        }
+    };
+
+    Ok(match (target, source) {
+        (Operand::Register(a), Operand::Memory(memory)) if a == "A" => {
+            high(memory)?.map(Instruction::LDA)
+        }
+        (Operand::Memory(memory), Operand::Register(a)) if a == "A" => {
+            high(memory)?.map(Instruction::STA)
+        }
+        _ => None,
+    })
+}
+
+/// The memory operand of LD A,(...) and LD (...),A. `None` stands for (HL), which has opcodes
+/// of its own.
+fn indirect(memory: &Memory, context: &Context) -> Result<Option<Indirect>, AssembleErrorKind> {
+    Ok(Some(match memory {
+        Memory::HL => return Ok(None),
+        Memory::HLIncrement => Indirect::HLIncrement,
+        Memory::HLDecrement => Indirect::HLDecrement,
+        Memory::BC => Indirect::BC,
+        Memory::DE => Indirect::DE,
+        Memory::HighC(None) => Indirect::HighC,
+        Memory::HighC(Some(base)) => match context.value(base)? {
+            0xFF00 => Indirect::HighC,
+            _ if !context.strict => Indirect::HighC,
+            base => return Err(AssembleErrorKind::ValueOutOfRange(base)),
+        },
+        Memory::Address(address) => Indirect::Immediate(context.word(address)?),
+    }))
+}
+
+fn register(operand: &Operand) -> Option<ArithmeticRegisters> {
+    match operand {
+        Operand::Register(name) => match name.as_str() {
+            "A" => Some(ArithmeticRegisters::A),
+            "B" => Some(ArithmeticRegisters::B),
+            "C" => Some(ArithmeticRegisters::C),
+            "D" => Some(ArithmeticRegisters::D),
+            "E" => Some(ArithmeticRegisters::E),
+            "H" => Some(ArithmeticRegisters::H),
+            "L" => Some(ArithmeticRegisters::L),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn wide_register(operand: &Operand) -> Option<WideRegisters> {
+    match operand {
+        Operand::Register(name) => match name.as_str() {
+            "BC" => Some(WideRegisters::BC),
+            "DE" => Some(WideRegisters::DE),
+            "HL" => Some(WideRegisters::HL),
+            "SP" => Some(WideRegisters::SP),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn stack_register(operand: &Operand) -> Option<StackRegisters> {
+    match operand {
+        Operand::Register(name) => match name.as_str() {
+            "BC" => Some(StackRegisters::BC),
+            "DE" => Some(StackRegisters::DE),
+            "HL" => Some(StackRegisters::HL),
+            "AF" => Some(StackRegisters::AF),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn jump_condition(operand: &Operand) -> Option<JumpCondition> {
+    match operand {
+        Operand::Register(name) => match name.as_str() {
+            "NZ" => Some(JumpCondition::NotZero),
+            "Z" => Some(JumpCondition::Zero),
+            "NC" => Some(JumpCondition::NotCarry),
+            "C" => Some(JumpCondition::Carry),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+const REGISTER_NAMES: [&str; 15] = [
+    "A", "B", "C", "D", "E", "H", "L", "AF", "BC", "DE", "HL", "SP", "NZ", "Z", "NC",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Identifier(String),
+    Number(i64),
+    Text(String),
+    Symbol(char),
+    ShiftLeft,
+    ShiftRight,
+}
+
+fn parse_source(source: &str) -> Result<Vec<Line>, AssembleError> {
+    let mut scope = None;
+    source
+        .lines()
+        .enumerate()
+        .map(|(index, text)| parse_line(index + 1, text, &mut scope))
+        .collect()
+}
+
+/// Parses one line of source. `scope` is the last global label, which local labels hang off.
+fn parse_line(
+    number: usize,
+    text: &str,
+    scope: &mut Option<String>,
+) -> Result<Line, AssembleError> {
+    let error = |kind| AssembleError { line: number, kind };
+    let mut tokens = tokenize(text).map_err(error)?;
+
+    // A global label opens the scope of the local labels that follow, itself included.
+    if let [Token::Identifier(name), Token::Symbol(':'), ..] = tokens.as_slice() {
+        if !name.starts_with('.') {
+            *scope = Some(name.clone());
+        }
+    }
+    for token in tokens.iter_mut() {
+        if let Token::Identifier(name) = token {
+            if name.starts_with('.') {
+                if let Some(scope) = scope {
+                    *name = format!("{}{}", scope, name);
+                }
+            }
+        }
+    }
+
+    let mut rest = tokens.as_slice();
+    let mut label = None;
+    if let [Token::Identifier(name), Token::Symbol(':'), tail @ ..] = rest {
+        label = Some(name.clone());
+        rest = match tail {
+            [Token::Symbol(':'), tail @ ..] => tail,
+            _ => tail,
+        };
+    }
+
+    let statement = parse_statement(rest).map_err(error)?;
+    Ok(Line {
+        number,
+        label,
+        statement,
+    })
+}
+
+fn parse_statement(tokens: &[Token]) -> Result<Option<Statement>, AssembleErrorKind> {
+    let (keyword, rest) = match tokens {
+        [] => return Ok(None),
+        [Token::Identifier(keyword), rest @ ..] => (keyword, rest),
+        _ => return Err(syntax("expected a mnemonic or directive")),
+    };
+
+    if let [Token::Identifier(equ), value @ ..] = rest {
+        if equ.eq_ignore_ascii_case("EQU") {
+            return Ok(Some(Statement::Equ {
+                name: keyword.clone(),
+                value: parse_expression(value)?,
+            }));
+        }
+    }
+
+    let keyword = keyword.to_ascii_uppercase();
+    let arguments = split_arguments(rest);
+
+    let statement = match keyword.as_str() {
+        "SECTION" => match rest {
+            [Token::Text(name), Token::Symbol(','), Token::Identifier(_), Token::Symbol('['), address @ .., Token::Symbol(']')] => {
+                Statement::Section {
+                    name: name.clone(),
+                    address: parse_expression(address)?,
+                }
+            }
+            _ => return Err(syntax("expected SECTION \"name\", TYPE[address]")),
+        },
+        "DB" => Statement::Db(
+            arguments
+                .iter()
+                .map(|argument| match argument {
+                    [Token::Text(text)] => Ok(Data::Text(text.clone())),
+                    _ => parse_expression(argument).map(Data::Byte),
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        "DW" => Statement::Dw(
+            arguments
+                .iter()
+                .map(|argument| parse_expression(argument))
+                .collect::<Result<_, _>>()?,
+        ),
+        "DS" => match arguments.as_slice() {
+            [count] => Statement::Ds {
+                count: parse_expression(count)?,
+                fill: None,
+            },
+            [count, fill] => Statement::Ds {
+                count: parse_expression(count)?,
+                fill: Some(parse_expression(fill)?),
+            },
+            _ => return Err(syntax("expected ds count[, fill]")),
+        },
+        _ => Statement::Instruction {
+            mnemonic: keyword,
+            operands: arguments
+                .iter()
+                .map(|argument| parse_operand(argument))
+                .collect::<Result<_, _>>()?,
+        },
+    };
+
+    Ok(Some(statement))
+}
+
+/// Splits `tokens` at the commas that are not nested in parentheses or brackets.
+fn split_arguments(tokens: &[Token]) -> Vec<&[Token]> {
+    if tokens.is_empty() {
+        return Vec::new();
+    }
+
+    let mut arguments = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (index, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Symbol('(') | Token::Symbol('[') => depth += 1,
+            Token::Symbol(')') | Token::Symbol(']') => depth -= 1,
+            Token::Symbol(',') if depth == 0 => {
+                arguments.push(&tokens[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    arguments.push(&tokens[start..]);
+    arguments
+}
+
+fn parse_operand(tokens: &[Token]) -> Result<Operand, AssembleErrorKind> {
+    match tokens {
+        [Token::Identifier(name)] if is_register(name) => {
+            return Ok(Operand::Register(name.to_ascii_uppercase()))
+        }
+        [Token::Identifier(sp), offset @ ..]
+            if sp.eq_ignore_ascii_case("SP")
+                && matches!(
+                    offset.first(),
+                    Some(Token::Symbol('+')) | Some(Token::Symbol('-'))
+                ) =>
+        {
+            return Ok(Operand::StackOffset(parse_expression(offset)?))
+        }
+        [Token::Symbol(open), inner @ .., Token::Symbol(close)]
+            if matching(*open) == Some(*close) && encloses(tokens) =>
+        {
+            return parse_memory(inner).map(Operand::Memory)
+        }
+        _ => {}
+    }
+    parse_expression(tokens).map(Operand::Value)
+}
+
+fn parse_memory(tokens: &[Token]) -> Result<Memory, AssembleErrorKind> {
+    let name = |token: &Token| match token {
+        Token::Identifier(name) => name.to_ascii_uppercase(),
+        _ => String::new(),
+    };
+
+    match tokens {
+        [register] => match name(register).as_str() {
+            "HL" => return Ok(Memory::HL),
+            "HLI" => return Ok(Memory::HLIncrement),
+            "HLD" => return Ok(Memory::HLDecrement),
+            "BC" => return Ok(Memory::BC),
+            "DE" => return Ok(Memory::DE),
+            "C" => return Ok(Memory::HighC(None)),
+            _ => {}
+        },
+        [register, Token::Symbol('+')] if name(register) == "HL" => return Ok(Memory::HLIncrement),
+        [register, Token::Symbol('-')] if name(register) == "HL" => return Ok(Memory::HLDecrement),
+        [base @ .., Token::Symbol('+'), register] if name(register) == "C" => {
+            return Ok(Memory::HighC(Some(parse_expression(base)?)))
+        }
+        _ => {}
+    }
+    parse_expression(tokens).map(Memory::Address)
+}
+
+fn is_register(name: &str) -> bool {
+    REGISTER_NAMES
+        .iter()
+        .any(|register| register.eq_ignore_ascii_case(name))
+}
+
+fn matching(open: char) -> Option<char> {
+    match open {
+        '(' => Some(')'),
+        '[' => Some(']'),
+        _ => None,
+    }
+}
+
+/// Whether the first token of `tokens` is closed by the last one, so `(1)+(2)` is not a
+/// memory operand.
+fn encloses(tokens: &[Token]) -> bool {
+    let mut depth = 0;
+    for (index, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Symbol('(') | Token::Symbol('[') => depth += 1,
+            Token::Symbol(')') | Token::Symbol(']') => {
+                depth -= 1;
+                if depth == 0 {
+                    return index == tokens.len() - 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+fn parse_expression(tokens: &[Token]) -> Result<Expr, AssembleErrorKind> {
+    let mut parser = ExpressionParser {
+        tokens,
+        position: 0,
+    };
+    let expr = parser.additive()?;
+    match parser.tokens.get(parser.position) {
+        None => Ok(expr),
+        Some(token) => Err(syntax(&format!("unexpected {:?} in expression", token))),
+    }
+}
+
+/// Recursive descent over the operators, from the loosest binding `+` and `-` to the
+/// tightest `*`, `/` and `%`.
+struct ExpressionParser<'t> {
+    tokens: &'t [Token],
+    position: usize,
+}
+
+impl<'t> ExpressionParser<'t> {
+    fn additive(&mut self) -> Result<Expr, AssembleErrorKind> {
+        self.binary(Self::bitwise, |token| match token {
+            Token::Symbol('+') => Some(Operator::Add),
+            Token::Symbol('-') => Some(Operator::Subtract),
+            _ => None,
+        })
+    }
+
+    fn bitwise(&mut self) -> Result<Expr, AssembleErrorKind> {
+        self.binary(Self::shift, |token| match token {
+            Token::Symbol('&') => Some(Operator::And),
+            Token::Symbol('|') => Some(Operator::Or),
+            Token::Symbol('^') => Some(Operator::Xor),
+            _ => None,
+        })
+    }
+
+    fn shift(&mut self) -> Result<Expr, AssembleErrorKind> {
+        self.binary(Self::multiplicative, |token| match token {
+            Token::ShiftLeft => Some(Operator::ShiftLeft),
+            Token::ShiftRight => Some(Operator::ShiftRight),
+            _ => None,
+        })
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, AssembleErrorKind> {
+        self.binary(Self::unary, |token| match token {
+            Token::Symbol('*') => Some(Operator::Multiply),
+            Token::Symbol('/') => Some(Operator::Divide),
+            Token::Symbol('%') => Some(Operator::Remainder),
+            _ => None,
+        })
+    }
+
+    fn binary(
+        &mut self,
+        operand: fn(&mut Self) -> Result<Expr, AssembleErrorKind>,
+        operator: fn(&Token) -> Option<Operator>,
+    ) -> Result<Expr, AssembleErrorKind> {
+        let mut left = operand(self)?;
+        while let Some(op) = self.tokens.get(self.position).and_then(operator) {
+            self.position += 1;
+            let right = operand(self)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, AssembleErrorKind> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+
+        match token {
+            Some(Token::Symbol('-')) => Ok(Expr::Negate(Box::new(self.unary()?))),
+            Some(Token::Symbol('+')) => self.unary(),
+            Some(Token::Symbol('~')) => Ok(Expr::Complement(Box::new(self.unary()?))),
+            Some(Token::Symbol('@')) => Ok(Expr::Current),
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::Symbol('(')) => {
+                let expr = self.additive()?;
+                self.expect(')')?;
+                Ok(expr)
+            }
+            Some(Token::Identifier(name)) => match name.to_ascii_uppercase().as_str() {
+                "HIGH" | "LOW" => {
+                    self.expect('(')?;
+                    let expr = Box::new(self.additive()?);
+                    self.expect(')')?;
+                    if name.eq_ignore_ascii_case("HIGH") {
+                        Ok(Expr::High(expr))
+                    } else {
+                        Ok(Expr::Low(expr))
+                    }
+                }
+                _ => Ok(Expr::Symbol(name)),
+            },
+            Some(token) => Err(syntax(&format!("unexpected {:?} in expression", token))),
+            None => Err(syntax("expression ends early")),
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), AssembleErrorKind> {
+        if self.tokens.get(self.position) == Some(&Token::Symbol(symbol)) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(syntax(&format!("expected '{}'", symbol)))
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, AssembleErrorKind> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        // Whether the previous token ends a value, making a following `%` the remainder
+        // operator rather than the prefix of a binary number.
+        let after_value = matches!(
+            tokens.last(),
+            Some(Token::Identifier(_))
+                | Some(Token::Number(_))
+                | Some(Token::Symbol(')'))
+                | Some(Token::Symbol(']'))
+                | Some(Token::Symbol('@'))
+        );
+
+        match c {
+            ';' => break,
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '0'..='9' => tokens.push(Token::Number(read_number(&mut chars, 10)?)),
+            '$' => {
+                chars.next();
+                tokens.push(Token::Number(read_number(&mut chars, 16)?));
+            }
+            '%' if !after_value => {
+                chars.next();
+                tokens.push(Token::Number(read_number(&mut chars, 2)?));
+            }
+            '"' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => text.push('\n'),
+                            Some('0') => text.push('\0'),
+                            Some(escaped) => text.push(escaped),
+                            None => return Err(syntax("unterminated string")),
+                        },
+                        Some(c) => text.push(c),
+                        None => return Err(syntax("unterminated string")),
+                    }
+                }
+                tokens.push(Token::Text(text));
+            }
+            c if c.is_ascii_alphabetic() || c == '_' || c == '.' => {
+                let mut name = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '#' {
+                        name.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Identifier(name));
+            }
+            '<' | '>' => {
+                chars.next();
+                if chars.next() != Some(c) {
+                    return Err(syntax(&format!("expected '{}{}'", c, c)));
+                }
+                tokens.push(if c == '<' {
+                    Token::ShiftLeft
+                } else {
+                    Token::ShiftRight
+                });
+            }
+            '(' | ')' | '[' | ']' | ',' | ':' | '+' | '-' | '*' | '/' | '%' | '&' | '|' | '^'
+            | '~' | '@' => {
+                chars.next();
+                tokens.push(Token::Symbol(c));
+            }
+            _ => return Err(syntax(&format!("unexpected character '{}'", c))),
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn read_number(
+    chars: &mut std::iter::Peekable<std::str::Chars>,
+    radix: u32,
+) -> Result<i64, AssembleErrorKind> {
+    let mut digits = String::new();
+    while let Some(&c) = chars.peek() {
+        if c.is_digit(radix) {
+            digits.push(c);
+            chars.next();
+        } else if c == '_' {
+            chars.next();
+        } else {
+            break;
+        }
+    }
+    i64::from_str_radix(&digits, radix).map_err(|_| syntax("invalid number"))
+}
+
+fn syntax(message: &str) -> AssembleErrorKind {
+    AssembleErrorKind::Syntax(message.to_string())
+}
+
+#[cfg(test)]
+mod assembler_tests {
+    use super::*;
+    use crate::disassembler::disassemble;
+    use crate::instructions::{ILLEGAL_OPCODES, PREFIX_BYTE};
+
+    fn assemble_bytes(source: &str) -> Vec<u8> {
+        assemble(source).unwrap().image()
+    }
+
+    fn error_of(source: &str) -> AssembleError {
+        assemble(source).unwrap_err()
+    }
+
+    #[test]
+    fn test_assemble_instructions() {
+        let bytes = assemble_bytes(
+            "
+            ld a, $41       ; load
+            add a, b
+            xor $0F
+            sbc a, [hl]
+            ld [hl+], a
+            ldh [$FF44], a
+            ld hl, sp-2
+            bit 7, h
+            jp nz, $0150
+            ",
+        );
+
+        assert_eq!(
+            bytes,
+            vec![
+                0x3E, 0x41, 0x80, 0xEE, 0x0F, 0x9E, 0x22, 0xE0, 0x44, 0xF8, 0xFE, 0xCB, 0x7C, 0xC2,
+                0x50, 0x01,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_assemble_sections_labels_and_data() {
+        let assembly = assemble(
+            r#"
+            SECTION "Header", ROM0[$0100]
+                jp Main
+
+            SECTION "Main", ROM0[$0150]
+            Main:
+                ld hl, Message
+            .loop:
+                dec c
+                jr nz, .loop
+                ret
+            Message:
+                db "Hi", 0
+                dw Main, $1234
+                ds 2, $FF
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(assembly.symbol("Main"), Some(0x0150));
+        assert_eq!(assembly.symbol("Main.loop"), Some(0x0153));
+        assert_eq!(assembly.symbol("Message"), Some(0x0157));
+
+        let sections = assembly.sections();
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].address, 0x0100);
+        assert_eq!(sections[0].bytes, vec![0xC3, 0x50, 0x01]);
+        assert_eq!(sections[1].name, "Main");
+        assert_eq!(
+            sections[1].bytes,
+            vec![
+                0x21, 0x57, 0x01, 0x0D, 0x20, 0xFD, 0xC9, b'H', b'i', 0x00, 0x50, 0x01, 0x34, 0x12,
+                0xFF, 0xFF,
+            ]
+        );
+
+        let image = assembly.image();
+        assert_eq!(image.len(), 0x0150 + 16);
+        assert_eq!(image[0x0100], 0xC3);
+    }
+
+    #[test]
+    fn test_assemble_expressions_and_constants() {
+        let bytes = assemble_bytes(
+            "
+            SIZE EQU 3 * (2 + 4)
+            ld a, SIZE
+            ld a, -1
+            ld a, %1010 | 1 << 4
+            ld a, SIZE % 4
+            ld b, HIGH($C0DE)
+            ld c, LOW($C0DE)
+            ld de, @
+            jr @+2
+            ",
+        );
+
+        assert_eq!(
+            bytes,
+            vec![
+                0x3E, 18, 0x3E, 0xFF, 0x3E, 0x1A, 0x3E, 2, 0x06, 0xC0, 0x0E, 0xDE, 0x11, 0x0C,
+                0x00, 0x18, 0x00,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_assemble_accepts_alternative_spellings() {
+        assert_eq!(
+            assemble_bytes(
+                "ld a,(hli)\nldi (hl),a\nldd a,[hl]\nld a,($FF00+c)\nldh (c),a\njp (hl)"
+            ),
+            vec![0x2A, 0x22, 0x3A, 0xF2, 0xE2, 0xE9]
+        );
+        assert_eq!(
+            assemble_bytes("cp a, 5\nsub a, b\nldh a, ($80)"),
+            vec![0xFE, 5, 0x90, 0xF0, 0x80]
+        );
+    }
+
+    #[test]
+    fn test_assemble_instruction_reads_back_every_displayed_instruction() {
+        for opcode in 0..=0xFFu8 {
+            if ILLEGAL_OPCODES.contains(&opcode) {
+                continue;
+            }
+
+            let mut bytes = vec![opcode, 0x12, 0x34].into_iter();
+            let instruction = Instruction::decode(|| bytes.next().unwrap()).unwrap();
+
+            assert_eq!(
+                assemble_instruction(&instruction.to_string()),
+                Ok(instruction),
+                "{}",
+                instruction
+            );
+        }
+
+        for opcode in 0..=0xFFu8 {
+            let instruction = Instruction::from_byte(opcode, true, || 0).unwrap();
+
+            assert_eq!(
+                assemble_instruction(&instruction.to_string()),
+                Ok(instruction),
+                "{}",
+                instruction
+            );
+        }
+    }
+
+    #[test]
+    fn test_disassembly_listing_assembles_to_the_same_bytes() {
+        let bytes = [
+            0x31,
+            0xFE,
+            0xFF,
+            0xAF,
+            0x21,
+            0x00,
+            0xC0,
+            0x22,
+            0x3C,
+            0xFE,
+            0x10,
+            0x20,
+            0xFA,
+            0xCD,
+            0x60,
+            0x01,
+            0x18,
+            0xFE,
+            PREFIX_BYTE,
+            0x46,
+            0xF0,
+            0x44,
+            0xE8,
+            0x80,
+            0x10,
+            0x00,
+        ];
+
+        let mut source = String::from("SECTION \"Code\", ROM0[$0150]\n");
+        for (_, _, instruction) in disassemble(&bytes, 0x0150) {
+            source += &format!("{}\n", instruction.unwrap());
+        }
+
+        assert_eq!(assemble(&source).unwrap().sections()[0].bytes, bytes);
+    }
+
+    #[test]
+    fn test_assemble_reports_errors_with_line_numbers() {
+        assert_eq!(
+            error_of("nop\nld a, Missing"),
+            AssembleError {
+                line: 2,
+                kind: AssembleErrorKind::UndefinedSymbol("Missing".to_string())
+            }
+        );
+        assert_eq!(
+            error_of("frob a").kind,
+            AssembleErrorKind::UnknownMnemonic("FROB".to_string())
+        );
+        assert_eq!(
+            error_of("ld (bc), b").kind,
+            AssembleErrorKind::InvalidOperands("LD".to_string())
+        );
+        assert_eq!(
+            error_of("ld a, 256").kind,
+            AssembleErrorKind::ValueOutOfRange(256)
+        );
+        assert_eq!(
+            error_of("jr Far\nds 200\nFar:").kind,
+            AssembleErrorKind::ValueOutOfRange(200)
+        );
+        assert_eq!(
+            error_of("rst $09").kind,
+            AssembleErrorKind::ValueOutOfRange(9)
+        );
+        assert_eq!(
+            error_of("Here:\nHere:\n").kind,
+            AssembleErrorKind::DuplicateSymbol("Here".to_string())
+        );
+    }
+
+    #[test]
+    fn test_assemble_rejects_overlapping_sections() {
+        let error = error_of("SECTION \"A\", ROM0[$0100]\nds 4\nSECTION \"B\", ROM0[$0102]\nnop");
+
+        assert_eq!(
+            error,
+            AssembleError {
+                line: 3,
+                kind: AssembleErrorKind::OverlappingSections("A".to_string(), "B".to_string())
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "line 3: sections \"A\" and \"B\" overlap"
+        );
+    }
+}
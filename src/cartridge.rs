@@ -0,0 +1,651 @@
+use std::fmt;
+
+use super::mbc1::Mbc1;
+use super::mbc2::Mbc2;
+use super::mbc3::{Clock, CycleClock, Mbc3, Rtc};
+use super::mbc5::Mbc5;
+use super::memorybus::{EXTERNAL_RAM_START, OPEN_BUS_VALUE};
+
+pub const HEADER_START: usize = 0x0100;
+pub const HEADER_END: usize = 0x014F;
+
+pub const ROM_BANK_SIZE: usize = 0x4000;
+pub const RAM_BANK_SIZE: usize = 0x2000;
+
+const LOGO_START: usize = 0x0104;
+const TITLE_START: usize = 0x0134;
+const MANUFACTURER_CODE_START: usize = 0x013F;
+const CGB_FLAG_ADDRESS: usize = 0x0143;
+const NEW_LICENSEE_CODE_START: usize = 0x0144;
+const SGB_FLAG_ADDRESS: usize = 0x0146;
+const CARTRIDGE_TYPE_ADDRESS: usize = 0x0147;
+const ROM_SIZE_ADDRESS: usize = 0x0148;
+const RAM_SIZE_ADDRESS: usize = 0x0149;
+const DESTINATION_CODE_ADDRESS: usize = 0x014A;
+const OLD_LICENSEE_CODE_ADDRESS: usize = 0x014B;
+const VERSION_ADDRESS: usize = 0x014C;
+const HEADER_CHECKSUM_ADDRESS: usize = 0x014D;
+const GLOBAL_CHECKSUM_ADDRESS: usize = 0x014E;
+
+/// Old licensee code telling that the publisher is given by the new licensee code instead.
+pub const USE_NEW_LICENSEE_CODE: u8 = 0x33;
+
+/// The logo the boot ROM scrolls down the screen, which refuses to start the cartridge unless
+/// the copy in the header matches.
+pub const NINTENDO_LOGO: [u8; 48] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
+];
+
+/// The chip that switches ROM and RAM banks in and out of the address space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mapper {
+    RomOnly,
+    Mbc1,
+    Mbc2,
+    Mmm01,
+    Mbc3,
+    Mbc5,
+    Mbc6,
+    Mbc7,
+    PocketCamera,
+    Tama5,
+    HuC3,
+    HuC1,
+}
+
+/// The cartridge type byte at 0x0147, split into the mapper and the hardware next to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CartridgeType {
+    pub code: u8,
+    pub mapper: Mapper,
+    pub ram: bool,
+    pub battery: bool,
+    pub timer: bool,
+    pub rumble: bool,
+}
+
+impl CartridgeType {
+    /// Decodes a cartridge type byte, `None` for codes no known cartridge uses.
+    pub fn from_code(code: u8) -> Option<CartridgeType> {
+        let (mapper, ram, battery, timer, rumble) = match code {
+            0x00 => (Mapper::RomOnly, false, false, false, false),
+            0x01 => (Mapper::Mbc1, false, false, false, false),
+            0x02 => (Mapper::Mbc1, true, false, false, false),
+            0x03 => (Mapper::Mbc1, true, true, false, false),
+            0x05 => (Mapper::Mbc2, false, false, false, false),
+            0x06 => (Mapper::Mbc2, false, true, false, false),
+            0x08 => (Mapper::RomOnly, true, false, false, false),
+            0x09 => (Mapper::RomOnly, true, true, false, false),
+            0x0B => (Mapper::Mmm01, false, false, false, false),
+            0x0C => (Mapper::Mmm01, true, false, false, false),
+            0x0D => (Mapper::Mmm01, true, true, false, false),
+            0x0F => (Mapper::Mbc3, false, true, true, false),
+            0x10 => (Mapper::Mbc3, true, true, true, false),
+            0x11 => (Mapper::Mbc3, false, false, false, false),
+            0x12 => (Mapper::Mbc3, true, false, false, false),
+            0x13 => (Mapper::Mbc3, true, true, false, false),
+            0x19 => (Mapper::Mbc5, false, false, false, false),
+            0x1A => (Mapper::Mbc5, true, false, false, false),
+            0x1B => (Mapper::Mbc5, true, true, false, false),
+            0x1C => (Mapper::Mbc5, false, false, false, true),
+            0x1D => (Mapper::Mbc5, true, false, false, true),
+            0x1E => (Mapper::Mbc5, true, true, false, true),
+            0x20 => (Mapper::Mbc6, false, false, false, false),
+            0x22 => (Mapper::Mbc7, true, true, false, true),
+            0xFC => (Mapper::PocketCamera, false, false, false, false),
+            0xFD => (Mapper::Tama5, false, false, false, false),
+            0xFE => (Mapper::HuC3, false, false, false, false),
+            0xFF => (Mapper::HuC1, true, true, false, false),
+            _ => return None,
+        };
+
+        Some(CartridgeType {
+            code,
+            mapper,
+            ram,
+            battery,
+            timer,
+            rumble,
+        })
+    }
+}
+
+/// Byte 0x0143, which the CGB uses to decide whether to run the cartridge in colour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CgbSupport {
+    /// Made for the DMG, where 0x0143 is the last character of the title.
+    None,
+    /// 0x80, runs on both.
+    Compatible,
+    /// 0xC0, runs on the CGB only.
+    Only,
+}
+
+/// The cartridge header at 0x0100-0x014F.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CartridgeHeader {
+    /// Usually a NOP and a jump to the actual start of the program.
+    pub entry_point: [u8; 4],
+    pub logo: [u8; 48],
+    /// Upper case ASCII padded with zeros, which are not included here.
+    pub title: String,
+    /// Only set in newer cartridges, where it takes the last four characters of the title.
+    pub manufacturer_code: [u8; 4],
+    pub cgb_support: CgbSupport,
+    /// Two ASCII characters naming the publisher when the old licensee code is 0x33.
+    pub new_licensee_code: [u8; 2],
+    pub sgb_support: bool,
+    pub cartridge_type: CartridgeType,
+    pub rom_size_code: u8,
+    pub ram_size_code: u8,
+    /// 0x00 for Japan, 0x01 for everywhere else.
+    pub destination_code: u8,
+    pub old_licensee_code: u8,
+    pub version: u8,
+    pub header_checksum: u8,
+    /// Sum of every byte in the ROM but the checksum itself. Nothing checks it on hardware.
+    pub global_checksum: u16,
+}
+
+impl CartridgeHeader {
+    /// Parses the header of `rom` and checks it against the rest of the image.
+    pub fn parse(rom: &[u8]) -> Result<CartridgeHeader, LoadError> {
+        if rom.len() <= HEADER_END {
+            return Err(LoadError::Truncated { length: rom.len() });
+        }
+
+        let computed = header_checksum(rom);
+        let header_checksum = rom[HEADER_CHECKSUM_ADDRESS];
+        if computed != header_checksum {
+            return Err(LoadError::BadHeaderChecksum {
+                expected: header_checksum,
+                computed,
+            });
+        }
+
+        let cartridge_type = rom[CARTRIDGE_TYPE_ADDRESS];
+        let cartridge_type = CartridgeType::from_code(cartridge_type)
+            .ok_or(LoadError::UnknownMapper(cartridge_type))?;
+
+        let cgb_support = match rom[CGB_FLAG_ADDRESS] {
+            0xC0 => CgbSupport::Only,
+            flag if flag & 0x80 != 0 => CgbSupport::Compatible,
+            _ => CgbSupport::None,
+        };
+        let title_end = match cgb_support {
+            CgbSupport::None => CGB_FLAG_ADDRESS + 1,
+            _ => CGB_FLAG_ADDRESS,
+        };
+        let title = rom[TITLE_START..title_end]
+            .iter()
+            .take_while(|&&byte| byte != 0)
+            .map(|&byte| byte as char)
+            .collect();
+
+        let header = CartridgeHeader {
+            entry_point: bytes(rom, HEADER_START),
+            logo: bytes(rom, LOGO_START),
+            title,
+            manufacturer_code: bytes(rom, MANUFACTURER_CODE_START),
+            cgb_support,
+            new_licensee_code: bytes(rom, NEW_LICENSEE_CODE_START),
+            sgb_support: rom[SGB_FLAG_ADDRESS] == 0x03,
+            cartridge_type,
+            rom_size_code: rom[ROM_SIZE_ADDRESS],
+            ram_size_code: rom[RAM_SIZE_ADDRESS],
+            destination_code: rom[DESTINATION_CODE_ADDRESS],
+            old_licensee_code: rom[OLD_LICENSEE_CODE_ADDRESS],
+            version: rom[VERSION_ADDRESS],
+            header_checksum,
+            global_checksum: u16::from_be_bytes(bytes(rom, GLOBAL_CHECKSUM_ADDRESS)),
+        };
+
+        let rom_size = header
+            .rom_size()
+            .ok_or(LoadError::UnknownRomSize(header.rom_size_code))?;
+        header
+            .ram_size()
+            .ok_or(LoadError::UnknownRamSize(header.ram_size_code))?;
+        if rom.len() != rom_size {
+            return Err(LoadError::SizeMismatch {
+                header: rom_size,
+                actual: rom.len(),
+            });
+        }
+
+        Ok(header)
+    }
+
+    /// ROM size in bytes, 32 KiB doubled `rom_size_code` times.
+    pub fn rom_size(&self) -> Option<usize> {
+        match self.rom_size_code {
+            0x00..=0x08 => Some(0x8000 << self.rom_size_code),
+            _ => None,
+        }
+    }
+
+    /// Size in bytes of the RAM on the cartridge. RAM built into the mapper, as on MBC2, is
+    /// not counted.
+    pub fn ram_size(&self) -> Option<usize> {
+        match self.ram_size_code {
+            0x00 => Some(0),
+            // Listed as unused, but a few homebrew images put 2 KiB here.
+            0x01 => Some(0x800),
+            0x02 => Some(0x2000),
+            0x03 => Some(0x8000),
+            0x04 => Some(0x2_0000),
+            0x05 => Some(0x1_0000),
+            _ => None,
+        }
+    }
+
+    pub fn has_valid_logo(&self) -> bool {
+        self.logo == NINTENDO_LOGO
+    }
+
+    /// Whether `global_checksum` matches the sum of `rom`.
+    pub fn global_checksum_matches(&self, rom: &[u8]) -> bool {
+        global_checksum(rom) == self.global_checksum
+    }
+}
+
+/// ROM and RAM of a cartridge, as its memory bank controller maps them to 0x0000-0x7FFF and
+/// 0xA000-0xBFFF. Writes to the ROM range go to the controller's registers.
+pub enum Cartridge {
+    RomOnly(RomOnly),
+    Mbc1(Mbc1),
+    Mbc2(Mbc2),
+    Mbc3(Mbc3),
+    Mbc5(Mbc5),
+}
+
+impl Cartridge {
+    /// Wires `rom` to the controller its header asks for. A real time clock on the cartridge
+    /// counts emulated cycles.
+    pub fn new(rom: Vec<u8>, header: &CartridgeHeader) -> Result<Cartridge, LoadError> {
+        Cartridge::with_clock(rom, header, Box::new(CycleClock::default()))
+    }
+
+    /// Like `new`, with a real time clock on the cartridge driven by `clock`. The clock is
+    /// dropped if the cartridge has none.
+    pub fn with_clock(
+        rom: Vec<u8>,
+        header: &CartridgeHeader,
+        clock: Box<dyn Clock>,
+    ) -> Result<Cartridge, LoadError> {
+        let ram_size = header
+            .ram_size()
+            .ok_or(LoadError::UnknownRamSize(header.ram_size_code))?;
+
+        match header.cartridge_type.mapper {
+            Mapper::RomOnly => Ok(Cartridge::RomOnly(RomOnly::new(rom, ram_size))),
+            Mapper::Mbc1 => Ok(Cartridge::Mbc1(Mbc1::new(rom, ram_size))),
+            // The RAM of the MBC2 is inside the controller and not counted in the header.
+            Mapper::Mbc2 => Ok(Cartridge::Mbc2(Mbc2::new(rom))),
+            Mapper::Mbc3 => {
+                let rtc = if header.cartridge_type.timer {
+                    Some(Rtc::with_clock(clock))
+                } else {
+                    None
+                };
+                Ok(Cartridge::Mbc3(Mbc3::new(rom, ram_size, rtc)))
+            }
+            Mapper::Mbc5 => Ok(Cartridge::Mbc5(Mbc5::new(
+                rom,
+                ram_size,
+                header.cartridge_type.rumble,
+            ))),
+            mapper => Err(LoadError::UnsupportedMapper(mapper)),
+        }
+    }
+
+    pub fn read_rom(&self, address: u16) -> u8 {
+        match self {
+            Cartridge::RomOnly(cartridge) => cartridge.read_rom(address),
+            Cartridge::Mbc1(mbc) => mbc.read_rom(address),
+            Cartridge::Mbc2(mbc) => mbc.read_rom(address),
+            Cartridge::Mbc3(mbc) => mbc.read_rom(address),
+            Cartridge::Mbc5(mbc) => mbc.read_rom(address),
+        }
+    }
+
+    pub fn write_rom(&mut self, address: u16, value: u8) {
+        match self {
+            // Without a memory bank controller the ROM is read only.
+            Cartridge::RomOnly(_) => {}
+            Cartridge::Mbc1(mbc) => mbc.write_rom(address, value),
+            Cartridge::Mbc2(mbc) => mbc.write_rom(address, value),
+            Cartridge::Mbc3(mbc) => mbc.write_rom(address, value),
+            Cartridge::Mbc5(mbc) => mbc.write_rom(address, value),
+        }
+    }
+
+    pub fn read_ram(&self, address: u16) -> u8 {
+        match self {
+            Cartridge::RomOnly(cartridge) => cartridge.read_ram(address),
+            Cartridge::Mbc1(mbc) => mbc.read_ram(address),
+            Cartridge::Mbc2(mbc) => mbc.read_ram(address),
+            Cartridge::Mbc3(mbc) => mbc.read_ram(address),
+            Cartridge::Mbc5(mbc) => mbc.read_ram(address),
+        }
+    }
+
+    pub fn write_ram(&mut self, address: u16, value: u8) {
+        match self {
+            Cartridge::RomOnly(cartridge) => cartridge.write_ram(address, value),
+            Cartridge::Mbc1(mbc) => mbc.write_ram(address, value),
+            Cartridge::Mbc2(mbc) => mbc.write_ram(address, value),
+            Cartridge::Mbc3(mbc) => mbc.write_ram(address, value),
+            Cartridge::Mbc5(mbc) => mbc.write_ram(address, value),
+        }
+    }
+
+    /// Advances clocks on the cartridge by one machine cycle.
+    pub fn tick(&mut self) {
+        if let Cartridge::Mbc3(mbc) = self {
+            mbc.tick();
+        }
+    }
+
+    /// The state the rumble motor was switched to, if it was switched since the last call.
+    pub fn take_rumble_change(&mut self) -> Option<bool> {
+        match self {
+            Cartridge::Mbc5(mbc) => mbc.take_motor_change(),
+            _ => None,
+        }
+    }
+}
+
+/// Up to 32 KiB of ROM wired straight to the address bus, with optional RAM.
+pub struct RomOnly {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+}
+
+impl RomOnly {
+    pub fn new(rom: Vec<u8>, ram_size: usize) -> Self {
+        RomOnly {
+            rom,
+            ram: vec![0; ram_size],
+        }
+    }
+
+    pub fn read_rom(&self, address: u16) -> u8 {
+        self.rom
+            .get(address as usize)
+            .copied()
+            .unwrap_or(OPEN_BUS_VALUE)
+    }
+
+    pub fn read_ram(&self, address: u16) -> u8 {
+        self.ram
+            .get((address - EXTERNAL_RAM_START) as usize)
+            .copied()
+            .unwrap_or(OPEN_BUS_VALUE)
+    }
+
+    pub fn write_ram(&mut self, address: u16, value: u8) {
+        if let Some(byte) = self.ram.get_mut((address - EXTERNAL_RAM_START) as usize) {
+            *byte = value;
+        }
+    }
+}
+
+/// Offset into a ROM of `rom_len` bytes of `address` as it reads with `bank` mapped to its
+/// half of 0x0000-0x7FFF. Address lines above the size of the ROM are not connected, so banks
+/// past the end wrap.
+pub(crate) fn rom_offset(rom_len: usize, bank: usize, address: u16) -> usize {
+    let banks = (rom_len / ROM_BANK_SIZE).max(1);
+    bank % banks * ROM_BANK_SIZE + address as usize % ROM_BANK_SIZE
+}
+
+/// A ROM of `banks` banks for testing controllers. Each bank is filled with the lower byte of
+/// its number, except for its first two bytes, which hold the whole number as a little endian
+/// word.
+#[cfg(test)]
+pub(crate) fn numbered_rom(banks: usize) -> Vec<u8> {
+    (0..banks)
+        .flat_map(|bank| {
+            let mut data = vec![bank as u8; ROM_BANK_SIZE];
+            data[..2].copy_from_slice(&(bank as u16).to_le_bytes());
+            data
+        })
+        .collect()
+}
+
+/// Checksum of the bytes at 0x0134-0x014C, which the boot ROM compares against 0x014D.
+///
+/// # Panics
+///
+/// If `rom` is shorter than 0x014D bytes. `CartridgeHeader::parse` rejects such images with
+/// `LoadError::Truncated` instead.
+pub fn header_checksum(rom: &[u8]) -> u8 {
+    rom[TITLE_START..HEADER_CHECKSUM_ADDRESS]
+        .iter()
+        .fold(0u8, |checksum, &byte| {
+            checksum.wrapping_sub(byte).wrapping_sub(1)
+        })
+}
+
+/// Sum of every byte of `rom` except the two of the global checksum.
+pub fn global_checksum(rom: &[u8]) -> u16 {
+    rom.iter()
+        .enumerate()
+        .filter(|&(address, _)| {
+            address != GLOBAL_CHECKSUM_ADDRESS && address != GLOBAL_CHECKSUM_ADDRESS + 1
+        })
+        .fold(0u16, |checksum, (_, &byte)| {
+            checksum.wrapping_add(byte as u16)
+        })
+}
+
+fn bytes<const N: usize>(rom: &[u8], start: usize) -> [u8; N] {
+    let mut bytes = [0; N];
+    bytes.copy_from_slice(&rom[start..start + N]);
+    bytes
+}
+
+/// Why a ROM image was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadError {
+    /// The image ends before the header does.
+    Truncated {
+        length: usize,
+    },
+    BadHeaderChecksum {
+        expected: u8,
+        computed: u8,
+    },
+    /// The cartridge type byte is not one any known cartridge uses.
+    UnknownMapper(u8),
+    /// A known memory bank controller that is not emulated.
+    UnsupportedMapper(Mapper),
+    UnknownRomSize(u8),
+    UnknownRamSize(u8),
+    /// The image is not as long as the ROM size in the header says.
+    SizeMismatch {
+        header: usize,
+        actual: usize,
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LoadError::Truncated { length } => {
+                write!(f, "ROM of {} bytes ends before its header", length)
+            }
+            LoadError::BadHeaderChecksum { expected, computed } => write!(
+                f,
+                "header checksum is {:#04x} but the header sums to {:#04x}",
+                expected, computed
+            ),
+            LoadError::UnknownMapper(code) => write!(f, "unknown cartridge type {:#04x}", code),
+            LoadError::UnsupportedMapper(mapper) => write!(f, "{:?} is not supported", mapper),
+            LoadError::UnknownRomSize(code) => write!(f, "unknown ROM size code {:#04x}", code),
+            LoadError::UnknownRamSize(code) => write!(f, "unknown RAM size code {:#04x}", code),
+            LoadError::SizeMismatch { header, actual } => write!(
+                f,
+                "header gives a ROM size of {} bytes but the image has {}",
+                header, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+#[cfg(test)]
+mod cartridge_tests {
+    use super::*;
+
+    /// An image of `rom_size_code` size whose header has a correct checksum.
+    fn rom_with_header(cartridge_type: u8, rom_size_code: u8, ram_size_code: u8) -> Vec<u8> {
+        let mut rom = vec![0; 0x8000 << rom_size_code];
+        rom[HEADER_START..HEADER_START + 4].copy_from_slice(&[0x00, 0xC3, 0x50, 0x01]);
+        rom[LOGO_START..LOGO_START + 48].copy_from_slice(&NINTENDO_LOGO);
+        rom[TITLE_START..TITLE_START + 6].copy_from_slice(b"TETRIS");
+        rom[CARTRIDGE_TYPE_ADDRESS] = cartridge_type;
+        rom[ROM_SIZE_ADDRESS] = rom_size_code;
+        rom[RAM_SIZE_ADDRESS] = ram_size_code;
+        rom[DESTINATION_CODE_ADDRESS] = 0x01;
+        rom[OLD_LICENSEE_CODE_ADDRESS] = 0x01;
+        rom[VERSION_ADDRESS] = 0x01;
+        rom[HEADER_CHECKSUM_ADDRESS] = header_checksum(&rom);
+        rom
+    }
+
+    #[test]
+    fn test_parse_reads_every_field() {
+        let mut rom = rom_with_header(0x03, 0x02, 0x03);
+        rom[0x4000] = 0xAB;
+        let [high, low] = global_checksum(&rom).to_be_bytes();
+        rom[GLOBAL_CHECKSUM_ADDRESS] = high;
+        rom[GLOBAL_CHECKSUM_ADDRESS + 1] = low;
+
+        let header = CartridgeHeader::parse(&rom).unwrap();
+
+        assert_eq!(header.entry_point, [0x00, 0xC3, 0x50, 0x01]);
+        assert_eq!(header.has_valid_logo(), true);
+        assert_eq!(header.title, "TETRIS");
+        assert_eq!(header.cgb_support, CgbSupport::None);
+        assert_eq!(header.sgb_support, false);
+        assert_eq!(
+            header.cartridge_type,
+            CartridgeType {
+                code: 0x03,
+                mapper: Mapper::Mbc1,
+                ram: true,
+                battery: true,
+                timer: false,
+                rumble: false,
+            }
+        );
+        assert_eq!(header.rom_size(), Some(0x2_0000));
+        assert_eq!(header.ram_size(), Some(0x8000));
+        assert_eq!(header.destination_code, 0x01);
+        assert_eq!(header.old_licensee_code, 0x01);
+        assert_eq!(header.version, 0x01);
+        assert_eq!(header.global_checksum_matches(&rom), true);
+    }
+
+    #[test]
+    fn test_parse_splits_title_of_cgb_cartridges() {
+        let mut rom = rom_with_header(0x1B, 0x00, 0x00);
+        rom[TITLE_START..CGB_FLAG_ADDRESS].copy_from_slice(b"ZELDA\0\0\0\0\0\0AZLE");
+        rom[CGB_FLAG_ADDRESS] = 0x80;
+        rom[NEW_LICENSEE_CODE_START..NEW_LICENSEE_CODE_START + 2].copy_from_slice(b"01");
+        rom[SGB_FLAG_ADDRESS] = 0x03;
+        rom[OLD_LICENSEE_CODE_ADDRESS] = USE_NEW_LICENSEE_CODE;
+        rom[HEADER_CHECKSUM_ADDRESS] = header_checksum(&rom);
+
+        let header = CartridgeHeader::parse(&rom).unwrap();
+
+        assert_eq!(header.title, "ZELDA");
+        assert_eq!(&header.manufacturer_code, b"AZLE");
+        assert_eq!(header.cgb_support, CgbSupport::Compatible);
+        assert_eq!(&header.new_licensee_code, b"01");
+        assert_eq!(header.sgb_support, true);
+        assert_eq!(header.cartridge_type.mapper, Mapper::Mbc5);
+    }
+
+    #[test]
+    fn test_parse_rejects_truncated_image() {
+        let rom = rom_with_header(0x00, 0x00, 0x00);
+
+        assert_eq!(
+            CartridgeHeader::parse(&rom[..0x014F]),
+            Err(LoadError::Truncated { length: 0x014F })
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_header_checksum_panics_on_truncated_image() {
+        let rom = rom_with_header(0x00, 0x00, 0x00);
+
+        header_checksum(&rom[..HEADER_CHECKSUM_ADDRESS - 1]);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_header_checksum() {
+        let mut rom = rom_with_header(0x00, 0x00, 0x00);
+        let checksum = rom[HEADER_CHECKSUM_ADDRESS];
+        rom[VERSION_ADDRESS] = 0x02;
+
+        assert_eq!(
+            CartridgeHeader::parse(&rom),
+            Err(LoadError::BadHeaderChecksum {
+                expected: checksum,
+                computed: checksum.wrapping_sub(1),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_codes() {
+        assert_eq!(
+            CartridgeHeader::parse(&rom_with_header(0x04, 0x00, 0x00)),
+            Err(LoadError::UnknownMapper(0x04))
+        );
+        assert_eq!(
+            CartridgeHeader::parse(&rom_with_header(0x00, 0x00, 0x06)),
+            Err(LoadError::UnknownRamSize(0x06))
+        );
+
+        let mut rom = rom_with_header(0x00, 0x00, 0x00);
+        rom[ROM_SIZE_ADDRESS] = 0x52;
+        rom[HEADER_CHECKSUM_ADDRESS] = header_checksum(&rom);
+        assert_eq!(
+            CartridgeHeader::parse(&rom),
+            Err(LoadError::UnknownRomSize(0x52))
+        );
+    }
+
+    #[test]
+    fn test_cartridge_rejects_unsupported_mapper() {
+        let rom = rom_with_header(0x0B, 0x00, 0x00);
+        let header = CartridgeHeader::parse(&rom).unwrap();
+
+        assert_eq!(
+            Cartridge::new(rom, &header).err(),
+            Some(LoadError::UnsupportedMapper(Mapper::Mmm01))
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_size_mismatch() {
+        let mut rom = rom_with_header(0x01, 0x01, 0x00);
+        rom.truncate(0x8000);
+
+        assert_eq!(
+            CartridgeHeader::parse(&rom),
+            Err(LoadError::SizeMismatch {
+                header: 0x1_0000,
+                actual: 0x8000,
+            })
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use super::instructions::{DecodeError, Instruction};
+
+/// Walks `bytes` as if they were mapped at `start`, decoding one instruction after another.
+/// Illegal opcodes come out as errors one byte long. An instruction cut short by the end of
+/// `bytes` ends the walk.
+pub fn disassemble(bytes: &[u8], start: u16) -> Disassembler<'_> {
+    Disassembler {
+        bytes,
+        offset: 0,
+        start,
+    }
+}
+
+pub struct Disassembler<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    start: u16,
+}
+
+impl<'a> Iterator for Disassembler<'a> {
+    /// The address of the instruction, the bytes it was decoded from and the instruction.
+    type Item = (u16, &'a [u8], Result<Instruction, DecodeError>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let remaining = &self.bytes[self.offset..];
+        if remaining.is_empty() {
+            return None;
+        }
+
+        let mut length = 0;
+        let mut truncated = false;
+        let result = Instruction::decode(|| match remaining.get(length) {
+            Some(&byte) => {
+                length += 1;
+                byte
+            }
+            None => {
+                truncated = true;
+                0x00
+            }
+        });
+        if truncated {
+            self.offset = self.bytes.len();
+            return None;
+        }
+
+        let address = self.start.wrapping_add(self.offset as u16);
+        self.offset += length;
+        Some((address, &remaining[..length], result))
+    }
+}
+
+#[cfg(test)]
+mod disassembler_tests {
+    use super::*;
+    use crate::instructions::{ArithmeticRegisters, JumpCondition};
+
+    #[test]
+    fn test_disassemble_yields_addresses_bytes_and_instructions() {
+        let bytes = [0x00, 0x3E, 0x0F, 0xCB, 0x37, 0xC3, 0x50, 0x01];
+
+        let lines: Vec<_> = disassemble(&bytes, 0x0150).collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                (0x0150, &bytes[0..1], Ok(Instruction::NOP())),
+                (
+                    0x0151,
+                    &bytes[1..3],
+                    Ok(Instruction::LDI(ArithmeticRegisters::A, 0x0F))
+                ),
+                (
+                    0x0153,
+                    &bytes[3..5],
+                    Ok(Instruction::SWAP(ArithmeticRegisters::A))
+                ),
+                (
+                    0x0155,
+                    &bytes[5..8],
+                    Ok(Instruction::JP(JumpCondition::Always, 0x0150))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_disassemble_reports_illegal_opcodes_and_carries_on() {
+        let bytes = [0xDD, 0x3C];
+
+        let lines: Vec<_> = disassemble(&bytes, 0x0000).collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                (0x0000, &bytes[0..1], Err(DecodeError::IllegalOpcode(0xDD))),
+                (
+                    0x0001,
+                    &bytes[1..2],
+                    Ok(Instruction::INC(ArithmeticRegisters::A))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_disassemble_stops_at_truncated_instruction() {
+        let lines: Vec<_> = disassemble(&[0x00, 0xC3, 0x50], 0x0000).collect();
+
+        assert_eq!(lines.len(), 1);
+    }
+
+    #[test]
+    fn test_disassemble_formats_listing() {
+        let bytes = [0x88, 0xEE, 0x0F, 0x9E, 0xE0, 0x44, 0x20, 0xFA];
+
+        let listing: Vec<_> = disassemble(&bytes, 0x4000)
+            .map(|(address, _, instruction)| format!("{:04X} {}", address, instruction.unwrap()))
+            .collect();
+
+        assert_eq!(
+            listing,
+            vec![
+                "4000 ADC A,B",
+                "4001 XOR $0F",
+                "4003 SBC A,(HL)",
+                "4004 LDH ($FF44),A",
+                "4006 JR NZ,@-4",
+            ]
+        );
+    }
+}
@@ -0,0 +1,782 @@
+use super::instructions::{
+    decode_condition, decode_destination, decode_operand, decode_stack_register,
+    decode_wide_register, ArithmeticRegisters, DecodeError, Indirect, JumpCondition, PREFIX_BYTE,
+};
+use super::CPU;
+
+/// Runs the instruction of one opcode, fetching its immediate operands as it goes. The opcode
+/// is passed along so that one handler serves every register an opcode group encodes.
+pub type Handler = fn(&mut CPU, u8) -> Result<(), DecodeError>;
+
+/// Handlers of the unprefixed page, indexed by opcode.
+pub static UNPREFIXED_HANDLERS: [Handler; 256] = unprefixed_handlers();
+
+/// Handlers of the opcodes that follow the CB prefix.
+pub static PREFIXED_HANDLERS: [Handler; 256] = prefixed_handlers();
+
+const fn unprefixed_handlers() -> [Handler; 256] {
+    let mut handlers = [illegal as Handler; 256];
+    let mut opcode = 0;
+    while opcode < 256 {
+        handlers[opcode] = unprefixed_handler(opcode as u8);
+        opcode += 1;
+    }
+    handlers
+}
+
+const fn prefixed_handlers() -> [Handler; 256] {
+    let mut handlers = [illegal as Handler; 256];
+    let mut opcode = 0;
+    while opcode < 256 {
+        handlers[opcode] = prefixed_handler(opcode as u8);
+        opcode += 1;
+    }
+    handlers
+}
+
+/// Same grouping of opcodes as `Instruction::decode`, with bits 0 to 2 or 3 to 5 equal to 6
+/// standing for (HL).
+const fn unprefixed_handler(opcode: u8) -> Handler {
+    let hl_operand = opcode & 0b111 == 6;
+    let hl_destination = (opcode >> 3) & 0b111 == 6;
+
+    match opcode {
+        0x00 => nop,
+        0x10 => stop,
+        0x76 => halt,
+        0xF3 => di,
+        0xFB => ei,
+        PREFIX_BYTE => prefix,
+
+        0x01 | 0x11 | 0x21 | 0x31 => ld_wide_immediate,
+        0x02 | 0x12 | 0x22 | 0x32 => st_a_indirect,
+        0x0A | 0x1A | 0x2A | 0x3A => ld_a_indirect,
+        0x03 | 0x13 | 0x23 | 0x33 => inc_wide,
+        0x0B | 0x1B | 0x2B | 0x3B => dec_wide,
+        0x09 | 0x19 | 0x29 | 0x39 => add_hl,
+        0x08 => st_sp,
+
+        0x34 => inc_relative,
+        0x35 => dec_relative,
+        0x36 => st_immediate,
+        0x04 | 0x0C | 0x14 | 0x1C | 0x24 | 0x2C | 0x3C => inc_reg,
+        0x05 | 0x0D | 0x15 | 0x1D | 0x25 | 0x2D | 0x3D => dec_reg,
+        0x06 | 0x0E | 0x16 | 0x1E | 0x26 | 0x2E | 0x3E => ld_immediate,
+
+        0x07 => rlca,
+        0x0F => rrca,
+        0x17 => rla,
+        0x1F => rra,
+        0x27 => daa,
+        0x2F => cpl,
+        0x37 => scf,
+        0x3F => ccf,
+
+        0x18 => jr,
+        0x20 | 0x28 | 0x30 | 0x38 => jr_conditional,
+
+        0x40..=0x7F if hl_operand => ld_relative,
+        0x40..=0x7F if hl_destination => st_reg,
+        0x40..=0x7F => ld_reg,
+
+        0x80..=0x87 if hl_operand => add_relative,
+        0x80..=0x87 => add_reg,
+        0x88..=0x8F if hl_operand => adc_relative,
+        0x88..=0x8F => adc_reg,
+        0x90..=0x97 if hl_operand => sub_relative,
+        0x90..=0x97 => sub_reg,
+        0x98..=0x9F if hl_operand => sbc_relative,
+        0x98..=0x9F => sbc_reg,
+        0xA0..=0xA7 if hl_operand => and_relative,
+        0xA0..=0xA7 => and_reg,
+        0xA8..=0xAF if hl_operand => xor_relative,
+        0xA8..=0xAF => xor_reg,
+        0xB0..=0xB7 if hl_operand => or_relative,
+        0xB0..=0xB7 => or_reg,
+        0xB8..=0xBF if hl_operand => cp_relative,
+        0xB8..=0xBF => cp_reg,
+
+        0xC6 => add_immediate,
+        0xCE => adc_immediate,
+        0xD6 => sub_immediate,
+        0xDE => sbc_immediate,
+        0xE6 => and_immediate,
+        0xEE => xor_immediate,
+        0xF6 => or_immediate,
+        0xFE => cp_immediate,
+
+        0xC0 | 0xC8 | 0xD0 | 0xD8 => ret_conditional,
+        0xC9 => ret,
+        0xD9 => reti,
+        0xC2 | 0xCA | 0xD2 | 0xDA => jp_conditional,
+        0xC3 => jp,
+        0xE9 => jp_hl,
+        0xC4 | 0xCC | 0xD4 | 0xDC => call_conditional,
+        0xCD => call,
+        0xC7 | 0xCF | 0xD7 | 0xDF | 0xE7 | 0xEF | 0xF7 | 0xFF => rst,
+
+        0xC1 | 0xD1 | 0xE1 | 0xF1 => pop,
+        0xC5 | 0xD5 | 0xE5 | 0xF5 => push,
+
+        0xE0 => st_a_high_immediate,
+        0xF0 => ld_a_high_immediate,
+        0xE2 => st_a_high_c,
+        0xF2 => ld_a_high_c,
+        0xEA => st_a_absolute,
+        0xFA => ld_a_absolute,
+
+        0xE8 => add_sp,
+        0xF8 => ld_hl_sp,
+        0xF9 => ld_sp_hl,
+
+        _ => illegal,
+    }
+}
+
+const fn prefixed_handler(opcode: u8) -> Handler {
+    let hl_operand = opcode & 0b111 == 6;
+
+    match opcode {
+        0x00..=0x07 if hl_operand => rlc_relative,
+        0x00..=0x07 => rlc_reg,
+        0x08..=0x0F if hl_operand => rrc_relative,
+        0x08..=0x0F => rrc_reg,
+        0x10..=0x17 if hl_operand => rl_relative,
+        0x10..=0x17 => rl_reg,
+        0x18..=0x1F if hl_operand => rr_relative,
+        0x18..=0x1F => rr_reg,
+        0x20..=0x27 if hl_operand => sla_relative,
+        0x20..=0x27 => sla_reg,
+        0x28..=0x2F if hl_operand => sra_relative,
+        0x28..=0x2F => sra_reg,
+        0x30..=0x37 if hl_operand => swap_relative,
+        0x30..=0x37 => swap_reg,
+        0x38..=0x3F if hl_operand => srl_relative,
+        0x38..=0x3F => srl_reg,
+        0x40..=0x7F if hl_operand => bit_relative,
+        0x40..=0x7F => bit_reg,
+        0x80..=0xBF if hl_operand => res_relative,
+        0x80..=0xBF => res_reg,
+        _ if hl_operand => set_relative,
+        _ => set_reg,
+    }
+}
+
+/// Register in bits 0 to 2 of an opcode whose handler is only used when they are not (HL).
+fn operand(opcode: u8) -> ArithmeticRegisters {
+    decode_operand(opcode).unwrap()
+}
+
+/// Register in bits 3 to 5 of an opcode whose handler is only used when they are not (HL).
+fn destination(opcode: u8) -> ArithmeticRegisters {
+    decode_destination(opcode).unwrap()
+}
+
+fn bit(opcode: u8) -> u8 {
+    (opcode >> 3) & 0b111
+}
+
+/// (BC), (DE), (HL+) or (HL-), encoded in bits 4 and 5.
+fn indirect(opcode: u8) -> Indirect {
+    match (opcode >> 4) & 0b11 {
+        0 => Indirect::BC,
+        1 => Indirect::DE,
+        2 => Indirect::HLIncrement,
+        _ => Indirect::HLDecrement,
+    }
+}
+
+fn fetch_word(cpu: &mut CPU) -> u16 {
+    let low = cpu.fetch_byte() as u16;
+    let high = cpu.fetch_byte() as u16;
+    (high << 8) | low
+}
+
+fn illegal(_: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    Err(DecodeError::IllegalOpcode(opcode))
+}
+
+fn prefix(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    let opcode = cpu.fetch_byte();
+    PREFIXED_HANDLERS[opcode as usize](cpu, opcode)
+}
+
+fn nop(_: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    Ok(())
+}
+
+fn stop(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    // STOP is followed by a padding byte that is skipped along with it.
+    cpu.fetch_byte();
+    Ok(())
+}
+
+fn halt(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_halt();
+    Ok(())
+}
+
+fn di(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_di();
+    Ok(())
+}
+
+fn ei(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_ei();
+    Ok(())
+}
+
+fn ld_wide_immediate(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    let immediate = fetch_word(cpu);
+    cpu.execute_ldw_immediate(decode_wide_register(opcode), immediate);
+    Ok(())
+}
+
+fn st_a_indirect(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_sta(indirect(opcode));
+    Ok(())
+}
+
+fn ld_a_indirect(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_lda(indirect(opcode));
+    Ok(())
+}
+
+fn inc_wide(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_incw_reg(decode_wide_register(opcode));
+    Ok(())
+}
+
+fn dec_wide(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_decw_reg(decode_wide_register(opcode));
+    Ok(())
+}
+
+fn add_hl(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_addhl_reg(decode_wide_register(opcode));
+    Ok(())
+}
+
+fn st_sp(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    let address = fetch_word(cpu);
+    cpu.execute_stsp(address);
+    Ok(())
+}
+
+fn inc_reg(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_inc_reg(destination(opcode));
+    Ok(())
+}
+
+fn inc_relative(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_inc_relative();
+    Ok(())
+}
+
+fn dec_reg(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_dec_reg(destination(opcode));
+    Ok(())
+}
+
+fn dec_relative(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_dec_relative();
+    Ok(())
+}
+
+fn ld_immediate(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    let immediate = cpu.fetch_byte();
+    cpu.execute_ld_immediate(destination(opcode), immediate);
+    Ok(())
+}
+
+fn st_immediate(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    let immediate = cpu.fetch_byte();
+    cpu.execute_st_immediate(immediate);
+    Ok(())
+}
+
+fn rlca(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_rlca();
+    Ok(())
+}
+
+fn rrca(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_rrca();
+    Ok(())
+}
+
+fn rla(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_rla();
+    Ok(())
+}
+
+fn rra(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_rra();
+    Ok(())
+}
+
+fn daa(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_daa();
+    Ok(())
+}
+
+fn cpl(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_cpl();
+    Ok(())
+}
+
+fn scf(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_scf();
+    Ok(())
+}
+
+fn ccf(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_ccf();
+    Ok(())
+}
+
+fn jr(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    let offset = cpu.fetch_byte() as i8;
+    cpu.execute_jr(JumpCondition::Always, offset);
+    Ok(())
+}
+
+fn jr_conditional(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    let offset = cpu.fetch_byte() as i8;
+    cpu.execute_jr(decode_condition(opcode), offset);
+    Ok(())
+}
+
+fn ld_reg(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_ld_reg(destination(opcode), operand(opcode));
+    Ok(())
+}
+
+fn ld_relative(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_ld_relative(destination(opcode));
+    Ok(())
+}
+
+fn st_reg(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_st_reg(operand(opcode));
+    Ok(())
+}
+
+fn add_reg(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_add_reg(operand(opcode));
+    Ok(())
+}
+
+fn add_relative(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_add_relative();
+    Ok(())
+}
+
+fn add_immediate(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    let immediate = cpu.fetch_byte();
+    cpu.execute_add_immediate(immediate);
+    Ok(())
+}
+
+fn adc_reg(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_adc_reg(operand(opcode));
+    Ok(())
+}
+
+fn adc_relative(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_adc_relative();
+    Ok(())
+}
+
+fn adc_immediate(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    let immediate = cpu.fetch_byte();
+    cpu.execute_adc_immediate(immediate);
+    Ok(())
+}
+
+fn sub_reg(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_sub_reg(operand(opcode));
+    Ok(())
+}
+
+fn sub_relative(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_sub_relative();
+    Ok(())
+}
+
+fn sub_immediate(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    let immediate = cpu.fetch_byte();
+    cpu.execute_sub_immediate(immediate);
+    Ok(())
+}
+
+fn sbc_reg(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_sbc_reg(operand(opcode));
+    Ok(())
+}
+
+fn sbc_relative(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_sbc_relative();
+    Ok(())
+}
+
+fn sbc_immediate(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    let immediate = cpu.fetch_byte();
+    cpu.execute_sbc_immediate(immediate);
+    Ok(())
+}
+
+fn and_reg(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_and_reg(operand(opcode));
+    Ok(())
+}
+
+fn and_relative(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_and_relative();
+    Ok(())
+}
+
+fn and_immediate(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    let immediate = cpu.fetch_byte();
+    cpu.execute_and_immediate(immediate);
+    Ok(())
+}
+
+fn xor_reg(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_xor_reg(operand(opcode));
+    Ok(())
+}
+
+fn xor_relative(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_xor_relative();
+    Ok(())
+}
+
+fn xor_immediate(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    let immediate = cpu.fetch_byte();
+    cpu.execute_xor_immediate(immediate);
+    Ok(())
+}
+
+fn or_reg(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_or_reg(operand(opcode));
+    Ok(())
+}
+
+fn or_relative(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_or_relative();
+    Ok(())
+}
+
+fn or_immediate(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    let immediate = cpu.fetch_byte();
+    cpu.execute_or_immediate(immediate);
+    Ok(())
+}
+
+fn cp_reg(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_cp_reg(operand(opcode));
+    Ok(())
+}
+
+fn cp_relative(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_cp_relative();
+    Ok(())
+}
+
+fn cp_immediate(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    let immediate = cpu.fetch_byte();
+    cpu.execute_cp_immediate(immediate);
+    Ok(())
+}
+
+fn ret(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_ret(JumpCondition::Always);
+    Ok(())
+}
+
+fn ret_conditional(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_ret(decode_condition(opcode));
+    Ok(())
+}
+
+fn reti(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_reti();
+    Ok(())
+}
+
+fn jp(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    let address = fetch_word(cpu);
+    cpu.execute_jp(JumpCondition::Always, address);
+    Ok(())
+}
+
+fn jp_conditional(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    let address = fetch_word(cpu);
+    cpu.execute_jp(decode_condition(opcode), address);
+    Ok(())
+}
+
+fn jp_hl(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_jphl();
+    Ok(())
+}
+
+fn call(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    let address = fetch_word(cpu);
+    cpu.execute_call(JumpCondition::Always, address);
+    Ok(())
+}
+
+fn call_conditional(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    let address = fetch_word(cpu);
+    cpu.execute_call(decode_condition(opcode), address);
+    Ok(())
+}
+
+fn rst(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_rst(opcode & 0b0011_1000);
+    Ok(())
+}
+
+fn pop(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_pop(decode_stack_register(opcode));
+    Ok(())
+}
+
+fn push(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_push(decode_stack_register(opcode));
+    Ok(())
+}
+
+fn st_a_high_immediate(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    let offset = cpu.fetch_byte();
+    cpu.execute_sta(Indirect::HighImmediate(offset));
+    Ok(())
+}
+
+fn ld_a_high_immediate(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    let offset = cpu.fetch_byte();
+    cpu.execute_lda(Indirect::HighImmediate(offset));
+    Ok(())
+}
+
+fn st_a_high_c(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_sta(Indirect::HighC);
+    Ok(())
+}
+
+fn ld_a_high_c(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_lda(Indirect::HighC);
+    Ok(())
+}
+
+fn st_a_absolute(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    let address = fetch_word(cpu);
+    cpu.execute_sta(Indirect::Immediate(address));
+    Ok(())
+}
+
+fn ld_a_absolute(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    let address = fetch_word(cpu);
+    cpu.execute_lda(Indirect::Immediate(address));
+    Ok(())
+}
+
+fn add_sp(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    let offset = cpu.fetch_byte() as i8;
+    cpu.execute_addsp(offset);
+    Ok(())
+}
+
+fn ld_hl_sp(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    let offset = cpu.fetch_byte() as i8;
+    cpu.execute_ldhlsp(offset);
+    Ok(())
+}
+
+fn ld_sp_hl(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_ldsphl();
+    Ok(())
+}
+
+fn rlc_reg(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_rlc_reg(operand(opcode));
+    Ok(())
+}
+
+fn rlc_relative(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_rlc_relative();
+    Ok(())
+}
+
+fn rrc_reg(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_rrc_reg(operand(opcode));
+    Ok(())
+}
+
+fn rrc_relative(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_rrc_relative();
+    Ok(())
+}
+
+fn rl_reg(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_rl_reg(operand(opcode));
+    Ok(())
+}
+
+fn rl_relative(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_rl_relative();
+    Ok(())
+}
+
+fn rr_reg(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_rr_reg(operand(opcode));
+    Ok(())
+}
+
+fn rr_relative(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_rr_relative();
+    Ok(())
+}
+
+fn sla_reg(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_sla_reg(operand(opcode));
+    Ok(())
+}
+
+fn sla_relative(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_sla_relative();
+    Ok(())
+}
+
+fn sra_reg(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_sra_reg(operand(opcode));
+    Ok(())
+}
+
+fn sra_relative(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_sra_relative();
+    Ok(())
+}
+
+fn swap_reg(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_swap_reg(operand(opcode));
+    Ok(())
+}
+
+fn swap_relative(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_swap_relative();
+    Ok(())
+}
+
+fn srl_reg(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_srl_reg(operand(opcode));
+    Ok(())
+}
+
+fn srl_relative(cpu: &mut CPU, _: u8) -> Result<(), DecodeError> {
+    cpu.execute_srl_relative();
+    Ok(())
+}
+
+fn bit_reg(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_bit_reg(bit(opcode), operand(opcode));
+    Ok(())
+}
+
+fn bit_relative(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_bit_relative(bit(opcode));
+    Ok(())
+}
+
+fn res_reg(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_res_reg(bit(opcode), operand(opcode));
+    Ok(())
+}
+
+fn res_relative(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_res_relative(bit(opcode));
+    Ok(())
+}
+
+fn set_reg(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_set_reg(bit(opcode), operand(opcode));
+    Ok(())
+}
+
+fn set_relative(cpu: &mut CPU, opcode: u8) -> Result<(), DecodeError> {
+    cpu.execute_set_relative(bit(opcode));
+    Ok(())
+}
+
+#[cfg(test)]
+mod dispatch_tests {
+    use super::*;
+    use crate::flagsregister::FlagsRegister;
+    use crate::instructions::Instruction;
+
+    const PROGRAM_START: u16 = 0xC000;
+    const HL: u16 = 0xC800;
+    const SP: u16 = 0xDFF0;
+
+    fn cpu_with_bytes(bytes: &[u8], flags: u8) -> CPU {
+        let mut cpu = CPU::new();
+        for (offset, &byte) in bytes.iter().enumerate() {
+            cpu.bus.write_byte(PROGRAM_START + offset as u16, byte);
+        }
+        cpu.bus.write_byte(HL, 0x9A);
+
+        cpu.registers.a = 0x3C;
+        cpu.registers.f = FlagsRegister::from(flags);
+        cpu.registers.set_bc(0x1234);
+        cpu.registers.set_de(0x8001);
+        cpu.registers.set_hl(HL);
+        cpu.registers.sp = SP;
+        cpu.registers.pc = PROGRAM_START;
+        cpu
+    }
+
+    #[test]
+    fn test_handlers_match_execute_for_every_opcode() {
+        for &prefixed in &[false, true] {
+            for opcode in 0..=0xFF {
+                for &flags in &[0x00, 0x60, 0x90, 0xF0] {
+                    let bytes = if prefixed {
+                        [PREFIX_BYTE, opcode, 0x00]
+                    } else {
+                        [opcode, 0x42, 0x99]
+                    };
+
+                    let mut dispatched = cpu_with_bytes(&bytes, flags);
+                    let first = dispatched.fetch_byte();
+                    let result = UNPREFIXED_HANDLERS[first as usize](&mut dispatched, first);
+
+                    let mut executed = cpu_with_bytes(&bytes, flags);
+                    let instruction = match Instruction::decode(|| executed.fetch_byte()) {
+                        Ok(instruction) => instruction,
+                        Err(error) => {
+                            assert_eq!(result, Err(error));
+                            continue;
+                        }
+                    };
+                    let cycles = executed.execute(instruction);
+
+                    assert_eq!(result, Ok(()), "{}", instruction);
+                    assert_eq!(dispatched.registers, executed.registers, "{}", instruction);
+                    assert_eq!(dispatched.step_cycles, cycles, "{}", instruction);
+                    assert_eq!(dispatched.ime_scheduled, executed.ime_scheduled);
+                    assert_eq!(dispatched.halted, executed.halted);
+                    for &address in &[HL, SP.wrapping_sub(2), SP.wrapping_sub(1), 0x9942] {
+                        assert_eq!(
+                            dispatched.bus.read_byte(address),
+                            executed.bus.read_byte(address),
+                            "{}",
+                            instruction
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -3,7 +3,7 @@ pub const OPERATION_FLAG_POSITION: u8 = 6;
 pub const HALF_CARRY_FLAG_POSITION: u8 = 5;
 pub const CARRY_FLAG_POSITION: u8 = 4;
 
-#[derive(Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct FlagsRegister {
     pub zero: bool,
     pub substraction: bool,
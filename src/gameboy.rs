@@ -0,0 +1,177 @@
+use super::cartridge::{Cartridge, CartridgeHeader, LoadError};
+use super::flagsregister::FlagsRegister;
+use super::joypad::Buttons;
+use super::mbc3::{Clock, CycleClock};
+use super::registers::Registers;
+use super::{ErrorPolicy, ExecutionError, TimingMode, CPU};
+
+pub const SCREEN_WIDTH: usize = 160;
+pub const SCREEN_HEIGHT: usize = 144;
+
+/// Machine cycles the PPU takes to draw one frame, 154 lines of 114 cycles each.
+pub const CYCLES_PER_FRAME: u64 = 17556;
+
+/// A DMG with a cartridge inserted, the entry point for running the emulator as a library.
+pub struct GameBoy {
+    cpu: CPU,
+    header: Option<CartridgeHeader>,
+    /// One shade per pixel, from 0 (white) to 3 (black), row by row.
+    framebuffer: Vec<u8>,
+    frame_end: u64,
+    rumble: bool,
+    rumble_callback: Option<Box<dyn FnMut(bool)>>,
+}
+
+impl Default for GameBoy {
+    fn default() -> Self {
+        GameBoy::new()
+    }
+}
+
+impl GameBoy {
+    pub fn new() -> Self {
+        GameBoy {
+            cpu: CPU::new(),
+            header: None,
+            framebuffer: vec![0; SCREEN_WIDTH * SCREEN_HEIGHT],
+            frame_end: CYCLES_PER_FRAME,
+            rumble: false,
+            rumble_callback: None,
+        }
+    }
+
+    /// Inserts `rom` and resets the console to the state the boot ROM leaves it in, with
+    /// execution starting at the cartridge entry point 0x0100. An image whose header does not
+    /// check out is rejected and leaves the console as it was.
+    pub fn load_rom(&mut self, rom: Vec<u8>) -> Result<(), LoadError> {
+        self.load_rom_with_clock(rom, Box::new(CycleClock::default()))
+    }
+
+    /// Like `load_rom`, with the real time clock of MBC3 cartridges driven by `clock` instead of
+    /// emulated cycles, for instance a `SystemClock` to follow the host's time.
+    pub fn load_rom_with_clock(
+        &mut self,
+        rom: Vec<u8>,
+        clock: Box<dyn Clock>,
+    ) -> Result<(), LoadError> {
+        let header = CartridgeHeader::parse(&rom)?;
+        let cartridge = Cartridge::with_clock(rom, &header, clock)?;
+
+        let mut cpu = CPU::new();
+        cpu.timing_mode = self.cpu.timing_mode;
+        cpu.error_policy = self.cpu.error_policy;
+        cpu.bus.insert_cartridge(cartridge);
+
+        cpu.registers.set_af(0x01B0);
+        cpu.registers.set_bc(0x0013);
+        cpu.registers.set_de(0x00D8);
+        cpu.registers.set_hl(0x014D);
+        cpu.registers.sp = 0xFFFE;
+        cpu.registers.pc = 0x0100;
+
+        self.cpu = cpu;
+        self.header = Some(header);
+        self.framebuffer.iter_mut().for_each(|shade| *shade = 0);
+        self.frame_end = CYCLES_PER_FRAME;
+        self.set_rumble(false);
+        Ok(())
+    }
+
+    /// Header of the cartridge inserted, `None` until a ROM is loaded.
+    pub fn header(&self) -> Option<&CartridgeHeader> {
+        self.header.as_ref()
+    }
+
+    pub fn set_timing_mode(&mut self, timing_mode: TimingMode) {
+        self.cpu.timing_mode = timing_mode;
+    }
+
+    pub fn set_error_policy(&mut self, error_policy: ErrorPolicy) {
+        self.cpu.error_policy = error_policy;
+    }
+
+    /// Runs one instruction, or one interrupt dispatch or halted cycle, and returns the machine
+    /// cycles it took.
+    pub fn step(&mut self) -> Result<u8, ExecutionError> {
+        let cycles = self.cpu.step()?;
+        self.poll_rumble();
+        Ok(cycles)
+    }
+
+    /// `step` without the opcode dispatch tables, decoding every instruction first. Only built
+    /// with the `bench` feature, for benchmarks.
+    #[cfg(feature = "bench")]
+    #[doc(hidden)]
+    pub fn step_decoded(&mut self) -> Result<u8, ExecutionError> {
+        let cycles = self.cpu.step_decoded()?;
+        self.poll_rumble();
+        Ok(cycles)
+    }
+
+    /// Runs until the end of the current frame. Instructions that straddle the end of a frame
+    /// count towards it, so frames stay aligned to `CYCLES_PER_FRAME`.
+    pub fn run_frame(&mut self) -> Result<(), ExecutionError> {
+        while self.cpu.cycles < self.frame_end {
+            self.step()?;
+        }
+        self.frame_end += CYCLES_PER_FRAME;
+        Ok(())
+    }
+
+    /// The last frame drawn, `SCREEN_WIDTH` shades per row. The PPU is not emulated yet, so
+    /// the screen stays blank.
+    pub fn framebuffer(&self) -> &[u8] {
+        &self.framebuffer
+    }
+
+    /// Whether the rumble motor of the cartridge is running. Always off for cartridges without
+    /// one.
+    pub fn rumble(&self) -> bool {
+        self.rumble
+    }
+
+    /// Calls `callback` with the new state of the rumble motor whenever the game switches it.
+    /// Games vary the strength of the rumble by switching it on and off quickly, so the
+    /// callback can be called several times a frame.
+    pub fn set_rumble_callback<F: FnMut(bool) + 'static>(&mut self, callback: F) {
+        self.rumble_callback = Some(Box::new(callback));
+    }
+
+    pub fn set_buttons(&mut self, buttons: Buttons) {
+        self.cpu.bus.set_buttons(buttons);
+    }
+
+    pub fn registers(&self) -> &Registers {
+        &self.cpu.registers
+    }
+
+    pub fn flags(&self) -> &FlagsRegister {
+        &self.cpu.registers.f
+    }
+
+    /// Machine cycles elapsed since the ROM was loaded.
+    pub fn cycles(&self) -> u64 {
+        self.cpu.cycles
+    }
+
+    /// Reads memory the way the CPU sees it, without spending any cycles.
+    pub fn read_byte(&self, address: u16) -> u8 {
+        self.cpu.bus.read_byte(address)
+    }
+
+    fn poll_rumble(&mut self) {
+        if let Some(rumble) = self.cpu.bus.cartridge_mut().take_rumble_change() {
+            self.set_rumble(rumble);
+        }
+    }
+
+    fn set_rumble(&mut self, rumble: bool) {
+        if rumble == self.rumble {
+            return;
+        }
+        self.rumble = rumble;
+        if let Some(callback) = &mut self.rumble_callback {
+            callback(rumble);
+        }
+    }
+}
@@ -1,4 +1,13 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Instruction {
+    NOP(),
+    STOP(),
+    HALT(),
+    DI(),
+    EI(),
+
     ADD(ArithmeticRegisters),
     ADDI(u8),
     ADDR(),
@@ -26,8 +35,101 @@ pub enum Instruction {
     XOR(ArithmeticRegisters),
     XORI(u8),
     XORR(),
+
+    CP(ArithmeticRegisters),
+    CPI(u8),
+    CPR(),
+
+    INC(ArithmeticRegisters),
+    INCR(),
+
+    DEC(ArithmeticRegisters),
+    DECR(),
+
+    DAA(),
+    CPL(),
+    SCF(),
+    CCF(),
+
+    RLCA(),
+    RRCA(),
+    RLA(),
+    RRA(),
+
+    ADDHL(WideRegisters),
+    ADDSP(i8),
+    INCW(WideRegisters),
+    DECW(WideRegisters),
+
+    RLC(ArithmeticRegisters),
+    RLCR(),
+
+    RRC(ArithmeticRegisters),
+    RRCR(),
+
+    RL(ArithmeticRegisters),
+    RLR(),
+
+    RR(ArithmeticRegisters),
+    RRR(),
+
+    SLA(ArithmeticRegisters),
+    SLAR(),
+
+    SRA(ArithmeticRegisters),
+    SRAR(),
+
+    SWAP(ArithmeticRegisters),
+    SWAPR(),
+
+    SRL(ArithmeticRegisters),
+    SRLR(),
+
+    BIT(u8, ArithmeticRegisters),
+    BITR(u8),
+
+    RES(u8, ArithmeticRegisters),
+    RESR(u8),
+
+    SET(u8, ArithmeticRegisters),
+    SETR(u8),
+
+    /// LD r, r'
+    LD(ArithmeticRegisters, ArithmeticRegisters),
+    /// LD r, n
+    LDI(ArithmeticRegisters, u8),
+    /// LD r, (HL)
+    LDR(ArithmeticRegisters),
+    /// LD (HL), r
+    STR(ArithmeticRegisters),
+    /// LD (HL), n
+    STRI(u8),
+    /// LD A, (indirect)
+    LDA(Indirect),
+    /// LD (indirect), A
+    STA(Indirect),
+    /// LD rr, nn
+    LDW(WideRegisters, u16),
+    /// LD (nn), SP
+    STSP(u16),
+    /// LD SP, HL
+    LDSPHL(),
+    /// LD HL, SP+e
+    LDHLSP(i8),
+
+    JP(JumpCondition, u16),
+    JPHL(),
+    JR(JumpCondition, i8),
+    CALL(JumpCondition, u16),
+    RET(JumpCondition),
+    RETI(),
+    RST(u8),
+
+    PUSH(StackRegisters),
+    POP(StackRegisters),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ArithmeticRegisters {
     A,
     B,
@@ -37,3 +139,1530 @@ pub enum ArithmeticRegisters {
     H,
     L,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WideRegisters {
+    BC,
+    DE,
+    HL,
+    SP,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackRegisters {
+    BC,
+    DE,
+    HL,
+    AF,
+}
+
+/// Memory operands that can only be loaded into or stored from register A.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indirect {
+    /// (BC)
+    BC,
+    /// (DE)
+    DE,
+    /// (HL+), HL is incremented after the access.
+    HLIncrement,
+    /// (HL-), HL is decremented after the access.
+    HLDecrement,
+    /// ($FF00+C)
+    HighC,
+    /// ($FF00+n)
+    HighImmediate(u8),
+    /// (nn)
+    Immediate(u16),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JumpCondition {
+    Always,
+    NotZero,
+    Zero,
+    NotCarry,
+    Carry,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// One of the unused opcodes, which lock up the CPU on real hardware.
+    IllegalOpcode(u8),
+}
+
+pub const PREFIX_BYTE: u8 = 0xCB;
+
+pub const ILLEGAL_OPCODES: [u8; 11] = [
+    0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC, 0xFD,
+];
+
+impl Instruction {
+    /// Decodes the instruction whose first byte is produced by `next_byte`, pulling the CB
+    /// suffix and any immediate operands from it as well.
+    pub fn decode<F: FnMut() -> u8>(mut next_byte: F) -> Result<Instruction, DecodeError> {
+        let opcode = next_byte();
+        Instruction::from_byte(opcode, false, next_byte)
+    }
+
+    /// Decodes `byte` as an opcode of the unprefixed page, or of the CB page if `prefixed` is
+    /// set. Immediate operands, and the second byte of a CB-prefixed opcode, are fetched with
+    /// `next_byte`.
+    pub fn from_byte<F: FnMut() -> u8>(
+        byte: u8,
+        prefixed: bool,
+        mut next_byte: F,
+    ) -> Result<Instruction, DecodeError> {
+        if prefixed {
+            Ok(Instruction::from_byte_prefixed(byte))
+        } else if byte == PREFIX_BYTE {
+            Ok(Instruction::from_byte_prefixed(next_byte()))
+        } else {
+            Instruction::from_byte_not_prefixed(byte, &mut next_byte)
+        }
+    }
+
+    fn from_byte_prefixed(byte: u8) -> Instruction {
+        let bit = (byte >> 3) & 0b111;
+        let operand = decode_operand(byte);
+
+        match (byte >> 6, operand) {
+            (0, operand) => match (bit, operand) {
+                (0, Some(reg)) => Instruction::RLC(reg),
+                (0, None) => Instruction::RLCR(),
+                (1, Some(reg)) => Instruction::RRC(reg),
+                (1, None) => Instruction::RRCR(),
+                (2, Some(reg)) => Instruction::RL(reg),
+                (2, None) => Instruction::RLR(),
+                (3, Some(reg)) => Instruction::RR(reg),
+                (3, None) => Instruction::RRR(),
+                (4, Some(reg)) => Instruction::SLA(reg),
+                (4, None) => Instruction::SLAR(),
+                (5, Some(reg)) => Instruction::SRA(reg),
+                (5, None) => Instruction::SRAR(),
+                (6, Some(reg)) => Instruction::SWAP(reg),
+                (6, None) => Instruction::SWAPR(),
+                (_, Some(reg)) => Instruction::SRL(reg),
+                (_, None) => Instruction::SRLR(),
+            },
+            (1, Some(reg)) => Instruction::BIT(bit, reg),
+            (1, None) => Instruction::BITR(bit),
+            (2, Some(reg)) => Instruction::RES(bit, reg),
+            (2, None) => Instruction::RESR(bit),
+            (_, Some(reg)) => Instruction::SET(bit, reg),
+            (_, None) => Instruction::SETR(bit),
+        }
+    }
+
+    fn from_byte_not_prefixed<F: FnMut() -> u8>(
+        byte: u8,
+        next_byte: &mut F,
+    ) -> Result<Instruction, DecodeError> {
+        let instruction = match byte {
+            0x00 => Instruction::NOP(),
+            0x10 => {
+                // STOP is followed by a padding byte that is skipped along with it.
+                next_byte();
+                Instruction::STOP()
+            }
+            0x76 => Instruction::HALT(),
+            0xF3 => Instruction::DI(),
+            0xFB => Instruction::EI(),
+
+            0x01 | 0x11 | 0x21 | 0x31 => {
+                Instruction::LDW(decode_wide_register(byte), fetch_word(next_byte))
+            }
+            0x02 => Instruction::STA(Indirect::BC),
+            0x12 => Instruction::STA(Indirect::DE),
+            0x22 => Instruction::STA(Indirect::HLIncrement),
+            0x32 => Instruction::STA(Indirect::HLDecrement),
+            0x0A => Instruction::LDA(Indirect::BC),
+            0x1A => Instruction::LDA(Indirect::DE),
+            0x2A => Instruction::LDA(Indirect::HLIncrement),
+            0x3A => Instruction::LDA(Indirect::HLDecrement),
+            0x03 | 0x13 | 0x23 | 0x33 => Instruction::INCW(decode_wide_register(byte)),
+            0x0B | 0x1B | 0x2B | 0x3B => Instruction::DECW(decode_wide_register(byte)),
+            0x09 | 0x19 | 0x29 | 0x39 => Instruction::ADDHL(decode_wide_register(byte)),
+            0x08 => Instruction::STSP(fetch_word(next_byte)),
+
+            0x34 => Instruction::INCR(),
+            0x35 => Instruction::DECR(),
+            0x36 => Instruction::STRI(next_byte()),
+            0x04 | 0x0C | 0x14 | 0x1C | 0x24 | 0x2C | 0x3C => {
+                Instruction::INC(decode_destination(byte).unwrap())
+            }
+            0x05 | 0x0D | 0x15 | 0x1D | 0x25 | 0x2D | 0x3D => {
+                Instruction::DEC(decode_destination(byte).unwrap())
+            }
+            0x06 | 0x0E | 0x16 | 0x1E | 0x26 | 0x2E | 0x3E => {
+                Instruction::LDI(decode_destination(byte).unwrap(), next_byte())
+            }
+
+            0x07 => Instruction::RLCA(),
+            0x0F => Instruction::RRCA(),
+            0x17 => Instruction::RLA(),
+            0x1F => Instruction::RRA(),
+            0x27 => Instruction::DAA(),
+            0x2F => Instruction::CPL(),
+            0x37 => Instruction::SCF(),
+            0x3F => Instruction::CCF(),
+
+            0x18 => Instruction::JR(JumpCondition::Always, next_byte() as i8),
+            0x20 | 0x28 | 0x30 | 0x38 => Instruction::JR(decode_condition(byte), next_byte() as i8),
+
+            0x40..=0x7F => match (decode_destination(byte), decode_operand(byte)) {
+                (Some(target), Some(source)) => Instruction::LD(target, source),
+                (Some(target), None) => Instruction::LDR(target),
+                (None, Some(source)) => Instruction::STR(source),
+                // 0x76 is HALT and is matched above.
+                (None, None) => unreachable!(),
+            },
+
+            0x80..=0xBF => match ((byte >> 3) & 0b111, decode_operand(byte)) {
+                (0, Some(reg)) => Instruction::ADD(reg),
+                (0, None) => Instruction::ADDR(),
+                (1, Some(reg)) => Instruction::ADC(reg),
+                (1, None) => Instruction::ADCR(),
+                (2, Some(reg)) => Instruction::SUB(reg),
+                (2, None) => Instruction::SUBR(),
+                (3, Some(reg)) => Instruction::SBC(reg),
+                (3, None) => Instruction::SBCR(),
+                (4, Some(reg)) => Instruction::AND(reg),
+                (4, None) => Instruction::ANDR(),
+                (5, Some(reg)) => Instruction::XOR(reg),
+                (5, None) => Instruction::XORR(),
+                (6, Some(reg)) => Instruction::OR(reg),
+                (6, None) => Instruction::ORR(),
+                (_, Some(reg)) => Instruction::CP(reg),
+                (_, None) => Instruction::CPR(),
+            },
+
+            0xC6 => Instruction::ADDI(next_byte()),
+            0xCE => Instruction::ADCI(next_byte()),
+            0xD6 => Instruction::SUBI(next_byte()),
+            0xDE => Instruction::SBCI(next_byte()),
+            0xE6 => Instruction::ANDI(next_byte()),
+            0xEE => Instruction::XORI(next_byte()),
+            0xF6 => Instruction::ORI(next_byte()),
+            0xFE => Instruction::CPI(next_byte()),
+
+            0xC0 | 0xC8 | 0xD0 | 0xD8 => Instruction::RET(decode_condition(byte)),
+            0xC9 => Instruction::RET(JumpCondition::Always),
+            0xD9 => Instruction::RETI(),
+            0xC2 | 0xCA | 0xD2 | 0xDA => {
+                Instruction::JP(decode_condition(byte), fetch_word(next_byte))
+            }
+            0xC3 => Instruction::JP(JumpCondition::Always, fetch_word(next_byte)),
+            0xE9 => Instruction::JPHL(),
+            0xC4 | 0xCC | 0xD4 | 0xDC => {
+                Instruction::CALL(decode_condition(byte), fetch_word(next_byte))
+            }
+            0xCD => Instruction::CALL(JumpCondition::Always, fetch_word(next_byte)),
+            0xC7 | 0xCF | 0xD7 | 0xDF | 0xE7 | 0xEF | 0xF7 | 0xFF => {
+                Instruction::RST(byte & 0b0011_1000)
+            }
+
+            0xC1 | 0xD1 | 0xE1 | 0xF1 => Instruction::POP(decode_stack_register(byte)),
+            0xC5 | 0xD5 | 0xE5 | 0xF5 => Instruction::PUSH(decode_stack_register(byte)),
+
+            0xE0 => Instruction::STA(Indirect::HighImmediate(next_byte())),
+            0xF0 => Instruction::LDA(Indirect::HighImmediate(next_byte())),
+            0xE2 => Instruction::STA(Indirect::HighC),
+            0xF2 => Instruction::LDA(Indirect::HighC),
+            0xEA => Instruction::STA(Indirect::Immediate(fetch_word(next_byte))),
+            0xFA => Instruction::LDA(Indirect::Immediate(fetch_word(next_byte))),
+
+            0xE8 => Instruction::ADDSP(next_byte() as i8),
+            0xF8 => Instruction::LDHLSP(next_byte() as i8),
+            0xF9 => Instruction::LDSPHL(),
+
+            _ => return Err(DecodeError::IllegalOpcode(byte)),
+        };
+
+        Ok(instruction)
+    }
+
+    /// Machine code of the instruction, the CB prefix and immediate operands included.
+    ///
+    /// Panics unless the operands are ones an opcode can hold: `RST` vectors are multiples of 8
+    /// up to 0x38 and bit indexes go up to 7.
+    pub fn encode(&self) -> Vec<u8> {
+        self.encoding().as_slice().to_vec()
+    }
+
+    /// Writes the machine code of the instruction to the start of `buffer` and returns how many
+    /// bytes that took. Panics if `buffer` is shorter than `length()`, or on operands `encode`
+    /// panics on.
+    pub fn encode_into(&self, buffer: &mut [u8]) -> usize {
+        let encoding = self.encoding();
+        buffer[..encoding.length].copy_from_slice(encoding.as_slice());
+        encoding.length
+    }
+
+    /// Bytes of machine code the instruction takes, from 1 to 3. Panics on operands `encode`
+    /// panics on.
+    pub fn length(&self) -> usize {
+        self.encoding().length
+    }
+
+    /// The machine code of the instruction in a fixed-size buffer, without allocating.
+    pub(crate) fn encoding(&self) -> Encoding {
+        match *self {
+            Instruction::NOP() => Encoding::new(&[0x00]),
+            Instruction::STOP() => Encoding::new(&[0x10, 0x00]),
+            Instruction::HALT() => Encoding::new(&[0x76]),
+            Instruction::DI() => Encoding::new(&[0xF3]),
+            Instruction::EI() => Encoding::new(&[0xFB]),
+
+            Instruction::ADD(source) => Encoding::new(&[0x80 | encode_operand(Some(source))]),
+            Instruction::ADDI(immediate) => Encoding::new(&[0xC6, immediate]),
+            Instruction::ADDR() => Encoding::new(&[0x80 | encode_operand(None)]),
+            Instruction::ADC(source) => Encoding::new(&[0x88 | encode_operand(Some(source))]),
+            Instruction::ADCI(immediate) => Encoding::new(&[0xCE, immediate]),
+            Instruction::ADCR() => Encoding::new(&[0x88 | encode_operand(None)]),
+            Instruction::SUB(source) => Encoding::new(&[0x90 | encode_operand(Some(source))]),
+            Instruction::SUBI(immediate) => Encoding::new(&[0xD6, immediate]),
+            Instruction::SUBR() => Encoding::new(&[0x90 | encode_operand(None)]),
+            Instruction::SBC(source) => Encoding::new(&[0x98 | encode_operand(Some(source))]),
+            Instruction::SBCI(immediate) => Encoding::new(&[0xDE, immediate]),
+            Instruction::SBCR() => Encoding::new(&[0x98 | encode_operand(None)]),
+            Instruction::AND(source) => Encoding::new(&[0xA0 | encode_operand(Some(source))]),
+            Instruction::ANDI(immediate) => Encoding::new(&[0xE6, immediate]),
+            Instruction::ANDR() => Encoding::new(&[0xA0 | encode_operand(None)]),
+            Instruction::XOR(source) => Encoding::new(&[0xA8 | encode_operand(Some(source))]),
+            Instruction::XORI(immediate) => Encoding::new(&[0xEE, immediate]),
+            Instruction::XORR() => Encoding::new(&[0xA8 | encode_operand(None)]),
+            Instruction::OR(source) => Encoding::new(&[0xB0 | encode_operand(Some(source))]),
+            Instruction::ORI(immediate) => Encoding::new(&[0xF6, immediate]),
+            Instruction::ORR() => Encoding::new(&[0xB0 | encode_operand(None)]),
+            Instruction::CP(source) => Encoding::new(&[0xB8 | encode_operand(Some(source))]),
+            Instruction::CPI(immediate) => Encoding::new(&[0xFE, immediate]),
+            Instruction::CPR() => Encoding::new(&[0xB8 | encode_operand(None)]),
+
+            Instruction::INC(target) => Encoding::new(&[0x04 | encode_destination(Some(target))]),
+            Instruction::INCR() => Encoding::new(&[0x04 | encode_destination(None)]),
+            Instruction::DEC(target) => Encoding::new(&[0x05 | encode_destination(Some(target))]),
+            Instruction::DECR() => Encoding::new(&[0x05 | encode_destination(None)]),
+
+            Instruction::DAA() => Encoding::new(&[0x27]),
+            Instruction::CPL() => Encoding::new(&[0x2F]),
+            Instruction::SCF() => Encoding::new(&[0x37]),
+            Instruction::CCF() => Encoding::new(&[0x3F]),
+            Instruction::RLCA() => Encoding::new(&[0x07]),
+            Instruction::RRCA() => Encoding::new(&[0x0F]),
+            Instruction::RLA() => Encoding::new(&[0x17]),
+            Instruction::RRA() => Encoding::new(&[0x1F]),
+
+            Instruction::ADDHL(source) => Encoding::new(&[0x09 | encode_wide_register(source)]),
+            Instruction::ADDSP(offset) => Encoding::new(&[0xE8, offset as u8]),
+            Instruction::INCW(target) => Encoding::new(&[0x03 | encode_wide_register(target)]),
+            Instruction::DECW(target) => Encoding::new(&[0x0B | encode_wide_register(target)]),
+
+            Instruction::RLC(target) => Encoding::new(&[PREFIX_BYTE, encode_operand(Some(target))]),
+            Instruction::RLCR() => Encoding::new(&[PREFIX_BYTE, encode_operand(None)]),
+            Instruction::RRC(target) => {
+                Encoding::new(&[PREFIX_BYTE, 0x08 | encode_operand(Some(target))])
+            }
+            Instruction::RRCR() => Encoding::new(&[PREFIX_BYTE, 0x08 | encode_operand(None)]),
+            Instruction::RL(target) => {
+                Encoding::new(&[PREFIX_BYTE, 0x10 | encode_operand(Some(target))])
+            }
+            Instruction::RLR() => Encoding::new(&[PREFIX_BYTE, 0x10 | encode_operand(None)]),
+            Instruction::RR(target) => {
+                Encoding::new(&[PREFIX_BYTE, 0x18 | encode_operand(Some(target))])
+            }
+            Instruction::RRR() => Encoding::new(&[PREFIX_BYTE, 0x18 | encode_operand(None)]),
+            Instruction::SLA(target) => {
+                Encoding::new(&[PREFIX_BYTE, 0x20 | encode_operand(Some(target))])
+            }
+            Instruction::SLAR() => Encoding::new(&[PREFIX_BYTE, 0x20 | encode_operand(None)]),
+            Instruction::SRA(target) => {
+                Encoding::new(&[PREFIX_BYTE, 0x28 | encode_operand(Some(target))])
+            }
+            Instruction::SRAR() => Encoding::new(&[PREFIX_BYTE, 0x28 | encode_operand(None)]),
+            Instruction::SWAP(target) => {
+                Encoding::new(&[PREFIX_BYTE, 0x30 | encode_operand(Some(target))])
+            }
+            Instruction::SWAPR() => Encoding::new(&[PREFIX_BYTE, 0x30 | encode_operand(None)]),
+            Instruction::SRL(target) => {
+                Encoding::new(&[PREFIX_BYTE, 0x38 | encode_operand(Some(target))])
+            }
+            Instruction::SRLR() => Encoding::new(&[PREFIX_BYTE, 0x38 | encode_operand(None)]),
+            Instruction::BIT(bit, source) => {
+                Encoding::new(&[PREFIX_BYTE, encode_bit(0x40, bit, Some(source))])
+            }
+            Instruction::BITR(bit) => Encoding::new(&[PREFIX_BYTE, encode_bit(0x40, bit, None)]),
+            Instruction::RES(bit, target) => {
+                Encoding::new(&[PREFIX_BYTE, encode_bit(0x80, bit, Some(target))])
+            }
+            Instruction::RESR(bit) => Encoding::new(&[PREFIX_BYTE, encode_bit(0x80, bit, None)]),
+            Instruction::SET(bit, target) => {
+                Encoding::new(&[PREFIX_BYTE, encode_bit(0xC0, bit, Some(target))])
+            }
+            Instruction::SETR(bit) => Encoding::new(&[PREFIX_BYTE, encode_bit(0xC0, bit, None)]),
+
+            Instruction::LD(target, source) => Encoding::new(&[0x40
+                | encode_destination(Some(target))
+                | encode_operand(Some(source))]),
+            Instruction::LDI(target, immediate) => {
+                Encoding::new(&[0x06 | encode_destination(Some(target)), immediate])
+            }
+            Instruction::LDR(target) => {
+                Encoding::new(&[0x40 | encode_destination(Some(target)) | encode_operand(None)])
+            }
+            Instruction::STR(source) => {
+                Encoding::new(&[0x40 | encode_destination(None) | encode_operand(Some(source))])
+            }
+            Instruction::STRI(immediate) => Encoding::new(&[0x36, immediate]),
+            Instruction::LDA(source) => match source {
+                Indirect::BC => Encoding::new(&[0x0A]),
+                Indirect::DE => Encoding::new(&[0x1A]),
+                Indirect::HLIncrement => Encoding::new(&[0x2A]),
+                Indirect::HLDecrement => Encoding::new(&[0x3A]),
+                Indirect::HighC => Encoding::new(&[0xF2]),
+                Indirect::HighImmediate(offset) => Encoding::new(&[0xF0, offset]),
+                Indirect::Immediate(address) => with_word(0xFA, address),
+            },
+            Instruction::STA(target) => match target {
+                Indirect::BC => Encoding::new(&[0x02]),
+                Indirect::DE => Encoding::new(&[0x12]),
+                Indirect::HLIncrement => Encoding::new(&[0x22]),
+                Indirect::HLDecrement => Encoding::new(&[0x32]),
+                Indirect::HighC => Encoding::new(&[0xE2]),
+                Indirect::HighImmediate(offset) => Encoding::new(&[0xE0, offset]),
+                Indirect::Immediate(address) => with_word(0xEA, address),
+            },
+            Instruction::LDW(target, immediate) => {
+                with_word(0x01 | encode_wide_register(target), immediate)
+            }
+            Instruction::STSP(address) => with_word(0x08, address),
+            Instruction::LDSPHL() => Encoding::new(&[0xF9]),
+            Instruction::LDHLSP(offset) => Encoding::new(&[0xF8, offset as u8]),
+
+            Instruction::JP(JumpCondition::Always, address) => with_word(0xC3, address),
+            Instruction::JP(condition, address) => {
+                with_word(0xC2 | encode_condition(condition), address)
+            }
+            Instruction::JPHL() => Encoding::new(&[0xE9]),
+            Instruction::JR(JumpCondition::Always, offset) => Encoding::new(&[0x18, offset as u8]),
+            Instruction::JR(condition, offset) => {
+                Encoding::new(&[0x20 | encode_condition(condition), offset as u8])
+            }
+            Instruction::CALL(JumpCondition::Always, address) => with_word(0xCD, address),
+            Instruction::CALL(condition, address) => {
+                with_word(0xC4 | encode_condition(condition), address)
+            }
+            Instruction::RET(JumpCondition::Always) => Encoding::new(&[0xC9]),
+            Instruction::RET(condition) => Encoding::new(&[0xC0 | encode_condition(condition)]),
+            Instruction::RETI() => Encoding::new(&[0xD9]),
+            Instruction::RST(vector) => {
+                assert!(
+                    vector & !0b0011_1000 == 0,
+                    "RST vector {:#04x} is not a multiple of 8 up to 0x38",
+                    vector
+                );
+                Encoding::new(&[0xC7 | (vector & 0b0011_1000)])
+            }
+
+            Instruction::PUSH(source) => Encoding::new(&[0xC5 | encode_stack_register(source)]),
+            Instruction::POP(target) => Encoding::new(&[0xC1 | encode_stack_register(target)]),
+        }
+    }
+
+    /// Machine cycles the instruction takes, including the fetch of its opcode and operands.
+    /// Conditional jumps, calls and returns take longer when `branch_taken` is set.
+    pub fn cycles(&self, branch_taken: bool) -> u8 {
+        match *self {
+            Instruction::NOP() | Instruction::HALT() | Instruction::DI() | Instruction::EI() => 1,
+            // The padding byte after STOP is fetched like an operand.
+            Instruction::STOP() => 2,
+
+            Instruction::ADD(_)
+            | Instruction::ADC(_)
+            | Instruction::SUB(_)
+            | Instruction::SBC(_)
+            | Instruction::AND(_)
+            | Instruction::OR(_)
+            | Instruction::XOR(_)
+            | Instruction::CP(_) => 1,
+            Instruction::ADDI(_)
+            | Instruction::ADCI(_)
+            | Instruction::SUBI(_)
+            | Instruction::SBCI(_)
+            | Instruction::ANDI(_)
+            | Instruction::ORI(_)
+            | Instruction::XORI(_)
+            | Instruction::CPI(_) => 2,
+            Instruction::ADDR()
+            | Instruction::ADCR()
+            | Instruction::SUBR()
+            | Instruction::SBCR()
+            | Instruction::ANDR()
+            | Instruction::ORR()
+            | Instruction::XORR()
+            | Instruction::CPR() => 2,
+
+            Instruction::INC(_) | Instruction::DEC(_) => 1,
+            Instruction::INCR() | Instruction::DECR() => 3,
+
+            Instruction::DAA()
+            | Instruction::CPL()
+            | Instruction::SCF()
+            | Instruction::CCF()
+            | Instruction::RLCA()
+            | Instruction::RRCA()
+            | Instruction::RLA()
+            | Instruction::RRA() => 1,
+
+            Instruction::ADDHL(_) | Instruction::INCW(_) | Instruction::DECW(_) => 2,
+            Instruction::ADDSP(_) => 4,
+
+            Instruction::RLC(_)
+            | Instruction::RRC(_)
+            | Instruction::RL(_)
+            | Instruction::RR(_)
+            | Instruction::SLA(_)
+            | Instruction::SRA(_)
+            | Instruction::SWAP(_)
+            | Instruction::SRL(_)
+            | Instruction::BIT(_, _)
+            | Instruction::RES(_, _)
+            | Instruction::SET(_, _) => 2,
+            Instruction::BITR(_) => 3,
+            Instruction::RLCR()
+            | Instruction::RRCR()
+            | Instruction::RLR()
+            | Instruction::RRR()
+            | Instruction::SLAR()
+            | Instruction::SRAR()
+            | Instruction::SWAPR()
+            | Instruction::SRLR()
+            | Instruction::RESR(_)
+            | Instruction::SETR(_) => 4,
+
+            Instruction::LD(_, _) => 1,
+            Instruction::LDI(_, _) | Instruction::LDR(_) | Instruction::STR(_) => 2,
+            Instruction::STRI(_) => 3,
+            Instruction::LDA(indirect) | Instruction::STA(indirect) => match indirect {
+                Indirect::HighImmediate(_) => 3,
+                Indirect::Immediate(_) => 4,
+                _ => 2,
+            },
+            Instruction::LDW(_, _) => 3,
+            Instruction::STSP(_) => 5,
+            Instruction::LDSPHL() => 2,
+            Instruction::LDHLSP(_) => 3,
+
+            Instruction::JP(_, _) => {
+                if branch_taken {
+                    4
+                } else {
+                    3
+                }
+            }
+            Instruction::JPHL() => 1,
+            Instruction::JR(_, _) => {
+                if branch_taken {
+                    3
+                } else {
+                    2
+                }
+            }
+            Instruction::CALL(_, _) => {
+                if branch_taken {
+                    6
+                } else {
+                    3
+                }
+            }
+            Instruction::RET(JumpCondition::Always) => 4,
+            Instruction::RET(_) => {
+                if branch_taken {
+                    5
+                } else {
+                    2
+                }
+            }
+            Instruction::RETI() | Instruction::RST(_) => 4,
+            Instruction::PUSH(_) => 4,
+            Instruction::POP(_) => 3,
+        }
+    }
+}
+
+/// Formats instructions with the usual assembler mnemonics, such as `ADD A,B`, `XOR $0F` or
+/// `SBC A,(HL)`. JR targets are written relative to the address of the JR itself (`@`), which is
+/// how an assembler expects them.
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Instruction::NOP() => write!(f, "NOP"),
+            Instruction::STOP() => write!(f, "STOP"),
+            Instruction::HALT() => write!(f, "HALT"),
+            Instruction::DI() => write!(f, "DI"),
+            Instruction::EI() => write!(f, "EI"),
+
+            Instruction::ADD(source) => write!(f, "ADD A,{}", source),
+            Instruction::ADDI(immediate) => write!(f, "ADD A,${:02X}", immediate),
+            Instruction::ADDR() => write!(f, "ADD A,(HL)"),
+            Instruction::ADC(source) => write!(f, "ADC A,{}", source),
+            Instruction::ADCI(immediate) => write!(f, "ADC A,${:02X}", immediate),
+            Instruction::ADCR() => write!(f, "ADC A,(HL)"),
+            Instruction::SUB(source) => write!(f, "SUB {}", source),
+            Instruction::SUBI(immediate) => write!(f, "SUB ${:02X}", immediate),
+            Instruction::SUBR() => write!(f, "SUB (HL)"),
+            Instruction::SBC(source) => write!(f, "SBC A,{}", source),
+            Instruction::SBCI(immediate) => write!(f, "SBC A,${:02X}", immediate),
+            Instruction::SBCR() => write!(f, "SBC A,(HL)"),
+            Instruction::AND(source) => write!(f, "AND {}", source),
+            Instruction::ANDI(immediate) => write!(f, "AND ${:02X}", immediate),
+            Instruction::ANDR() => write!(f, "AND (HL)"),
+            Instruction::OR(source) => write!(f, "OR {}", source),
+            Instruction::ORI(immediate) => write!(f, "OR ${:02X}", immediate),
+            Instruction::ORR() => write!(f, "OR (HL)"),
+            Instruction::XOR(source) => write!(f, "XOR {}", source),
+            Instruction::XORI(immediate) => write!(f, "XOR ${:02X}", immediate),
+            Instruction::XORR() => write!(f, "XOR (HL)"),
+            Instruction::CP(source) => write!(f, "CP {}", source),
+            Instruction::CPI(immediate) => write!(f, "CP ${:02X}", immediate),
+            Instruction::CPR() => write!(f, "CP (HL)"),
+
+            Instruction::INC(target) => write!(f, "INC {}", target),
+            Instruction::INCR() => write!(f, "INC (HL)"),
+            Instruction::DEC(target) => write!(f, "DEC {}", target),
+            Instruction::DECR() => write!(f, "DEC (HL)"),
+
+            Instruction::DAA() => write!(f, "DAA"),
+            Instruction::CPL() => write!(f, "CPL"),
+            Instruction::SCF() => write!(f, "SCF"),
+            Instruction::CCF() => write!(f, "CCF"),
+            Instruction::RLCA() => write!(f, "RLCA"),
+            Instruction::RRCA() => write!(f, "RRCA"),
+            Instruction::RLA() => write!(f, "RLA"),
+            Instruction::RRA() => write!(f, "RRA"),
+
+            Instruction::ADDHL(source) => write!(f, "ADD HL,{}", source),
+            Instruction::ADDSP(offset) => write!(f, "ADD SP,{}", offset),
+            Instruction::INCW(target) => write!(f, "INC {}", target),
+            Instruction::DECW(target) => write!(f, "DEC {}", target),
+
+            Instruction::RLC(target) => write!(f, "RLC {}", target),
+            Instruction::RLCR() => write!(f, "RLC (HL)"),
+            Instruction::RRC(target) => write!(f, "RRC {}", target),
+            Instruction::RRCR() => write!(f, "RRC (HL)"),
+            Instruction::RL(target) => write!(f, "RL {}", target),
+            Instruction::RLR() => write!(f, "RL (HL)"),
+            Instruction::RR(target) => write!(f, "RR {}", target),
+            Instruction::RRR() => write!(f, "RR (HL)"),
+            Instruction::SLA(target) => write!(f, "SLA {}", target),
+            Instruction::SLAR() => write!(f, "SLA (HL)"),
+            Instruction::SRA(target) => write!(f, "SRA {}", target),
+            Instruction::SRAR() => write!(f, "SRA (HL)"),
+            Instruction::SWAP(target) => write!(f, "SWAP {}", target),
+            Instruction::SWAPR() => write!(f, "SWAP (HL)"),
+            Instruction::SRL(target) => write!(f, "SRL {}", target),
+            Instruction::SRLR() => write!(f, "SRL (HL)"),
+            Instruction::BIT(bit, source) => write!(f, "BIT {},{}", bit, source),
+            Instruction::BITR(bit) => write!(f, "BIT {},(HL)", bit),
+            Instruction::RES(bit, target) => write!(f, "RES {},{}", bit, target),
+            Instruction::RESR(bit) => write!(f, "RES {},(HL)", bit),
+            Instruction::SET(bit, target) => write!(f, "SET {},{}", bit, target),
+            Instruction::SETR(bit) => write!(f, "SET {},(HL)", bit),
+
+            Instruction::LD(target, source) => write!(f, "LD {},{}", target, source),
+            Instruction::LDI(target, immediate) => write!(f, "LD {},${:02X}", target, immediate),
+            Instruction::LDR(target) => write!(f, "LD {},(HL)", target),
+            Instruction::STR(source) => write!(f, "LD (HL),{}", source),
+            Instruction::STRI(immediate) => write!(f, "LD (HL),${:02X}", immediate),
+            Instruction::LDA(source) => write!(f, "{} A,{}", indirect_mnemonic(source), source),
+            Instruction::STA(target) => write!(f, "{} {},A", indirect_mnemonic(target), target),
+            Instruction::LDW(target, immediate) => write!(f, "LD {},${:04X}", target, immediate),
+            Instruction::STSP(address) => write!(f, "LD (${:04X}),SP", address),
+            Instruction::LDSPHL() => write!(f, "LD SP,HL"),
+            Instruction::LDHLSP(offset) => write!(f, "LD HL,SP{:+}", offset),
+
+            Instruction::JP(condition, address) => {
+                write!(f, "JP {}${:04X}", ConditionPrefix(condition), address)
+            }
+            Instruction::JPHL() => write!(f, "JP HL"),
+            Instruction::JR(condition, offset) => {
+                // The offset counts from the end of the two byte instruction.
+                let distance = offset as i16 + 2;
+                write!(f, "JR {}@{:+}", ConditionPrefix(condition), distance)
+            }
+            Instruction::CALL(condition, address) => {
+                write!(f, "CALL {}${:04X}", ConditionPrefix(condition), address)
+            }
+            Instruction::RET(JumpCondition::Always) => write!(f, "RET"),
+            Instruction::RET(condition) => write!(f, "RET {}", condition),
+            Instruction::RETI() => write!(f, "RETI"),
+            Instruction::RST(vector) => write!(f, "RST ${:02X}", vector),
+
+            Instruction::PUSH(source) => write!(f, "PUSH {}", source),
+            Instruction::POP(target) => write!(f, "POP {}", target),
+        }
+    }
+}
+
+/// The accesses to 0xFF00-0xFFFF have a mnemonic of their own.
+fn indirect_mnemonic(indirect: Indirect) -> &'static str {
+    match indirect {
+        Indirect::HighC | Indirect::HighImmediate(_) => "LDH",
+        _ => "LD",
+    }
+}
+
+/// A jump condition followed by the comma that separates it from the target, or nothing for
+/// unconditional jumps.
+struct ConditionPrefix(JumpCondition);
+
+impl fmt::Display for ConditionPrefix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            JumpCondition::Always => Ok(()),
+            condition => write!(f, "{},", condition),
+        }
+    }
+}
+
+impl fmt::Display for ArithmeticRegisters {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ArithmeticRegisters::A => "A",
+            ArithmeticRegisters::B => "B",
+            ArithmeticRegisters::C => "C",
+            ArithmeticRegisters::D => "D",
+            ArithmeticRegisters::E => "E",
+            ArithmeticRegisters::H => "H",
+            ArithmeticRegisters::L => "L",
+        };
+        f.write_str(name)
+    }
+}
+
+impl fmt::Display for WideRegisters {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            WideRegisters::BC => "BC",
+            WideRegisters::DE => "DE",
+            WideRegisters::HL => "HL",
+            WideRegisters::SP => "SP",
+        };
+        f.write_str(name)
+    }
+}
+
+impl fmt::Display for StackRegisters {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            StackRegisters::BC => "BC",
+            StackRegisters::DE => "DE",
+            StackRegisters::HL => "HL",
+            StackRegisters::AF => "AF",
+        };
+        f.write_str(name)
+    }
+}
+
+impl fmt::Display for Indirect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Indirect::BC => write!(f, "(BC)"),
+            Indirect::DE => write!(f, "(DE)"),
+            Indirect::HLIncrement => write!(f, "(HL+)"),
+            Indirect::HLDecrement => write!(f, "(HL-)"),
+            Indirect::HighC => write!(f, "(C)"),
+            Indirect::HighImmediate(offset) => write!(f, "($FF{:02X})", offset),
+            Indirect::Immediate(address) => write!(f, "(${:04X})", address),
+        }
+    }
+}
+
+/// Only the conditional forms are written out. `Always` has no text of its own.
+impl fmt::Display for JumpCondition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            JumpCondition::Always => "",
+            JumpCondition::NotZero => "NZ",
+            JumpCondition::Zero => "Z",
+            JumpCondition::NotCarry => "NC",
+            JumpCondition::Carry => "C",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Register encoded in the lowest three bits of an opcode, `None` meaning (HL).
+pub(crate) fn decode_operand(byte: u8) -> Option<ArithmeticRegisters> {
+    decode_register_index(byte & 0b111)
+}
+
+/// Register encoded in bits 3 to 5 of an opcode, `None` meaning (HL).
+pub(crate) fn decode_destination(byte: u8) -> Option<ArithmeticRegisters> {
+    decode_register_index((byte >> 3) & 0b111)
+}
+
+fn decode_register_index(index: u8) -> Option<ArithmeticRegisters> {
+    match index {
+        0 => Some(ArithmeticRegisters::B),
+        1 => Some(ArithmeticRegisters::C),
+        2 => Some(ArithmeticRegisters::D),
+        3 => Some(ArithmeticRegisters::E),
+        4 => Some(ArithmeticRegisters::H),
+        5 => Some(ArithmeticRegisters::L),
+        6 => None,
+        _ => Some(ArithmeticRegisters::A),
+    }
+}
+
+pub(crate) fn decode_wide_register(byte: u8) -> WideRegisters {
+    match (byte >> 4) & 0b11 {
+        0 => WideRegisters::BC,
+        1 => WideRegisters::DE,
+        2 => WideRegisters::HL,
+        _ => WideRegisters::SP,
+    }
+}
+
+pub(crate) fn decode_stack_register(byte: u8) -> StackRegisters {
+    match (byte >> 4) & 0b11 {
+        0 => StackRegisters::BC,
+        1 => StackRegisters::DE,
+        2 => StackRegisters::HL,
+        _ => StackRegisters::AF,
+    }
+}
+
+pub(crate) fn decode_condition(byte: u8) -> JumpCondition {
+    match (byte >> 3) & 0b11 {
+        0 => JumpCondition::NotZero,
+        1 => JumpCondition::Zero,
+        2 => JumpCondition::NotCarry,
+        _ => JumpCondition::Carry,
+    }
+}
+
+fn fetch_word<F: FnMut() -> u8>(next_byte: &mut F) -> u16 {
+    let low = next_byte() as u16;
+    let high = next_byte() as u16;
+    (high << 8) | low
+}
+
+/// Inverse of `decode_operand`.
+fn encode_operand(reg: Option<ArithmeticRegisters>) -> u8 {
+    encode_register_index(reg)
+}
+
+/// Inverse of `decode_destination`.
+fn encode_destination(reg: Option<ArithmeticRegisters>) -> u8 {
+    encode_register_index(reg) << 3
+}
+
+fn encode_register_index(reg: Option<ArithmeticRegisters>) -> u8 {
+    match reg {
+        Some(ArithmeticRegisters::B) => 0,
+        Some(ArithmeticRegisters::C) => 1,
+        Some(ArithmeticRegisters::D) => 2,
+        Some(ArithmeticRegisters::E) => 3,
+        Some(ArithmeticRegisters::H) => 4,
+        Some(ArithmeticRegisters::L) => 5,
+        None => 6,
+        Some(ArithmeticRegisters::A) => 7,
+    }
+}
+
+fn encode_bit(base: u8, bit: u8, reg: Option<ArithmeticRegisters>) -> u8 {
+    assert!(bit <= 7, "bit index {} is out of range", bit);
+    base | (bit & 0b111) << 3 | encode_operand(reg)
+}
+
+fn encode_wide_register(reg: WideRegisters) -> u8 {
+    match reg {
+        WideRegisters::BC => 0x00,
+        WideRegisters::DE => 0x10,
+        WideRegisters::HL => 0x20,
+        WideRegisters::SP => 0x30,
+    }
+}
+
+fn encode_stack_register(reg: StackRegisters) -> u8 {
+    match reg {
+        StackRegisters::BC => 0x00,
+        StackRegisters::DE => 0x10,
+        StackRegisters::HL => 0x20,
+        StackRegisters::AF => 0x30,
+    }
+}
+
+/// Condition bits of the conditional jumps, calls and returns. The unconditional forms have
+/// opcodes of their own.
+fn encode_condition(condition: JumpCondition) -> u8 {
+    match condition {
+        JumpCondition::NotZero => 0x00,
+        JumpCondition::Zero => 0x08,
+        JumpCondition::NotCarry => 0x10,
+        JumpCondition::Carry => 0x18,
+        JumpCondition::Always => unreachable!("unconditional forms have opcodes of their own"),
+    }
+}
+
+/// `opcode` followed by `word` in little endian.
+fn with_word(opcode: u8, word: u16) -> Encoding {
+    Encoding::new(&[opcode, word as u8, (word >> 8) as u8])
+}
+
+/// Machine code of one instruction, which never takes more than three bytes.
+/// Up to three bytes of machine code, as `Instruction::encoding` builds them.
+pub(crate) struct Encoding {
+    bytes: [u8; 3],
+    length: usize,
+}
+
+impl Encoding {
+    fn new(bytes: &[u8]) -> Self {
+        let mut encoding = Encoding {
+            bytes: [0; 3],
+            length: bytes.len(),
+        };
+        encoding.bytes[..bytes.len()].copy_from_slice(bytes);
+        encoding
+    }
+
+    pub(crate) fn as_slice(&self) -> &[u8] {
+        &self.bytes[..self.length]
+    }
+}
+
+#[cfg(test)]
+mod instructions_tests {
+    use super::*;
+
+    fn decode_bytes(bytes: &[u8]) -> Result<Instruction, DecodeError> {
+        let mut iter = bytes.iter();
+        Instruction::decode(|| *iter.next().expect("decoder read past the end"))
+    }
+
+    #[test]
+    fn test_decode_all_unprefixed_opcodes() {
+        for opcode in 0..=0xFFu8 {
+            let result = decode_bytes(&[opcode, 0, 0]);
+
+            if ILLEGAL_OPCODES.contains(&opcode) {
+                assert_eq!(result, Err(DecodeError::IllegalOpcode(opcode)));
+            } else {
+                assert!(result.is_ok(), "opcode {:#04x} did not decode", opcode);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_all_prefixed_opcodes() {
+        for opcode in 0..=0xFFu8 {
+            assert!(Instruction::from_byte(opcode, true, || 0).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_decode_alu_register_operands() {
+        assert_eq!(
+            decode_bytes(&[0x80]),
+            Ok(Instruction::ADD(ArithmeticRegisters::B))
+        );
+        assert_eq!(
+            decode_bytes(&[0x89]),
+            Ok(Instruction::ADC(ArithmeticRegisters::C))
+        );
+        assert_eq!(
+            decode_bytes(&[0x92]),
+            Ok(Instruction::SUB(ArithmeticRegisters::D))
+        );
+        assert_eq!(
+            decode_bytes(&[0x9B]),
+            Ok(Instruction::SBC(ArithmeticRegisters::E))
+        );
+        assert_eq!(
+            decode_bytes(&[0xA4]),
+            Ok(Instruction::AND(ArithmeticRegisters::H))
+        );
+        assert_eq!(
+            decode_bytes(&[0xAD]),
+            Ok(Instruction::XOR(ArithmeticRegisters::L))
+        );
+        assert_eq!(decode_bytes(&[0xB6]), Ok(Instruction::ORR()));
+        assert_eq!(
+            decode_bytes(&[0xBF]),
+            Ok(Instruction::CP(ArithmeticRegisters::A))
+        );
+    }
+
+    #[test]
+    fn test_decode_alu_immediates() {
+        assert_eq!(decode_bytes(&[0xC6, 0x12]), Ok(Instruction::ADDI(0x12)));
+        assert_eq!(decode_bytes(&[0xCE, 0x12]), Ok(Instruction::ADCI(0x12)));
+        assert_eq!(decode_bytes(&[0xD6, 0x12]), Ok(Instruction::SUBI(0x12)));
+        assert_eq!(decode_bytes(&[0xDE, 0x12]), Ok(Instruction::SBCI(0x12)));
+        assert_eq!(decode_bytes(&[0xE6, 0x12]), Ok(Instruction::ANDI(0x12)));
+        assert_eq!(decode_bytes(&[0xEE, 0x12]), Ok(Instruction::XORI(0x12)));
+        assert_eq!(decode_bytes(&[0xF6, 0x12]), Ok(Instruction::ORI(0x12)));
+        assert_eq!(decode_bytes(&[0xFE, 0x12]), Ok(Instruction::CPI(0x12)));
+    }
+
+    #[test]
+    fn test_decode_loads() {
+        assert_eq!(
+            decode_bytes(&[0x41]),
+            Ok(Instruction::LD(
+                ArithmeticRegisters::B,
+                ArithmeticRegisters::C
+            ))
+        );
+        assert_eq!(
+            decode_bytes(&[0x7E]),
+            Ok(Instruction::LDR(ArithmeticRegisters::A))
+        );
+        assert_eq!(
+            decode_bytes(&[0x70]),
+            Ok(Instruction::STR(ArithmeticRegisters::B))
+        );
+        assert_eq!(decode_bytes(&[0x36, 0x99]), Ok(Instruction::STRI(0x99)));
+        assert_eq!(
+            decode_bytes(&[0x2E, 0x99]),
+            Ok(Instruction::LDI(ArithmeticRegisters::L, 0x99))
+        );
+        assert_eq!(
+            decode_bytes(&[0x2A]),
+            Ok(Instruction::LDA(Indirect::HLIncrement))
+        );
+        assert_eq!(
+            decode_bytes(&[0x32]),
+            Ok(Instruction::STA(Indirect::HLDecrement))
+        );
+        assert_eq!(
+            decode_bytes(&[0xE0, 0x44]),
+            Ok(Instruction::STA(Indirect::HighImmediate(0x44)))
+        );
+        assert_eq!(decode_bytes(&[0xF2]), Ok(Instruction::LDA(Indirect::HighC)));
+    }
+
+    #[test]
+    fn test_decode_word_operands_are_little_endian() {
+        assert_eq!(
+            decode_bytes(&[0x31, 0xFE, 0xFF]),
+            Ok(Instruction::LDW(WideRegisters::SP, 0xFFFE))
+        );
+        assert_eq!(
+            decode_bytes(&[0x08, 0x34, 0x12]),
+            Ok(Instruction::STSP(0x1234))
+        );
+        assert_eq!(
+            decode_bytes(&[0xFA, 0x00, 0xC0]),
+            Ok(Instruction::LDA(Indirect::Immediate(0xC000)))
+        );
+        assert_eq!(
+            decode_bytes(&[0xC3, 0x50, 0x01]),
+            Ok(Instruction::JP(JumpCondition::Always, 0x0150))
+        );
+    }
+
+    #[test]
+    fn test_decode_control_flow() {
+        assert_eq!(
+            decode_bytes(&[0x20, 0xFE]),
+            Ok(Instruction::JR(JumpCondition::NotZero, -2))
+        );
+        assert_eq!(
+            decode_bytes(&[0xDC, 0x00, 0x40]),
+            Ok(Instruction::CALL(JumpCondition::Carry, 0x4000))
+        );
+        assert_eq!(
+            decode_bytes(&[0xC8]),
+            Ok(Instruction::RET(JumpCondition::Zero))
+        );
+        assert_eq!(
+            decode_bytes(&[0xC9]),
+            Ok(Instruction::RET(JumpCondition::Always))
+        );
+        assert_eq!(decode_bytes(&[0xD9]), Ok(Instruction::RETI()));
+        assert_eq!(decode_bytes(&[0xEF]), Ok(Instruction::RST(0x28)));
+        assert_eq!(
+            decode_bytes(&[0xF1]),
+            Ok(Instruction::POP(StackRegisters::AF))
+        );
+        assert_eq!(
+            decode_bytes(&[0xC5]),
+            Ok(Instruction::PUSH(StackRegisters::BC))
+        );
+    }
+
+    #[test]
+    fn test_decode_stop_consumes_padding_byte() {
+        let bytes = [0x10, 0x00, 0x3C];
+        let mut iter = bytes.iter();
+
+        let instruction = Instruction::decode(|| *iter.next().unwrap());
+        assert_eq!(instruction, Ok(Instruction::STOP()));
+        assert_eq!(iter.next(), Some(&0x3C));
+    }
+
+    #[test]
+    fn test_decode_prefixed_opcodes() {
+        assert_eq!(
+            decode_bytes(&[0xCB, 0x00]),
+            Ok(Instruction::RLC(ArithmeticRegisters::B))
+        );
+        assert_eq!(decode_bytes(&[0xCB, 0x1E]), Ok(Instruction::RRR()));
+        assert_eq!(
+            decode_bytes(&[0xCB, 0x37]),
+            Ok(Instruction::SWAP(ArithmeticRegisters::A))
+        );
+        assert_eq!(decode_bytes(&[0xCB, 0x3E]), Ok(Instruction::SRLR()));
+        assert_eq!(
+            decode_bytes(&[0xCB, 0x7C]),
+            Ok(Instruction::BIT(7, ArithmeticRegisters::H))
+        );
+        assert_eq!(decode_bytes(&[0xCB, 0x86]), Ok(Instruction::RESR(0)));
+        assert_eq!(
+            decode_bytes(&[0xCB, 0xFF]),
+            Ok(Instruction::SET(7, ArithmeticRegisters::A))
+        );
+    }
+
+    #[test]
+    fn test_from_byte_with_prefix_flag_matches_decode() {
+        for opcode in 0..=0xFFu8 {
+            assert_eq!(
+                Instruction::from_byte(opcode, true, || 0),
+                decode_bytes(&[PREFIX_BYTE, opcode])
+            );
+        }
+    }
+
+    #[test]
+    fn test_cycles_include_operand_and_prefix_fetches() {
+        assert_eq!(Instruction::ADD(ArithmeticRegisters::B).cycles(false), 1);
+        assert_eq!(Instruction::ADDI(0).cycles(false), 2);
+        assert_eq!(Instruction::ADDR().cycles(false), 2);
+        assert_eq!(Instruction::SWAP(ArithmeticRegisters::A).cycles(false), 2);
+        assert_eq!(Instruction::SWAPR().cycles(false), 4);
+        assert_eq!(Instruction::BITR(0).cycles(false), 3);
+        assert_eq!(Instruction::STA(Indirect::Immediate(0)).cycles(false), 4);
+    }
+
+    #[test]
+    fn test_cycles_of_taken_and_not_taken_branches_differ() {
+        assert_eq!(Instruction::JP(JumpCondition::Zero, 0).cycles(true), 4);
+        assert_eq!(Instruction::JP(JumpCondition::Zero, 0).cycles(false), 3);
+        assert_eq!(Instruction::JR(JumpCondition::Carry, 0).cycles(true), 3);
+        assert_eq!(Instruction::JR(JumpCondition::Carry, 0).cycles(false), 2);
+        assert_eq!(Instruction::CALL(JumpCondition::NotZero, 0).cycles(true), 6);
+        assert_eq!(
+            Instruction::CALL(JumpCondition::NotZero, 0).cycles(false),
+            3
+        );
+        assert_eq!(Instruction::RET(JumpCondition::NotCarry).cycles(true), 5);
+        assert_eq!(Instruction::RET(JumpCondition::NotCarry).cycles(false), 2);
+        assert_eq!(Instruction::RET(JumpCondition::Always).cycles(true), 4);
+    }
+
+    #[test]
+    fn test_display_alu_instructions() {
+        assert_eq!(
+            Instruction::ADD(ArithmeticRegisters::B).to_string(),
+            "ADD A,B"
+        );
+        assert_eq!(Instruction::ADCI(0x80).to_string(), "ADC A,$80");
+        assert_eq!(
+            Instruction::SUB(ArithmeticRegisters::L).to_string(),
+            "SUB L"
+        );
+        assert_eq!(Instruction::SBCR().to_string(), "SBC A,(HL)");
+        assert_eq!(Instruction::XORI(0x0F).to_string(), "XOR $0F");
+        assert_eq!(Instruction::CPR().to_string(), "CP (HL)");
+        assert_eq!(Instruction::INCR().to_string(), "INC (HL)");
+        assert_eq!(
+            Instruction::ADDHL(WideRegisters::SP).to_string(),
+            "ADD HL,SP"
+        );
+        assert_eq!(Instruction::ADDSP(-2).to_string(), "ADD SP,-2");
+        assert_eq!(Instruction::DECW(WideRegisters::DE).to_string(), "DEC DE");
+    }
+
+    #[test]
+    fn test_display_prefixed_instructions() {
+        assert_eq!(
+            Instruction::RLC(ArithmeticRegisters::C).to_string(),
+            "RLC C"
+        );
+        assert_eq!(Instruction::SWAPR().to_string(), "SWAP (HL)");
+        assert_eq!(
+            Instruction::BIT(7, ArithmeticRegisters::H).to_string(),
+            "BIT 7,H"
+        );
+        assert_eq!(Instruction::RESR(0).to_string(), "RES 0,(HL)");
+    }
+
+    #[test]
+    fn test_display_loads() {
+        assert_eq!(
+            Instruction::LD(ArithmeticRegisters::D, ArithmeticRegisters::E).to_string(),
+            "LD D,E"
+        );
+        assert_eq!(
+            Instruction::LDI(ArithmeticRegisters::B, 0x05).to_string(),
+            "LD B,$05"
+        );
+        assert_eq!(Instruction::STRI(0xAA).to_string(), "LD (HL),$AA");
+        assert_eq!(
+            Instruction::LDA(Indirect::HLIncrement).to_string(),
+            "LD A,(HL+)"
+        );
+        assert_eq!(
+            Instruction::STA(Indirect::HLDecrement).to_string(),
+            "LD (HL-),A"
+        );
+        assert_eq!(Instruction::LDA(Indirect::HighC).to_string(), "LDH A,(C)");
+        assert_eq!(
+            Instruction::STA(Indirect::HighImmediate(0x80)).to_string(),
+            "LDH ($FF80),A"
+        );
+        assert_eq!(
+            Instruction::LDA(Indirect::Immediate(0xC000)).to_string(),
+            "LD A,($C000)"
+        );
+        assert_eq!(
+            Instruction::LDW(WideRegisters::HL, 0x9800).to_string(),
+            "LD HL,$9800"
+        );
+        assert_eq!(Instruction::STSP(0xC100).to_string(), "LD ($C100),SP");
+        assert_eq!(Instruction::LDHLSP(5).to_string(), "LD HL,SP+5");
+        assert_eq!(Instruction::LDHLSP(-128).to_string(), "LD HL,SP-128");
+    }
+
+    #[test]
+    fn test_display_control_flow() {
+        assert_eq!(
+            Instruction::JP(JumpCondition::Always, 0x0150).to_string(),
+            "JP $0150"
+        );
+        assert_eq!(
+            Instruction::CALL(JumpCondition::NotCarry, 0x4000).to_string(),
+            "CALL NC,$4000"
+        );
+        assert_eq!(
+            Instruction::JR(JumpCondition::Always, -2).to_string(),
+            "JR @+0"
+        );
+        assert_eq!(
+            Instruction::JR(JumpCondition::Zero, 5).to_string(),
+            "JR Z,@+7"
+        );
+        assert_eq!(Instruction::RET(JumpCondition::Always).to_string(), "RET");
+        assert_eq!(Instruction::RET(JumpCondition::Carry).to_string(), "RET C");
+        assert_eq!(Instruction::RST(0x38).to_string(), "RST $38");
+        assert_eq!(Instruction::PUSH(StackRegisters::AF).to_string(), "PUSH AF");
+    }
+
+    #[test]
+    fn test_encode_examples() {
+        assert_eq!(Instruction::NOP().encode(), vec![0x00]);
+        assert_eq!(Instruction::STOP().encode(), vec![0x10, 0x00]);
+        assert_eq!(Instruction::ADDI(0x12).encode(), vec![0xC6, 0x12]);
+        assert_eq!(Instruction::SBCR().encode(), vec![0x9E]);
+        assert_eq!(
+            Instruction::LD(ArithmeticRegisters::H, ArithmeticRegisters::A).encode(),
+            vec![0x67]
+        );
+        assert_eq!(
+            Instruction::LDW(WideRegisters::SP, 0xFFFE).encode(),
+            vec![0x31, 0xFE, 0xFF]
+        );
+        assert_eq!(
+            Instruction::STA(Indirect::HighImmediate(0x40)).encode(),
+            vec![0xE0, 0x40]
+        );
+        assert_eq!(
+            Instruction::JR(JumpCondition::Carry, -2).encode(),
+            vec![0x38, 0xFE]
+        );
+        assert_eq!(Instruction::RST(0x28).encode(), vec![0xEF]);
+        assert_eq!(Instruction::POP(StackRegisters::AF).encode(), vec![0xF1]);
+        assert_eq!(Instruction::SETR(5).encode(), vec![PREFIX_BYTE, 0xEE]);
+    }
+
+    const REGISTERS: [ArithmeticRegisters; 7] = [
+        ArithmeticRegisters::A,
+        ArithmeticRegisters::B,
+        ArithmeticRegisters::C,
+        ArithmeticRegisters::D,
+        ArithmeticRegisters::E,
+        ArithmeticRegisters::H,
+        ArithmeticRegisters::L,
+    ];
+
+    const WIDE_REGISTERS: [WideRegisters; 4] = [
+        WideRegisters::BC,
+        WideRegisters::DE,
+        WideRegisters::HL,
+        WideRegisters::SP,
+    ];
+
+    const STACK_REGISTERS: [StackRegisters; 4] = [
+        StackRegisters::BC,
+        StackRegisters::DE,
+        StackRegisters::HL,
+        StackRegisters::AF,
+    ];
+
+    const CONDITIONS: [JumpCondition; 5] = [
+        JumpCondition::Always,
+        JumpCondition::NotZero,
+        JumpCondition::Zero,
+        JumpCondition::NotCarry,
+        JumpCondition::Carry,
+    ];
+
+    /// Every value of `Instruction` that has an encoding, with every operand value.
+    fn all_instructions() -> Vec<Instruction> {
+        let mut all = vec![
+            Instruction::NOP(),
+            Instruction::STOP(),
+            Instruction::HALT(),
+            Instruction::DI(),
+            Instruction::EI(),
+            Instruction::ADDR(),
+            Instruction::ADCR(),
+            Instruction::SUBR(),
+            Instruction::SBCR(),
+            Instruction::ANDR(),
+            Instruction::ORR(),
+            Instruction::XORR(),
+            Instruction::CPR(),
+            Instruction::INCR(),
+            Instruction::DECR(),
+            Instruction::DAA(),
+            Instruction::CPL(),
+            Instruction::SCF(),
+            Instruction::CCF(),
+            Instruction::RLCA(),
+            Instruction::RRCA(),
+            Instruction::RLA(),
+            Instruction::RRA(),
+            Instruction::RLCR(),
+            Instruction::RRCR(),
+            Instruction::RLR(),
+            Instruction::RRR(),
+            Instruction::SLAR(),
+            Instruction::SRAR(),
+            Instruction::SWAPR(),
+            Instruction::SRLR(),
+            Instruction::LDSPHL(),
+            Instruction::JPHL(),
+            Instruction::RETI(),
+        ];
+
+        for &indirect in &[
+            Indirect::BC,
+            Indirect::DE,
+            Indirect::HLIncrement,
+            Indirect::HLDecrement,
+            Indirect::HighC,
+        ] {
+            all.push(Instruction::LDA(indirect));
+            all.push(Instruction::STA(indirect));
+        }
+
+        for &reg in &REGISTERS {
+            all.extend_from_slice(&[
+                Instruction::ADD(reg),
+                Instruction::ADC(reg),
+                Instruction::SUB(reg),
+                Instruction::SBC(reg),
+                Instruction::AND(reg),
+                Instruction::OR(reg),
+                Instruction::XOR(reg),
+                Instruction::CP(reg),
+                Instruction::INC(reg),
+                Instruction::DEC(reg),
+                Instruction::RLC(reg),
+                Instruction::RRC(reg),
+                Instruction::RL(reg),
+                Instruction::RR(reg),
+                Instruction::SLA(reg),
+                Instruction::SRA(reg),
+                Instruction::SWAP(reg),
+                Instruction::SRL(reg),
+                Instruction::LDR(reg),
+                Instruction::STR(reg),
+            ]);
+            for &source in &REGISTERS {
+                all.push(Instruction::LD(reg, source));
+            }
+        }
+
+        for bit in 0..8 {
+            all.extend_from_slice(&[
+                Instruction::BITR(bit),
+                Instruction::RESR(bit),
+                Instruction::SETR(bit),
+            ]);
+            for &reg in &REGISTERS {
+                all.extend_from_slice(&[
+                    Instruction::BIT(bit, reg),
+                    Instruction::RES(bit, reg),
+                    Instruction::SET(bit, reg),
+                ]);
+            }
+        }
+
+        for byte in 0..=0xFFu8 {
+            all.extend_from_slice(&[
+                Instruction::ADDI(byte),
+                Instruction::ADCI(byte),
+                Instruction::SUBI(byte),
+                Instruction::SBCI(byte),
+                Instruction::ANDI(byte),
+                Instruction::ORI(byte),
+                Instruction::XORI(byte),
+                Instruction::CPI(byte),
+                Instruction::STRI(byte),
+                Instruction::LDA(Indirect::HighImmediate(byte)),
+                Instruction::STA(Indirect::HighImmediate(byte)),
+                Instruction::ADDSP(byte as i8),
+                Instruction::LDHLSP(byte as i8),
+            ]);
+            for &reg in &REGISTERS {
+                all.push(Instruction::LDI(reg, byte));
+            }
+            for &condition in &CONDITIONS {
+                all.push(Instruction::JR(condition, byte as i8));
+            }
+        }
+
+        for word in 0..=0xFFFFu16 {
+            all.extend_from_slice(&[
+                Instruction::STSP(word),
+                Instruction::LDA(Indirect::Immediate(word)),
+                Instruction::STA(Indirect::Immediate(word)),
+            ]);
+            for &reg in &WIDE_REGISTERS {
+                all.push(Instruction::LDW(reg, word));
+            }
+            for &condition in &CONDITIONS {
+                all.push(Instruction::JP(condition, word));
+                all.push(Instruction::CALL(condition, word));
+            }
+        }
+
+        for &reg in &WIDE_REGISTERS {
+            all.extend_from_slice(&[
+                Instruction::ADDHL(reg),
+                Instruction::INCW(reg),
+                Instruction::DECW(reg),
+            ]);
+        }
+        for &reg in &STACK_REGISTERS {
+            all.push(Instruction::PUSH(reg));
+            all.push(Instruction::POP(reg));
+        }
+        for &condition in &CONDITIONS {
+            all.push(Instruction::RET(condition));
+        }
+        for vector in (0x00..=0x38).step_by(8) {
+            all.push(Instruction::RST(vector));
+        }
+
+        all
+    }
+
+    #[test]
+    fn test_decode_of_encode_is_identity_for_every_instruction() {
+        let mut unprefixed_seen = [false; 256];
+        let mut prefixed_seen = [false; 256];
+
+        for instruction in all_instructions() {
+            let bytes = instruction.encode();
+            assert_eq!(bytes.len(), instruction.length(), "{:?}", instruction);
+
+            let mut consumed = 0;
+            let decoded = Instruction::decode(|| {
+                consumed += 1;
+                bytes[consumed - 1]
+            });
+            assert_eq!(decoded, Ok(instruction), "{:02X?}", bytes);
+            assert_eq!(consumed, bytes.len(), "{:?}", instruction);
+
+            if bytes[0] == PREFIX_BYTE {
+                prefixed_seen[bytes[1] as usize] = true;
+            } else {
+                unprefixed_seen[bytes[0] as usize] = true;
+            }
+        }
+
+        for opcode in 0..=0xFFu8 {
+            let legal = !ILLEGAL_OPCODES.contains(&opcode) && opcode != PREFIX_BYTE;
+            assert_eq!(unprefixed_seen[opcode as usize], legal, "{:#04x}", opcode);
+            assert_eq!(prefixed_seen[opcode as usize], true, "0xcb {:#04x}", opcode);
+        }
+    }
+
+    #[test]
+    fn test_encode_of_decode_reproduces_the_bytes() {
+        for opcode in 0..=0xFFu8 {
+            if ILLEGAL_OPCODES.contains(&opcode) {
+                continue;
+            }
+
+            let bytes = [opcode, 0x9A, 0xBC];
+            let instruction = decode_bytes(&bytes).unwrap();
+            let length = instruction.length();
+
+            // STOP always encodes its padding byte as zero.
+            if opcode != 0x10 {
+                assert_eq!(
+                    instruction.encode(),
+                    bytes[..length].to_vec(),
+                    "{:?}",
+                    instruction
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_encode_into_writes_to_start_of_buffer() {
+        let mut buffer = [0xFF; 4];
+
+        let length = Instruction::CALL(JumpCondition::Zero, 0x1234).encode_into(&mut buffer);
+
+        assert_eq!(length, 3);
+        assert_eq!(buffer, [0xCC, 0x34, 0x12, 0xFF]);
+    }
+
+    /// Operands no opcode can hold must not quietly encode as another instruction.
+    #[test]
+    fn test_encode_catches_out_of_range_operands() {
+        let invalid = [
+            Instruction::RST(0x09),
+            Instruction::RST(0x40),
+            Instruction::BIT(9, ArithmeticRegisters::B),
+            Instruction::BITR(8),
+            Instruction::RES(8, ArithmeticRegisters::A),
+            Instruction::SETR(0xFF),
+        ];
+
+        for instruction in invalid.iter() {
+            let encode = std::panic::catch_unwind(|| instruction.encode());
+            assert!(
+                encode.is_err(),
+                "{:?} encoded as {:02X?}",
+                instruction,
+                encode
+            );
+
+            let length = std::panic::catch_unwind(|| instruction.length());
+            assert!(length.is_err(), "{:?}", instruction);
+
+            let info = std::panic::catch_unwind(|| instruction.info().mnemonic);
+            assert!(info.is_err(), "{:?} has info of {:?}", instruction, info);
+        }
+    }
+}
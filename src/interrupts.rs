@@ -0,0 +1,98 @@
+pub const INTERRUPT_FLAG_ADDRESS: u16 = 0xFF0F;
+
+/// Only the lower five bits of IF and IE correspond to interrupt sources.
+pub const INTERRUPT_MASK: u8 = 0x1F;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interrupt {
+    VBlank,
+    LcdStat,
+    Timer,
+    Serial,
+    Joypad,
+}
+
+impl Interrupt {
+    /// Interrupts ordered from highest to lowest priority.
+    pub const ALL: [Interrupt; 5] = [
+        Interrupt::VBlank,
+        Interrupt::LcdStat,
+        Interrupt::Timer,
+        Interrupt::Serial,
+        Interrupt::Joypad,
+    ];
+
+    /// Bit of the interrupt in the IF and IE registers.
+    pub fn mask(self) -> u8 {
+        match self {
+            Interrupt::VBlank => 1 << 0,
+            Interrupt::LcdStat => 1 << 1,
+            Interrupt::Timer => 1 << 2,
+            Interrupt::Serial => 1 << 3,
+            Interrupt::Joypad => 1 << 4,
+        }
+    }
+
+    /// Address the CPU jumps to when dispatching the interrupt.
+    pub fn vector(self) -> u16 {
+        match self {
+            Interrupt::VBlank => 0x0040,
+            Interrupt::LcdStat => 0x0048,
+            Interrupt::Timer => 0x0050,
+            Interrupt::Serial => 0x0058,
+            Interrupt::Joypad => 0x0060,
+        }
+    }
+
+    /// The interrupt that gets dispatched first out of the bits set in `pending`.
+    pub fn highest_priority(pending: u8) -> Option<Interrupt> {
+        Interrupt::ALL
+            .iter()
+            .copied()
+            .find(|interrupt| pending & interrupt.mask() != 0)
+    }
+}
+
+#[cfg(test)]
+mod interrupts_tests {
+    use super::*;
+
+    #[test]
+    fn test_highest_priority_picks_lowest_bit() {
+        assert_eq!(
+            Interrupt::highest_priority(0b1_1111),
+            Some(Interrupt::VBlank)
+        );
+        assert_eq!(
+            Interrupt::highest_priority(0b1_1110),
+            Some(Interrupt::LcdStat)
+        );
+        assert_eq!(
+            Interrupt::highest_priority(0b1_1100),
+            Some(Interrupt::Timer)
+        );
+        assert_eq!(
+            Interrupt::highest_priority(0b1_1000),
+            Some(Interrupt::Serial)
+        );
+        assert_eq!(
+            Interrupt::highest_priority(0b1_0000),
+            Some(Interrupt::Joypad)
+        );
+    }
+
+    #[test]
+    fn test_highest_priority_without_pending_interrupts() {
+        assert_eq!(Interrupt::highest_priority(0), None);
+        assert_eq!(Interrupt::highest_priority(0b1110_0000), None);
+    }
+
+    #[test]
+    fn test_vectors() {
+        assert_eq!(Interrupt::VBlank.vector(), 0x40);
+        assert_eq!(Interrupt::LcdStat.vector(), 0x48);
+        assert_eq!(Interrupt::Timer.vector(), 0x50);
+        assert_eq!(Interrupt::Serial.vector(), 0x58);
+        assert_eq!(Interrupt::Joypad.vector(), 0x60);
+    }
+}
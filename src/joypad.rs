@@ -0,0 +1,139 @@
+pub const JOYPAD_ADDRESS: u16 = 0xFF00;
+
+const SELECT_DIRECTIONS: u8 = 1 << 4;
+const SELECT_BUTTONS: u8 = 1 << 5;
+const SELECT_MASK: u8 = SELECT_DIRECTIONS | SELECT_BUTTONS;
+
+/// State of the eight inputs, `true` meaning held down.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Buttons {
+    pub right: bool,
+    pub left: bool,
+    pub up: bool,
+    pub down: bool,
+    pub a: bool,
+    pub b: bool,
+    pub select: bool,
+    pub start: bool,
+}
+
+/// The P1 register. The game selects the direction keys, the action buttons or both by
+/// clearing bit 4 or 5, and reads the selected inputs from the lower nibble, where a pressed
+/// input reads as 0.
+pub struct Joypad {
+    buttons: Buttons,
+    select: u8,
+}
+
+impl Default for Joypad {
+    fn default() -> Self {
+        Joypad::new()
+    }
+}
+
+impl Joypad {
+    pub fn new() -> Self {
+        Joypad {
+            buttons: Buttons::default(),
+            select: SELECT_MASK,
+        }
+    }
+
+    pub fn read_byte(&self) -> u8 {
+        0xC0 | self.select | self.input_lines()
+    }
+
+    pub fn write_byte(&mut self, value: u8) {
+        self.select = value & SELECT_MASK;
+    }
+
+    /// Replaces the held inputs and returns whether that pulled one of the selected lines low,
+    /// which requests the joypad interrupt.
+    pub fn set_buttons(&mut self, buttons: Buttons) -> bool {
+        let old_lines = self.input_lines();
+        self.buttons = buttons;
+        old_lines & !self.input_lines() != 0
+    }
+
+    fn input_lines(&self) -> u8 {
+        let mut pressed = 0;
+        if self.select & SELECT_DIRECTIONS == 0 {
+            pressed |= nibble(
+                self.buttons.right,
+                self.buttons.left,
+                self.buttons.up,
+                self.buttons.down,
+            );
+        }
+        if self.select & SELECT_BUTTONS == 0 {
+            pressed |= nibble(
+                self.buttons.a,
+                self.buttons.b,
+                self.buttons.select,
+                self.buttons.start,
+            );
+        }
+        !pressed & 0x0F
+    }
+}
+
+fn nibble(bit0: bool, bit1: bool, bit2: bool, bit3: bool) -> u8 {
+    (bit0 as u8) | (bit1 as u8) << 1 | (bit2 as u8) << 2 | (bit3 as u8) << 3
+}
+
+#[cfg(test)]
+mod joypad_tests {
+    use super::*;
+
+    #[test]
+    fn test_nothing_selected_reads_all_released() {
+        let mut joypad = Joypad::new();
+
+        joypad.set_buttons(Buttons {
+            a: true,
+            right: true,
+            ..Buttons::default()
+        });
+
+        assert_eq!(joypad.read_byte(), 0xFF);
+    }
+
+    #[test]
+    fn test_read_selected_group() {
+        let mut joypad = Joypad::new();
+
+        joypad.set_buttons(Buttons {
+            right: true,
+            down: true,
+            b: true,
+            ..Buttons::default()
+        });
+
+        joypad.write_byte(SELECT_BUTTONS);
+        assert_eq!(joypad.read_byte(), 0xE6);
+
+        joypad.write_byte(SELECT_DIRECTIONS);
+        assert_eq!(joypad.read_byte(), 0xDD);
+
+        joypad.write_byte(0x00);
+        assert_eq!(joypad.read_byte(), 0xC4);
+    }
+
+    #[test]
+    fn test_pressing_a_selected_input_requests_interrupt() {
+        let mut joypad = Joypad::new();
+
+        joypad.write_byte(SELECT_BUTTONS);
+
+        let start = Buttons {
+            start: true,
+            ..Buttons::default()
+        };
+        assert_eq!(joypad.set_buttons(start), false);
+
+        let up = Buttons { up: true, ..start };
+        assert_eq!(joypad.set_buttons(up), true);
+        assert_eq!(joypad.set_buttons(up), false);
+        assert_eq!(joypad.set_buttons(Buttons::default()), false);
+    }
+}
@@ -1,26 +1,320 @@
+#![cfg_attr(
+    test,
+    allow(clippy::bool_assert_comparison, clippy::field_reassign_with_default)
+)]
+
+pub mod assembler;
+pub mod cartridge;
+pub mod disassembler;
+mod dispatch;
 pub mod flagsregister;
+pub mod gameboy;
 pub mod instructions;
+pub mod interrupts;
+pub mod joypad;
+pub mod mbc1;
+pub mod mbc2;
+pub mod mbc3;
+pub mod mbc5;
+pub mod memorybus;
+pub mod opcodes;
 pub mod registers;
+pub mod timer;
 
-use self::flagsregister::{
-    FlagsRegister, CARRY_FLAG_POSITION, HALF_CARRY_FLAG_POSITION, OPERATION_FLAG_POSITION,
-    ZERO_FLAG_POSITION,
+use self::dispatch::UNPREFIXED_HANDLERS;
+#[cfg(any(test, feature = "bench"))]
+use self::instructions::Instruction;
+use self::instructions::{
+    ArithmeticRegisters, DecodeError, Indirect, JumpCondition, StackRegisters, WideRegisters,
 };
-use self::instructions::{ArithmeticRegisters, Instruction};
+use self::interrupts::Interrupt;
+use self::memorybus::MemoryBus;
 use self::registers::Registers;
 
+pub use self::gameboy::GameBoy;
+pub use self::joypad::Buttons;
+
+#[allow(clippy::upper_case_acronyms)]
 struct CPU {
     registers: Registers,
+    bus: MemoryBus,
+    timing_mode: TimingMode,
+    error_policy: ErrorPolicy,
+    /// Interrupt master enable.
+    ime: bool,
+    /// Set by EI, which only enables interrupts after the instruction that follows it.
+    ime_scheduled: bool,
+    halted: bool,
+    /// Set when HALT is executed with IME off and an interrupt already pending. The CPU then
+    /// fails to increment PC after the next opcode fetch, so that byte is read twice.
+    halt_bug: bool,
+    /// Address and value of the illegal opcode that froze the CPU for good.
+    locked_up: Option<(u16, u8)>,
+    /// Machine cycles elapsed since power on.
+    cycles: u64,
+    /// Machine cycles taken so far by the current step.
+    step_cycles: u8,
+}
+
+/// When the peripherals on the bus are advanced relative to the CPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimingMode {
+    /// Peripherals catch up once the whole instruction has executed.
+    Fast,
+    /// Peripherals advance one machine cycle at a time in lockstep with the CPU, so every
+    /// memory access sees them as they are on the cycle the access happens.
+    Precise,
+}
+
+/// What `step` does with instructions it cannot execute normally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Return an `ExecutionError`. Illegal opcodes still lock up the CPU, so every later step
+    /// fails with `ExecutionError::LockedUp`.
+    Report,
+    /// Carry on as if the instruction were a NOP.
+    Nop,
+    /// Behave like the hardware without reporting anything. Illegal opcodes freeze the CPU and
+    /// STOP does nothing, as the low power mode it enters is not emulated.
+    Hardware,
+}
+
+/// Why `step` failed at the instruction at `pc`, whose first byte is `opcode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionError {
+    /// The instruction decoded but the CPU cannot execute it. Every instruction that decodes is
+    /// implemented at the moment.
+    UnimplementedInstruction {
+        pc: u16,
+        opcode: u8,
+    },
+    IllegalOpcode {
+        pc: u16,
+        opcode: u8,
+    },
+    /// The CPU is stuck at the illegal opcode that locked it up.
+    LockedUp {
+        pc: u16,
+        opcode: u8,
+    },
+    StopEncountered {
+        pc: u16,
+        opcode: u8,
+    },
+}
+
+impl std::fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            ExecutionError::UnimplementedInstruction { pc, opcode } => {
+                write!(
+                    f,
+                    "unimplemented instruction {:#04x} at {:#06x}",
+                    opcode, pc
+                )
+            }
+            ExecutionError::IllegalOpcode { pc, opcode } => {
+                write!(f, "illegal opcode {:#04x} at {:#06x}", opcode, pc)
+            }
+            ExecutionError::LockedUp { pc, opcode } => write!(
+                f,
+                "CPU locked up by illegal opcode {:#04x} at {:#06x}",
+                opcode, pc
+            ),
+            ExecutionError::StopEncountered { pc, opcode } => {
+                write!(f, "STOP {:#04x} encountered at {:#06x}", opcode, pc)
+            }
+        }
+    }
 }
 
+impl std::error::Error for ExecutionError {}
+
+/// Two wait cycles, two to push PC and one to jump to the vector.
+const INTERRUPT_DISPATCH_CYCLES: u8 = 5;
+
+const STOP_OPCODE: u8 = 0x10;
+
 impl CPU {
     fn new() -> Self {
         CPU {
             registers: Registers::new(),
+            bus: MemoryBus::new(),
+            timing_mode: TimingMode::Fast,
+            error_policy: ErrorPolicy::Report,
+            ime: false,
+            ime_scheduled: false,
+            halted: false,
+            halt_bug: false,
+            locked_up: None,
+            cycles: 0,
+            step_cycles: 0,
+        }
+    }
+
+    /// Dispatches a pending interrupt or fetches, decodes and executes the next instruction, and
+    /// returns the machine cycles that took. The cycles of a failed step still elapse.
+    fn step(&mut self) -> Result<u8, ExecutionError> {
+        self.step_with(|cpu, opcode| UNPREFIXED_HANDLERS[opcode as usize](cpu, opcode))
+    }
+
+    /// Like `step`, but decodes each instruction into an `Instruction` and runs it through
+    /// `execute`, the way the CPU worked before the dispatch tables. Kept so benchmarks can
+    /// compare the two.
+    #[cfg(any(test, feature = "bench"))]
+    fn step_decoded(&mut self) -> Result<u8, ExecutionError> {
+        self.step_with(|cpu, opcode| {
+            let instruction = Instruction::from_byte(opcode, false, || cpu.fetch_byte())?;
+            let cycles = cpu.execute(instruction);
+            debug_assert_eq!(cpu.step_cycles, cycles, "{:?}", instruction);
+            Ok(())
+        })
+    }
+
+    /// Steps the CPU, with `execute_opcode` running the instruction whose first byte was just
+    /// fetched.
+    fn step_with<F>(&mut self, execute_opcode: F) -> Result<u8, ExecutionError>
+    where
+        F: FnOnce(&mut CPU, u8) -> Result<(), DecodeError>,
+    {
+        self.step_cycles = 0;
+        let result = self.step_instruction(execute_opcode);
+
+        let cycles = self.step_cycles;
+        if self.timing_mode == TimingMode::Fast {
+            self.bus.tick(cycles);
+        }
+        self.cycles += cycles as u64;
+
+        result.map(|()| cycles)
+    }
+
+    fn step_instruction<F>(&mut self, execute_opcode: F) -> Result<(), ExecutionError>
+    where
+        F: FnOnce(&mut CPU, u8) -> Result<(), DecodeError>,
+    {
+        if let Some((pc, opcode)) = self.locked_up {
+            self.internal_cycle();
+            return match self.error_policy {
+                ErrorPolicy::Report => Err(ExecutionError::LockedUp { pc, opcode }),
+                _ => Ok(()),
+            };
+        }
+        if self.handle_interrupts() {
+            return Ok(());
+        }
+        if self.halted {
+            self.internal_cycle();
+            return Ok(());
+        }
+
+        let enable_ime = self.ime_scheduled;
+        let pc = self.registers.pc;
+        let opcode = self.fetch_byte();
+
+        let result = match execute_opcode(self, opcode) {
+            Ok(()) if opcode == STOP_OPCODE && self.error_policy == ErrorPolicy::Report => {
+                Err(ExecutionError::StopEncountered { pc, opcode })
+            }
+            Ok(()) => Ok(()),
+            Err(DecodeError::IllegalOpcode(opcode)) => match self.error_policy {
+                ErrorPolicy::Report => {
+                    self.locked_up = Some((pc, opcode));
+                    Err(ExecutionError::IllegalOpcode { pc, opcode })
+                }
+                ErrorPolicy::Nop => Ok(()),
+                ErrorPolicy::Hardware => {
+                    self.locked_up = Some((pc, opcode));
+                    Ok(())
+                }
+            },
+        };
+
+        // A DI right after EI cancels the scheduled enable.
+        if enable_ime && self.ime_scheduled {
+            self.ime = true;
+            self.ime_scheduled = false;
+        }
+
+        result
+    }
+
+    /// Reads `address` on a machine cycle of its own.
+    fn read_byte(&mut self, address: u16) -> u8 {
+        self.tick();
+        self.bus.read_byte(address)
+    }
+
+    /// Writes `address` on a machine cycle of its own.
+    fn write_byte(&mut self, address: u16, value: u8) {
+        self.tick();
+        self.bus.write_byte(address, value);
+    }
+
+    /// A machine cycle the CPU spends without using the bus.
+    fn internal_cycle(&mut self) {
+        self.tick();
+    }
+
+    fn tick(&mut self) {
+        self.step_cycles += 1;
+        if self.timing_mode == TimingMode::Precise {
+            self.bus.tick(1);
+        }
+    }
+
+    fn fetch_byte(&mut self) -> u8 {
+        let byte = self.read_byte(self.registers.pc);
+        if self.halt_bug {
+            self.halt_bug = false;
+        } else {
+            self.registers.pc = self.registers.pc.wrapping_add(1);
+        }
+        byte
+    }
+
+    /// Wakes the CPU from HALT when an interrupt is pending and, if IME is set, jumps to the
+    /// handler of the one with the highest priority. Returns whether an interrupt was dispatched.
+    fn handle_interrupts(&mut self) -> bool {
+        let pending = self.bus.pending_interrupts();
+        if pending == 0 {
+            return false;
+        }
+
+        // Pending interrupts end HALT even when IME is off.
+        self.halted = false;
+
+        let interrupt = match Interrupt::highest_priority(pending) {
+            Some(interrupt) if self.ime => interrupt,
+            _ => return false,
+        };
+
+        // With EI right before a buggy HALT the handler returns to the HALT itself.
+        if self.halt_bug {
+            self.halt_bug = false;
+            self.registers.pc = self.registers.pc.wrapping_sub(1);
         }
+
+        self.ime = false;
+        self.bus.acknowledge_interrupt(interrupt);
+
+        self.internal_cycle();
+        self.internal_cycle();
+        self.push(self.registers.pc);
+        self.registers.pc = interrupt.vector();
+        self.internal_cycle();
+
+        debug_assert_eq!(self.step_cycles, INTERRUPT_DISPATCH_CYCLES);
+        true
     }
 
-    fn execute(&mut self, instruction: Instruction) {
+    /// Executes an already decoded `instruction` and returns the machine cycles it took. `step`
+    /// goes through the dispatch tables instead; this is the reference they are tested against,
+    /// and what `step_decoded` runs.
+    #[cfg(any(test, feature = "bench"))]
+    fn execute(&mut self, instruction: Instruction) -> u8 {
+        let mut branch_taken = false;
+
         match instruction {
             Instruction::ADD(reg) => {
                 self.execute_add_reg(reg);
@@ -28,44 +322,259 @@ impl CPU {
             Instruction::ADDI(immediate) => {
                 self.execute_add_immediate(immediate);
             }
+            Instruction::ADDR() => {
+                self.execute_add_relative();
+            }
             Instruction::ADC(reg) => {
                 self.execute_adc_reg(reg);
             }
             Instruction::ADCI(immediate) => {
                 self.execute_adc_immediate(immediate);
             }
+            Instruction::ADCR() => {
+                self.execute_adc_relative();
+            }
             Instruction::SUB(reg) => {
                 self.execute_sub_reg(reg);
             }
             Instruction::SUBI(immediate) => {
                 self.execute_sub_immediate(immediate);
             }
+            Instruction::SUBR() => {
+                self.execute_sub_relative();
+            }
             Instruction::SBC(reg) => {
                 self.execute_sbc_reg(reg);
             }
             Instruction::SBCI(immediate) => {
                 self.execute_sbc_immediate(immediate);
             }
+            Instruction::SBCR() => {
+                self.execute_sbc_relative();
+            }
             Instruction::AND(reg) => {
                 self.execute_and_reg(reg);
             }
             Instruction::ANDI(immediate) => {
                 self.execute_and_immediate(immediate);
             }
+            Instruction::ANDR() => {
+                self.execute_and_relative();
+            }
             Instruction::OR(reg) => {
                 self.execute_or_reg(reg);
             }
             Instruction::ORI(immediate) => {
                 self.execute_or_immediate(immediate);
             }
+            Instruction::ORR() => {
+                self.execute_or_relative();
+            }
             Instruction::XOR(reg) => {
                 self.execute_xor_reg(reg);
             }
             Instruction::XORI(immediate) => {
                 self.execute_xor_immediate(immediate);
             }
-            _ => { /* TODO: support more instructions */ }
+            Instruction::XORR() => {
+                self.execute_xor_relative();
+            }
+            Instruction::CP(reg) => {
+                self.execute_cp_reg(reg);
+            }
+            Instruction::CPI(immediate) => {
+                self.execute_cp_immediate(immediate);
+            }
+            Instruction::CPR() => {
+                self.execute_cp_relative();
+            }
+            Instruction::RLC(reg) => {
+                self.execute_rlc_reg(reg);
+            }
+            Instruction::RRC(reg) => {
+                self.execute_rrc_reg(reg);
+            }
+            Instruction::RL(reg) => {
+                self.execute_rl_reg(reg);
+            }
+            Instruction::RR(reg) => {
+                self.execute_rr_reg(reg);
+            }
+            Instruction::SLA(reg) => {
+                self.execute_sla_reg(reg);
+            }
+            Instruction::SRA(reg) => {
+                self.execute_sra_reg(reg);
+            }
+            Instruction::SWAP(reg) => {
+                self.execute_swap_reg(reg);
+            }
+            Instruction::SRL(reg) => {
+                self.execute_srl_reg(reg);
+            }
+            Instruction::BIT(bit, reg) => {
+                self.execute_bit_reg(bit, reg);
+            }
+            Instruction::RES(bit, reg) => {
+                self.execute_res_reg(bit, reg);
+            }
+            Instruction::SET(bit, reg) => {
+                self.execute_set_reg(bit, reg);
+            }
+            Instruction::RLCR() => {
+                self.execute_rlc_relative();
+            }
+            Instruction::RRCR() => {
+                self.execute_rrc_relative();
+            }
+            Instruction::RLR() => {
+                self.execute_rl_relative();
+            }
+            Instruction::RRR() => {
+                self.execute_rr_relative();
+            }
+            Instruction::SLAR() => {
+                self.execute_sla_relative();
+            }
+            Instruction::SRAR() => {
+                self.execute_sra_relative();
+            }
+            Instruction::SWAPR() => {
+                self.execute_swap_relative();
+            }
+            Instruction::SRLR() => {
+                self.execute_srl_relative();
+            }
+            Instruction::BITR(bit) => {
+                self.execute_bit_relative(bit);
+            }
+            Instruction::RESR(bit) => {
+                self.execute_res_relative(bit);
+            }
+            Instruction::SETR(bit) => {
+                self.execute_set_relative(bit);
+            }
+            Instruction::INC(reg) => {
+                self.execute_inc_reg(reg);
+            }
+            Instruction::INCR() => {
+                self.execute_inc_relative();
+            }
+            Instruction::DEC(reg) => {
+                self.execute_dec_reg(reg);
+            }
+            Instruction::DECR() => {
+                self.execute_dec_relative();
+            }
+            Instruction::DAA() => {
+                self.execute_daa();
+            }
+            Instruction::CPL() => {
+                self.execute_cpl();
+            }
+            Instruction::SCF() => {
+                self.execute_scf();
+            }
+            Instruction::CCF() => {
+                self.execute_ccf();
+            }
+            Instruction::RLCA() => {
+                self.execute_rlca();
+            }
+            Instruction::RRCA() => {
+                self.execute_rrca();
+            }
+            Instruction::RLA() => {
+                self.execute_rla();
+            }
+            Instruction::RRA() => {
+                self.execute_rra();
+            }
+            Instruction::LD(target, source) => {
+                self.execute_ld_reg(target, source);
+            }
+            Instruction::LDI(target, immediate) => {
+                self.execute_ld_immediate(target, immediate);
+            }
+            Instruction::LDR(target) => {
+                self.execute_ld_relative(target);
+            }
+            Instruction::STR(source) => {
+                self.execute_st_reg(source);
+            }
+            Instruction::STRI(immediate) => {
+                self.execute_st_immediate(immediate);
+            }
+            Instruction::LDA(source) => {
+                self.execute_lda(source);
+            }
+            Instruction::STA(target) => {
+                self.execute_sta(target);
+            }
+            Instruction::LDW(target, immediate) => {
+                self.execute_ldw_immediate(target, immediate);
+            }
+            Instruction::STSP(address) => {
+                self.execute_stsp(address);
+            }
+            Instruction::LDSPHL() => {
+                self.execute_ldsphl();
+            }
+            Instruction::LDHLSP(offset) => {
+                self.execute_ldhlsp(offset);
+            }
+            Instruction::ADDSP(offset) => {
+                self.execute_addsp(offset);
+            }
+            Instruction::ADDHL(reg) => {
+                self.execute_addhl_reg(reg);
+            }
+            Instruction::INCW(reg) => {
+                self.execute_incw_reg(reg);
+            }
+            Instruction::DECW(reg) => {
+                self.execute_decw_reg(reg);
+            }
+            Instruction::JP(condition, address) => {
+                branch_taken = self.execute_jp(condition, address);
+            }
+            Instruction::JPHL() => {
+                self.execute_jphl();
+            }
+            Instruction::JR(condition, offset) => {
+                branch_taken = self.execute_jr(condition, offset);
+            }
+            Instruction::CALL(condition, address) => {
+                branch_taken = self.execute_call(condition, address);
+            }
+            Instruction::RET(condition) => {
+                branch_taken = self.execute_ret(condition);
+            }
+            Instruction::RETI() => {
+                self.execute_reti();
+            }
+            Instruction::RST(vector) => {
+                self.execute_rst(vector);
+            }
+            Instruction::PUSH(reg) => {
+                self.execute_push(reg);
+            }
+            Instruction::POP(reg) => {
+                self.execute_pop(reg);
+            }
+            Instruction::NOP() | Instruction::STOP() => {}
+            Instruction::HALT() => {
+                self.execute_halt();
+            }
+            Instruction::DI() => {
+                self.execute_di();
+            }
+            Instruction::EI() => {
+                self.execute_ei();
+            }
         }
+
+        instruction.cycles(branch_taken)
     }
 
     fn execute_add_reg(&mut self, source: ArithmeticRegisters) {
@@ -84,7 +593,10 @@ impl CPU {
         self.registers.a = new_value;
     }
 
-    fn execute_add_relative(&mut self, source: ArithmeticRegisters) {}
+    fn execute_add_relative(&mut self) {
+        let value = self.read_byte(self.registers.get_hl());
+        self.execute_add_immediate(value);
+    }
 
     fn execute_adc_reg(&mut self, source: ArithmeticRegisters) {
         let source_value = self.registers.load(source);
@@ -107,7 +619,10 @@ impl CPU {
         self.registers.a = new_value;
     }
 
-    fn execute_adc_relative(&mut self, source: ArithmeticRegisters) {}
+    fn execute_adc_relative(&mut self) {
+        let value = self.read_byte(self.registers.get_hl());
+        self.execute_adc_immediate(value);
+    }
 
     fn execute_sub_reg(&mut self, source: ArithmeticRegisters) {
         let source_value = self.registers.load(source);
@@ -115,17 +630,25 @@ impl CPU {
     }
 
     fn execute_sub_immediate(&mut self, immediate: u8) {
+        self.registers.a = self.subtract(immediate);
+    }
+
+    /// Sets the flags for A - `immediate` and returns the difference without storing it.
+    fn subtract(&mut self, immediate: u8) -> u8 {
         let (new_value, overflow) = self.registers.a.overflowing_sub(immediate);
 
         self.registers.f.zero = new_value == 0;
         self.registers.f.substraction = true;
-        self.registers.f.half_carry = (self.registers.a & 0xF) + (immediate & 0xF) > 0xF;
+        self.registers.f.half_carry = (self.registers.a & 0xF) < (immediate & 0xF);
         self.registers.f.carry = overflow;
 
-        self.registers.a = new_value;
+        new_value
     }
 
-    fn execute_sub_relative(&mut self, source: ArithmeticRegisters) {}
+    fn execute_sub_relative(&mut self) {
+        let value = self.read_byte(self.registers.get_hl());
+        self.execute_sub_immediate(value);
+    }
 
     fn execute_sbc_reg(&mut self, source: ArithmeticRegisters) {
         let source_value = self.registers.load(source);
@@ -140,15 +663,16 @@ impl CPU {
         self.registers.f.zero = new_value == 0;
         self.registers.f.substraction = true;
         self.registers.f.half_carry = (self.registers.a & 0xF)
-            + (immediate & 0xF)
-            + (if self.registers.f.carry { 1 } else { 0 })
-            > 0xF;
+            < (immediate & 0xF) + (if self.registers.f.carry { 1 } else { 0 });
         self.registers.f.carry = overflow_1 || overflow_2;
 
         self.registers.a = new_value;
     }
 
-    fn execute_scb_relative(&mut self, source: ArithmeticRegisters) {}
+    fn execute_sbc_relative(&mut self) {
+        let value = self.read_byte(self.registers.get_hl());
+        self.execute_sbc_immediate(value);
+    }
 
     fn execute_and_reg(&mut self, source: ArithmeticRegisters) {
         let source_value = self.registers.load(source);
@@ -166,7 +690,10 @@ impl CPU {
         self.registers.a = new_value;
     }
 
-    fn execute_and_relative(&mut self, immediate: u8) {}
+    fn execute_and_relative(&mut self) {
+        let value = self.read_byte(self.registers.get_hl());
+        self.execute_and_immediate(value);
+    }
 
     fn execute_or_reg(&mut self, source: ArithmeticRegisters) {
         let source_value = self.registers.load(source);
@@ -184,7 +711,10 @@ impl CPU {
         self.registers.a = new_value;
     }
 
-    fn execute_or_relative(&mut self, immediate: u8) {}
+    fn execute_or_relative(&mut self) {
+        let value = self.read_byte(self.registers.get_hl());
+        self.execute_or_immediate(value);
+    }
 
     fn execute_xor_reg(&mut self, source: ArithmeticRegisters) {
         let source_value = self.registers.load(source);
@@ -202,443 +732,3304 @@ impl CPU {
         self.registers.a = new_value;
     }
 
-    fn execute_xor_relative(&mut self, immediate: u8) {}
-}
+    fn execute_xor_relative(&mut self) {
+        let value = self.read_byte(self.registers.get_hl());
+        self.execute_xor_immediate(value);
+    }
 
-#[cfg(test)]
-mod cpu_tests {
-    use super::*;
+    fn execute_cp_reg(&mut self, source: ArithmeticRegisters) {
+        let source_value = self.registers.load(source);
+        self.execute_cp_immediate(source_value);
+    }
 
-    #[test]
-    fn test_execute_add_reg_for_all_registers() {
-        let mut cpu = CPU::new();
+    fn execute_cp_immediate(&mut self, immediate: u8) {
+        self.subtract(immediate);
+    }
 
-        cpu.registers = Registers {
-            a: 1,
-            b: 2,
-            c: 3,
-            d: 4,
-            e: 5,
-            f: FlagsRegister::new(),
-            h: 6,
-            l: 7,
-        };
+    fn execute_cp_relative(&mut self) {
+        let value = self.read_byte(self.registers.get_hl());
+        self.execute_cp_immediate(value);
+    }
 
-        cpu.execute(Instruction::ADD(ArithmeticRegisters::A));
-        assert_eq!(cpu.registers.a, 2);
-        assert_eq!(cpu.registers.f.substraction, false);
+    fn execute_inc_reg(&mut self, target: ArithmeticRegisters) {
+        let value = self.registers.load(target);
+        let new_value = self.increment(value);
+        self.registers.store(target, new_value);
+    }
 
-        cpu.execute(Instruction::ADD(ArithmeticRegisters::B));
-        assert_eq!(cpu.registers.a, 4);
+    fn execute_inc_relative(&mut self) {
+        let address = self.registers.get_hl();
+        let value = self.read_byte(address);
+        let new_value = self.increment(value);
+        self.write_byte(address, new_value);
+    }
 
-        cpu.execute(Instruction::ADD(ArithmeticRegisters::C));
-        assert_eq!(cpu.registers.a, 7);
+    fn increment(&mut self, value: u8) -> u8 {
+        let new_value = value.wrapping_add(1);
 
-        cpu.execute(Instruction::ADD(ArithmeticRegisters::D));
-        assert_eq!(cpu.registers.a, 11);
+        self.registers.f.zero = new_value == 0;
+        self.registers.f.substraction = false;
+        self.registers.f.half_carry = value & 0xF == 0xF;
 
-        cpu.execute(Instruction::ADD(ArithmeticRegisters::E));
-        assert_eq!(cpu.registers.a, 16);
+        new_value
+    }
 
-        cpu.execute(Instruction::ADD(ArithmeticRegisters::H));
-        assert_eq!(cpu.registers.a, 22);
+    fn execute_dec_reg(&mut self, target: ArithmeticRegisters) {
+        let value = self.registers.load(target);
+        let new_value = self.decrement(value);
+        self.registers.store(target, new_value);
+    }
 
-        cpu.execute(Instruction::ADD(ArithmeticRegisters::L));
-        assert_eq!(cpu.registers.a, 29);
+    fn execute_dec_relative(&mut self) {
+        let address = self.registers.get_hl();
+        let value = self.read_byte(address);
+        let new_value = self.decrement(value);
+        self.write_byte(address, new_value);
     }
 
-    #[test]
-    fn test_execute_add_reg_with_overflow() {
-        let mut cpu = CPU::new();
+    fn decrement(&mut self, value: u8) -> u8 {
+        let new_value = value.wrapping_sub(1);
 
-        cpu.registers.a = 244;
-        cpu.registers.b = 230;
+        self.registers.f.zero = new_value == 0;
+        self.registers.f.substraction = true;
+        self.registers.f.half_carry = value & 0xF == 0;
 
-        cpu.execute(Instruction::ADD(ArithmeticRegisters::B));
-        assert_eq!(cpu.registers.a, (244 + 230) as u8);
-        assert_eq!(cpu.registers.f.carry, true);
+        new_value
     }
 
-    #[test]
-    fn test_execute_add_immediate() {
-        let mut cpu = CPU::new();
+    /// Adjusts A back into binary coded decimal after an addition or subtraction of two BCD
+    /// values, using the flags that operation left behind.
+    fn execute_daa(&mut self) {
+        let mut new_value = self.registers.a;
 
-        cpu.registers.a = 244;
-        let immediate: u8 = 0;
+        if !self.registers.f.substraction {
+            if self.registers.f.carry || new_value > 0x99 {
+                new_value = new_value.wrapping_add(0x60);
+                self.registers.f.carry = true;
+            }
+            if self.registers.f.half_carry || new_value & 0xF > 0x9 {
+                new_value = new_value.wrapping_add(0x06);
+            }
+        } else {
+            if self.registers.f.carry {
+                new_value = new_value.wrapping_sub(0x60);
+            }
+            if self.registers.f.half_carry {
+                new_value = new_value.wrapping_sub(0x06);
+            }
+        }
 
-        cpu.execute(Instruction::ADDI(immediate));
-        assert_eq!(cpu.registers.a, 244);
-        assert_eq!(cpu.registers.f.carry, false);
-        assert_eq!(cpu.registers.f.substraction, false);
+        self.registers.f.zero = new_value == 0;
+        self.registers.f.half_carry = false;
+
+        self.registers.a = new_value;
     }
 
-    #[test]
-    fn test_execute_add_immediate_with_overflow() {
-        let mut cpu = CPU::new();
+    fn execute_cpl(&mut self) {
+        self.registers.a = !self.registers.a;
 
-        cpu.registers.a = 244;
-        let immediate: u8 = 230;
+        self.registers.f.substraction = true;
+        self.registers.f.half_carry = true;
+    }
 
-        cpu.execute(Instruction::ADDI(immediate));
-        assert_eq!(cpu.registers.a, (244 + 230) as u8);
-        assert_eq!(cpu.registers.f.carry, true);
+    fn execute_scf(&mut self) {
+        self.registers.f.substraction = false;
+        self.registers.f.half_carry = false;
+        self.registers.f.carry = true;
     }
 
-    #[test]
-    fn test_execute_adc_reg_for_all_registers() {
-        let mut cpu = CPU::new();
+    fn execute_ccf(&mut self) {
+        self.registers.f.substraction = false;
+        self.registers.f.half_carry = false;
+        self.registers.f.carry = !self.registers.f.carry;
+    }
 
-        cpu.registers = Registers {
-            a: 1,
-            b: 2,
-            c: 3,
-            d: 4,
-            e: 5,
-            f: FlagsRegister::new(),
-            h: 6,
-            l: 7,
-        };
+    // The accumulator rotates behave like their CB-prefixed versions, except that they always
+    // clear the zero flag.
 
-        cpu.registers.f.carry = true;
-        cpu.execute(Instruction::ADC(ArithmeticRegisters::A));
-        assert_eq!(cpu.registers.a, 3);
-        assert_eq!(cpu.registers.f.carry, false);
+    fn execute_rlca(&mut self) {
+        self.registers.a = self.rotate_left_circular(self.registers.a);
+        self.registers.f.zero = false;
+    }
 
-        cpu.registers.f.carry = true;
-        cpu.execute(Instruction::ADC(ArithmeticRegisters::B));
-        assert_eq!(cpu.registers.a, 6);
-        assert_eq!(cpu.registers.f.carry, false);
+    fn execute_rrca(&mut self) {
+        self.registers.a = self.rotate_right_circular(self.registers.a);
+        self.registers.f.zero = false;
+    }
 
+    fn execute_rla(&mut self) {
+        self.registers.a = self.rotate_left(self.registers.a);
+        self.registers.f.zero = false;
+    }
+
+    fn execute_rra(&mut self) {
+        self.registers.a = self.rotate_right(self.registers.a);
+        self.registers.f.zero = false;
+    }
+
+    fn execute_addhl_reg(&mut self, source: WideRegisters) {
+        let hl = self.registers.get_hl();
+        let source_value = self.registers.load_wide(source);
+        let (new_value, overflow) = hl.overflowing_add(source_value);
+
+        self.registers.f.substraction = false;
+        self.registers.f.half_carry = (hl & 0xFFF) + (source_value & 0xFFF) > 0xFFF;
+        self.registers.f.carry = overflow;
+
+        self.registers.set_hl(new_value);
+        self.internal_cycle();
+    }
+
+    fn execute_incw_reg(&mut self, target: WideRegisters) {
+        let value = self.registers.load_wide(target);
+        self.registers.store_wide(target, value.wrapping_add(1));
+        self.internal_cycle();
+    }
+
+    fn execute_decw_reg(&mut self, target: WideRegisters) {
+        let value = self.registers.load_wide(target);
+        self.registers.store_wide(target, value.wrapping_sub(1));
+        self.internal_cycle();
+    }
+
+    fn execute_addsp(&mut self, offset: i8) {
+        self.registers.sp = self.offset_sp(offset);
+        self.internal_cycle();
+        self.internal_cycle();
+    }
+
+    /// Sets the flags for SP + `offset` and returns the sum. Both flags come from the addition
+    /// of the unsigned offset byte to the low byte of SP.
+    fn offset_sp(&mut self, offset: i8) -> u16 {
+        let sp = self.registers.sp;
+        let offset_byte = offset as u8 as u16;
+
+        self.registers.f.zero = false;
+        self.registers.f.substraction = false;
+        self.registers.f.half_carry = (sp & 0xF) + (offset_byte & 0xF) > 0xF;
+        self.registers.f.carry = (sp & 0xFF) + offset_byte > 0xFF;
+
+        sp.wrapping_add(offset as u16)
+    }
+
+    fn execute_ld_reg(&mut self, target: ArithmeticRegisters, source: ArithmeticRegisters) {
+        let source_value = self.registers.load(source);
+        self.registers.store(target, source_value);
+    }
+
+    fn execute_ld_immediate(&mut self, target: ArithmeticRegisters, immediate: u8) {
+        self.registers.store(target, immediate);
+    }
+
+    fn execute_ld_relative(&mut self, target: ArithmeticRegisters) {
+        let value = self.read_byte(self.registers.get_hl());
+        self.registers.store(target, value);
+    }
+
+    fn execute_st_reg(&mut self, source: ArithmeticRegisters) {
+        let source_value = self.registers.load(source);
+        self.execute_st_immediate(source_value);
+    }
+
+    fn execute_st_immediate(&mut self, immediate: u8) {
+        self.write_byte(self.registers.get_hl(), immediate);
+    }
+
+    fn execute_lda(&mut self, source: Indirect) {
+        let address = self.resolve_indirect(source);
+        self.registers.a = self.read_byte(address);
+    }
+
+    fn execute_sta(&mut self, target: Indirect) {
+        let address = self.resolve_indirect(target);
+        self.write_byte(address, self.registers.a);
+    }
+
+    /// Returns the address `indirect` points to, applying the post increment or decrement of HL.
+    fn resolve_indirect(&mut self, indirect: Indirect) -> u16 {
+        match indirect {
+            Indirect::BC => self.registers.get_bc(),
+            Indirect::DE => self.registers.get_de(),
+            Indirect::HLIncrement => {
+                let hl = self.registers.get_hl();
+                self.registers.set_hl(hl.wrapping_add(1));
+                hl
+            }
+            Indirect::HLDecrement => {
+                let hl = self.registers.get_hl();
+                self.registers.set_hl(hl.wrapping_sub(1));
+                hl
+            }
+            Indirect::HighC => 0xFF00 | self.registers.c as u16,
+            Indirect::HighImmediate(offset) => 0xFF00 | offset as u16,
+            Indirect::Immediate(address) => address,
+        }
+    }
+
+    fn execute_ldw_immediate(&mut self, target: WideRegisters, immediate: u16) {
+        self.registers.store_wide(target, immediate);
+    }
+
+    fn execute_stsp(&mut self, address: u16) {
+        let sp = self.registers.sp;
+        self.write_byte(address, sp as u8);
+        self.write_byte(address.wrapping_add(1), (sp >> 8) as u8);
+    }
+
+    fn execute_ldsphl(&mut self) {
+        self.registers.sp = self.registers.get_hl();
+        self.internal_cycle();
+    }
+
+    fn execute_ldhlsp(&mut self, offset: i8) {
+        let new_value = self.offset_sp(offset);
+        self.registers.set_hl(new_value);
+        self.internal_cycle();
+    }
+
+    fn condition_holds(&self, condition: JumpCondition) -> bool {
+        match condition {
+            JumpCondition::Always => true,
+            JumpCondition::NotZero => !self.registers.f.zero,
+            JumpCondition::Zero => self.registers.f.zero,
+            JumpCondition::NotCarry => !self.registers.f.carry,
+            JumpCondition::Carry => self.registers.f.carry,
+        }
+    }
+
+    fn execute_jp(&mut self, condition: JumpCondition, address: u16) -> bool {
+        let taken = self.condition_holds(condition);
+        if taken {
+            self.registers.pc = address;
+            self.internal_cycle();
+        }
+        taken
+    }
+
+    fn execute_jphl(&mut self) {
+        self.registers.pc = self.registers.get_hl();
+    }
+
+    /// The offset is relative to the address following the JR instruction.
+    fn execute_jr(&mut self, condition: JumpCondition, offset: i8) -> bool {
+        let taken = self.condition_holds(condition);
+        if taken {
+            self.registers.pc = self.registers.pc.wrapping_add(offset as u16);
+            self.internal_cycle();
+        }
+        taken
+    }
+
+    fn execute_call(&mut self, condition: JumpCondition, address: u16) -> bool {
+        let taken = self.condition_holds(condition);
+        if taken {
+            self.internal_cycle();
+            self.push(self.registers.pc);
+            self.registers.pc = address;
+        }
+        taken
+    }
+
+    fn execute_ret(&mut self, condition: JumpCondition) -> bool {
+        // Checking a condition takes a cycle of its own.
+        if condition != JumpCondition::Always {
+            self.internal_cycle();
+        }
+
+        let taken = self.condition_holds(condition);
+        if taken {
+            self.registers.pc = self.pop();
+            self.internal_cycle();
+        }
+        taken
+    }
+
+    fn execute_reti(&mut self) {
+        self.registers.pc = self.pop();
+        self.internal_cycle();
+        self.ime = true;
+    }
+
+    fn execute_halt(&mut self) {
+        if !self.ime && self.bus.pending_interrupts() != 0 {
+            self.halt_bug = true;
+        } else {
+            self.halted = true;
+        }
+    }
+
+    fn execute_di(&mut self) {
+        self.ime = false;
+        self.ime_scheduled = false;
+    }
+
+    fn execute_ei(&mut self) {
+        self.ime_scheduled = true;
+    }
+
+    fn execute_rst(&mut self, vector: u8) {
+        self.internal_cycle();
+        self.push(self.registers.pc);
+        self.registers.pc = vector as u16;
+    }
+
+    fn execute_push(&mut self, source: StackRegisters) {
+        let value = match source {
+            StackRegisters::BC => self.registers.get_bc(),
+            StackRegisters::DE => self.registers.get_de(),
+            StackRegisters::HL => self.registers.get_hl(),
+            StackRegisters::AF => self.registers.get_af(),
+        };
+        self.internal_cycle();
+        self.push(value);
+    }
+
+    fn execute_pop(&mut self, target: StackRegisters) {
+        let value = self.pop();
+        match target {
+            StackRegisters::BC => self.registers.set_bc(value),
+            StackRegisters::DE => self.registers.set_de(value),
+            StackRegisters::HL => self.registers.set_hl(value),
+            StackRegisters::AF => self.registers.set_af(value),
+        }
+    }
+
+    fn push(&mut self, value: u16) {
+        self.registers.sp = self.registers.sp.wrapping_sub(1);
+        self.write_byte(self.registers.sp, (value >> 8) as u8);
+        self.registers.sp = self.registers.sp.wrapping_sub(1);
+        self.write_byte(self.registers.sp, value as u8);
+    }
+
+    fn pop(&mut self) -> u16 {
+        let low = self.read_byte(self.registers.sp) as u16;
+        self.registers.sp = self.registers.sp.wrapping_add(1);
+        let high = self.read_byte(self.registers.sp) as u16;
+        self.registers.sp = self.registers.sp.wrapping_add(1);
+        (high << 8) | low
+    }
+
+    fn execute_rlc_reg(&mut self, target: ArithmeticRegisters) {
+        let value = self.registers.load(target);
+        let new_value = self.rotate_left_circular(value);
+        self.registers.store(target, new_value);
+    }
+
+    fn execute_rlc_relative(&mut self) {
+        let address = self.registers.get_hl();
+        let value = self.read_byte(address);
+        let new_value = self.rotate_left_circular(value);
+        self.write_byte(address, new_value);
+    }
+
+    fn rotate_left_circular(&mut self, value: u8) -> u8 {
+        let new_value = value.rotate_left(1);
+
+        self.registers.f.zero = new_value == 0;
+        self.registers.f.substraction = false;
+        self.registers.f.half_carry = false;
+        self.registers.f.carry = value & 0x80 != 0;
+
+        new_value
+    }
+
+    fn execute_rrc_reg(&mut self, target: ArithmeticRegisters) {
+        let value = self.registers.load(target);
+        let new_value = self.rotate_right_circular(value);
+        self.registers.store(target, new_value);
+    }
+
+    fn execute_rrc_relative(&mut self) {
+        let address = self.registers.get_hl();
+        let value = self.read_byte(address);
+        let new_value = self.rotate_right_circular(value);
+        self.write_byte(address, new_value);
+    }
+
+    fn rotate_right_circular(&mut self, value: u8) -> u8 {
+        let new_value = value.rotate_right(1);
+
+        self.registers.f.zero = new_value == 0;
+        self.registers.f.substraction = false;
+        self.registers.f.half_carry = false;
+        self.registers.f.carry = value & 0x01 != 0;
+
+        new_value
+    }
+
+    fn execute_rl_reg(&mut self, target: ArithmeticRegisters) {
+        let value = self.registers.load(target);
+        let new_value = self.rotate_left(value);
+        self.registers.store(target, new_value);
+    }
+
+    fn execute_rl_relative(&mut self) {
+        let address = self.registers.get_hl();
+        let value = self.read_byte(address);
+        let new_value = self.rotate_left(value);
+        self.write_byte(address, new_value);
+    }
+
+    fn rotate_left(&mut self, value: u8) -> u8 {
+        let new_value = (value << 1) | (if self.registers.f.carry { 1 } else { 0 });
+
+        self.registers.f.zero = new_value == 0;
+        self.registers.f.substraction = false;
+        self.registers.f.half_carry = false;
+        self.registers.f.carry = value & 0x80 != 0;
+
+        new_value
+    }
+
+    fn execute_rr_reg(&mut self, target: ArithmeticRegisters) {
+        let value = self.registers.load(target);
+        let new_value = self.rotate_right(value);
+        self.registers.store(target, new_value);
+    }
+
+    fn execute_rr_relative(&mut self) {
+        let address = self.registers.get_hl();
+        let value = self.read_byte(address);
+        let new_value = self.rotate_right(value);
+        self.write_byte(address, new_value);
+    }
+
+    fn rotate_right(&mut self, value: u8) -> u8 {
+        let new_value = (value >> 1) | (if self.registers.f.carry { 0x80 } else { 0 });
+
+        self.registers.f.zero = new_value == 0;
+        self.registers.f.substraction = false;
+        self.registers.f.half_carry = false;
+        self.registers.f.carry = value & 0x01 != 0;
+
+        new_value
+    }
+
+    fn execute_sla_reg(&mut self, target: ArithmeticRegisters) {
+        let value = self.registers.load(target);
+        let new_value = self.shift_left_arithmetic(value);
+        self.registers.store(target, new_value);
+    }
+
+    fn execute_sla_relative(&mut self) {
+        let address = self.registers.get_hl();
+        let value = self.read_byte(address);
+        let new_value = self.shift_left_arithmetic(value);
+        self.write_byte(address, new_value);
+    }
+
+    fn shift_left_arithmetic(&mut self, value: u8) -> u8 {
+        let new_value = value << 1;
+
+        self.registers.f.zero = new_value == 0;
+        self.registers.f.substraction = false;
+        self.registers.f.half_carry = false;
+        self.registers.f.carry = value & 0x80 != 0;
+
+        new_value
+    }
+
+    fn execute_sra_reg(&mut self, target: ArithmeticRegisters) {
+        let value = self.registers.load(target);
+        let new_value = self.shift_right_arithmetic(value);
+        self.registers.store(target, new_value);
+    }
+
+    fn execute_sra_relative(&mut self) {
+        let address = self.registers.get_hl();
+        let value = self.read_byte(address);
+        let new_value = self.shift_right_arithmetic(value);
+        self.write_byte(address, new_value);
+    }
+
+    fn shift_right_arithmetic(&mut self, value: u8) -> u8 {
+        let new_value = (value >> 1) | (value & 0x80);
+
+        self.registers.f.zero = new_value == 0;
+        self.registers.f.substraction = false;
+        self.registers.f.half_carry = false;
+        self.registers.f.carry = value & 0x01 != 0;
+
+        new_value
+    }
+
+    fn execute_swap_reg(&mut self, target: ArithmeticRegisters) {
+        let value = self.registers.load(target);
+        let new_value = self.swap_nibbles(value);
+        self.registers.store(target, new_value);
+    }
+
+    fn execute_swap_relative(&mut self) {
+        let address = self.registers.get_hl();
+        let value = self.read_byte(address);
+        let new_value = self.swap_nibbles(value);
+        self.write_byte(address, new_value);
+    }
+
+    fn swap_nibbles(&mut self, value: u8) -> u8 {
+        let new_value = value.rotate_left(4);
+
+        self.registers.f.zero = new_value == 0;
+        self.registers.f.substraction = false;
+        self.registers.f.half_carry = false;
+        self.registers.f.carry = false;
+
+        new_value
+    }
+
+    fn execute_srl_reg(&mut self, target: ArithmeticRegisters) {
+        let value = self.registers.load(target);
+        let new_value = self.shift_right_logical(value);
+        self.registers.store(target, new_value);
+    }
+
+    fn execute_srl_relative(&mut self) {
+        let address = self.registers.get_hl();
+        let value = self.read_byte(address);
+        let new_value = self.shift_right_logical(value);
+        self.write_byte(address, new_value);
+    }
+
+    fn shift_right_logical(&mut self, value: u8) -> u8 {
+        let new_value = value >> 1;
+
+        self.registers.f.zero = new_value == 0;
+        self.registers.f.substraction = false;
+        self.registers.f.half_carry = false;
+        self.registers.f.carry = value & 0x01 != 0;
+
+        new_value
+    }
+
+    fn execute_bit_reg(&mut self, bit: u8, source: ArithmeticRegisters) {
+        let value = self.registers.load(source);
+        self.test_bit(bit, value);
+    }
+
+    fn execute_bit_relative(&mut self, bit: u8) {
+        let value = self.read_byte(self.registers.get_hl());
+        self.test_bit(bit, value);
+    }
+
+    fn test_bit(&mut self, bit: u8, value: u8) {
+        self.registers.f.zero = value & (1 << bit) == 0;
+        self.registers.f.substraction = false;
+        self.registers.f.half_carry = true;
+    }
+
+    fn execute_res_reg(&mut self, bit: u8, target: ArithmeticRegisters) {
+        let value = self.registers.load(target);
+        self.registers.store(target, value & !(1 << bit));
+    }
+
+    fn execute_res_relative(&mut self, bit: u8) {
+        let address = self.registers.get_hl();
+        let value = self.read_byte(address);
+        self.write_byte(address, value & !(1 << bit));
+    }
+
+    fn execute_set_reg(&mut self, bit: u8, target: ArithmeticRegisters) {
+        let value = self.registers.load(target);
+        self.registers.store(target, value | (1 << bit));
+    }
+
+    fn execute_set_relative(&mut self, bit: u8) {
+        let address = self.registers.get_hl();
+        let value = self.read_byte(address);
+        self.write_byte(address, value | (1 << bit));
+    }
+}
+
+#[cfg(test)]
+mod cpu_tests {
+    use super::*;
+    use crate::assembler::assemble;
+    use crate::flagsregister::FlagsRegister;
+    use crate::instructions::{ILLEGAL_OPCODES, PREFIX_BYTE};
+    use crate::interrupts::INTERRUPT_FLAG_ADDRESS;
+    use crate::memorybus::INTERRUPT_ENABLE_ADDRESS;
+    use crate::opcodes::{FlagEffect, PREFIXED_OPCODES, UNPREFIXED_OPCODES};
+    use crate::timer::DIV_ADDRESS;
+
+    #[test]
+    fn test_execute_add_reg_for_all_registers() {
+        let mut cpu = CPU::new();
+
+        cpu.registers = Registers {
+            a: 1,
+            b: 2,
+            c: 3,
+            d: 4,
+            e: 5,
+            f: FlagsRegister::new(),
+            h: 6,
+            l: 7,
+            ..Default::default()
+        };
+
+        cpu.execute(Instruction::ADD(ArithmeticRegisters::A));
+        assert_eq!(cpu.registers.a, 2);
+        assert_eq!(cpu.registers.f.substraction, false);
+
+        cpu.execute(Instruction::ADD(ArithmeticRegisters::B));
+        assert_eq!(cpu.registers.a, 4);
+
+        cpu.execute(Instruction::ADD(ArithmeticRegisters::C));
+        assert_eq!(cpu.registers.a, 7);
+
+        cpu.execute(Instruction::ADD(ArithmeticRegisters::D));
+        assert_eq!(cpu.registers.a, 11);
+
+        cpu.execute(Instruction::ADD(ArithmeticRegisters::E));
+        assert_eq!(cpu.registers.a, 16);
+
+        cpu.execute(Instruction::ADD(ArithmeticRegisters::H));
+        assert_eq!(cpu.registers.a, 22);
+
+        cpu.execute(Instruction::ADD(ArithmeticRegisters::L));
+        assert_eq!(cpu.registers.a, 29);
+    }
+
+    #[test]
+    fn test_execute_add_reg_with_overflow() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 244;
+        cpu.registers.b = 230;
+
+        cpu.execute(Instruction::ADD(ArithmeticRegisters::B));
+        assert_eq!(cpu.registers.a, (244 + 230) as u8);
+        assert_eq!(cpu.registers.f.carry, true);
+    }
+
+    #[test]
+    fn test_execute_add_immediate() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 244;
+        let immediate: u8 = 0;
+
+        cpu.execute(Instruction::ADDI(immediate));
+        assert_eq!(cpu.registers.a, 244);
+        assert_eq!(cpu.registers.f.carry, false);
+        assert_eq!(cpu.registers.f.substraction, false);
+    }
+
+    #[test]
+    fn test_execute_add_immediate_with_overflow() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 244;
+        let immediate: u8 = 230;
+
+        cpu.execute(Instruction::ADDI(immediate));
+        assert_eq!(cpu.registers.a, (244 + 230) as u8);
+        assert_eq!(cpu.registers.f.carry, true);
+    }
+
+    #[test]
+    fn test_execute_adc_reg_for_all_registers() {
+        let mut cpu = CPU::new();
+
+        cpu.registers = Registers {
+            a: 1,
+            b: 2,
+            c: 3,
+            d: 4,
+            e: 5,
+            f: FlagsRegister::new(),
+            h: 6,
+            l: 7,
+            ..Default::default()
+        };
+
+        cpu.registers.f.carry = true;
+        cpu.execute(Instruction::ADC(ArithmeticRegisters::A));
+        assert_eq!(cpu.registers.a, 3);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        cpu.registers.f.carry = true;
+        cpu.execute(Instruction::ADC(ArithmeticRegisters::B));
+        assert_eq!(cpu.registers.a, 6);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        cpu.registers.f.carry = true;
+        cpu.execute(Instruction::ADC(ArithmeticRegisters::C));
+        assert_eq!(cpu.registers.a, 10);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        cpu.registers.f.carry = true;
+        cpu.execute(Instruction::ADC(ArithmeticRegisters::D));
+        assert_eq!(cpu.registers.a, 15);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        cpu.registers.f.carry = true;
+        cpu.execute(Instruction::ADC(ArithmeticRegisters::E));
+        assert_eq!(cpu.registers.a, 21);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        cpu.registers.f.carry = true;
+        cpu.execute(Instruction::ADC(ArithmeticRegisters::H));
+        assert_eq!(cpu.registers.a, 28);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        cpu.registers.f.carry = true;
+        cpu.execute(Instruction::ADC(ArithmeticRegisters::L));
+        assert_eq!(cpu.registers.a, 36);
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_adc_reg_with_overflow() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 244;
+        cpu.registers.b = 229;
+        cpu.registers.f.carry = true;
+
+        cpu.execute(Instruction::ADC(ArithmeticRegisters::B));
+        assert_eq!(cpu.registers.a, (244 + 229 + 1) as u8);
+        assert_eq!(cpu.registers.f.carry, true);
+    }
+
+    #[test]
+    fn test_execute_adc_immediate() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 244;
+        let immediate: u8 = 0;
+        cpu.registers.f.carry = true;
+
+        cpu.execute(Instruction::ADCI(immediate));
+        assert_eq!(cpu.registers.a, 245);
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_adc_immediate_with_overflow() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 244;
+        let immediate: u8 = 229;
+        cpu.registers.f.carry = true;
+
+        cpu.execute(Instruction::ADCI(immediate));
+        assert_eq!(cpu.registers.a, (244 + 229 + 1) as u8);
+        assert_eq!(cpu.registers.f.carry, true);
+    }
+
+    #[test]
+    fn test_execute_sub_reg_for_all_registers() {
+        let mut cpu = CPU::new();
+
+        cpu.registers = Registers {
+            a: 29,
+            b: 2,
+            c: 3,
+            d: 4,
+            e: 5,
+            f: FlagsRegister::new(),
+            h: 6,
+            l: 7,
+            ..Default::default()
+        };
+
+        cpu.execute(Instruction::SUB(ArithmeticRegisters::B));
+        assert_eq!(cpu.registers.a, 27);
+
+        cpu.execute(Instruction::SUB(ArithmeticRegisters::C));
+        assert_eq!(cpu.registers.a, 24);
+
+        cpu.execute(Instruction::SUB(ArithmeticRegisters::D));
+        assert_eq!(cpu.registers.a, 20);
+
+        cpu.execute(Instruction::SUB(ArithmeticRegisters::E));
+        assert_eq!(cpu.registers.a, 15);
+
+        cpu.execute(Instruction::SUB(ArithmeticRegisters::H));
+        assert_eq!(cpu.registers.a, 9);
+
+        cpu.execute(Instruction::SUB(ArithmeticRegisters::L));
+        assert_eq!(cpu.registers.a, 2);
+        assert_eq!(cpu.registers.f.carry, false);
+        assert_eq!(cpu.registers.f.substraction, true);
+    }
+
+    #[test]
+    fn test_execute_sub_reg_from_itself_gives_zero() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 255;
+
+        cpu.execute(Instruction::SUB(ArithmeticRegisters::A));
+        assert_eq!(cpu.registers.a, 0);
+        assert_eq!(cpu.registers.f.zero, true);
+        assert_eq!(cpu.registers.f.substraction, true);
+    }
+
+    #[test]
+    fn test_execute_sub_reg_with_overflow() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 2;
+        cpu.registers.b = 230;
+
+        cpu.execute(Instruction::SUB(ArithmeticRegisters::B));
+        assert_eq!(cpu.registers.a, (2 - 230) as u8);
+        assert_eq!(cpu.registers.f.carry, true);
+    }
+
+    #[test]
+    fn test_execute_sub_immediate() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 244;
+        let immediate: u8 = 1;
+
+        cpu.execute(Instruction::SUBI(immediate));
+        assert_eq!(cpu.registers.a, 243);
+        assert_eq!(cpu.registers.f.substraction, true);
+    }
+
+    #[test]
+    fn test_execute_sbc_reg_for_all_registers() {
+        let mut cpu = CPU::new();
+
+        cpu.registers = Registers {
+            a: 36,
+            b: 2,
+            c: 3,
+            d: 4,
+            e: 5,
+            f: FlagsRegister::new(),
+            h: 6,
+            l: 7,
+            ..Default::default()
+        };
+
+        cpu.registers.f.carry = true;
+        cpu.execute(Instruction::SBC(ArithmeticRegisters::B));
+        assert_eq!(cpu.registers.a, 33);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        cpu.registers.f.carry = true;
+        cpu.execute(Instruction::SBC(ArithmeticRegisters::C));
+        assert_eq!(cpu.registers.a, 29);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        cpu.registers.f.carry = true;
+        cpu.execute(Instruction::SBC(ArithmeticRegisters::D));
+        assert_eq!(cpu.registers.a, 24);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        cpu.registers.f.carry = true;
+        cpu.execute(Instruction::SBC(ArithmeticRegisters::E));
+        assert_eq!(cpu.registers.a, 18);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        cpu.registers.f.carry = true;
+        cpu.execute(Instruction::SBC(ArithmeticRegisters::H));
+        assert_eq!(cpu.registers.a, 11);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        cpu.registers.f.carry = true;
+        cpu.execute(Instruction::SBC(ArithmeticRegisters::L));
+        assert_eq!(cpu.registers.a, 3);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        assert_eq!(cpu.registers.f.substraction, true);
+    }
+
+    #[test]
+    fn test_execute_sbc_immediate() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 244;
+        let immediate: u8 = 100;
+        cpu.registers.f.carry = true;
+
+        cpu.execute(Instruction::SBCI(immediate));
+        assert_eq!(cpu.registers.a, 143);
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_sbc_immediate_with_overflow() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 10;
+        let immediate: u8 = 10;
+        cpu.registers.f.carry = true;
+
+        cpu.execute(Instruction::SBCI(immediate));
+        assert_eq!(cpu.registers.a, (0 - 1) as u8);
+        assert_eq!(cpu.registers.f.carry, true);
+    }
+
+    #[test]
+    fn test_execute_and_reg() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 1;
+        cpu.registers.b = 3;
+        cpu.registers.c = 0;
+
+        cpu.execute(Instruction::AND(ArithmeticRegisters::B));
+        assert_eq!(cpu.registers.a, 1);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, true);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        cpu.execute(Instruction::AND(ArithmeticRegisters::C));
+        assert_eq!(cpu.registers.a, 0);
+        assert_eq!(cpu.registers.f.zero, true);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, true);
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_and_immediate() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 3;
+        let immediate: u8 = 2;
+
+        cpu.execute(Instruction::ANDI(immediate));
+        assert_eq!(cpu.registers.a, 2);
+
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, true);
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_or_reg() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 1;
+        cpu.registers.b = 4;
+
+        cpu.execute(Instruction::OR(ArithmeticRegisters::B));
+        assert_eq!(cpu.registers.a, 5);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        cpu.registers.a = 0;
+        cpu.registers.c = 0;
+
+        cpu.execute(Instruction::OR(ArithmeticRegisters::C));
+        assert_eq!(cpu.registers.a, 0);
+        assert_eq!(cpu.registers.f.zero, true);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_or_immediate() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 1;
+        let immediate: u8 = 2;
+
+        cpu.execute(Instruction::ORI(immediate));
+        assert_eq!(cpu.registers.a, 3);
+
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_xor_reg() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 5;
+        cpu.registers.b = 5;
+
+        cpu.execute(Instruction::XOR(ArithmeticRegisters::B));
+        assert_eq!(cpu.registers.a, 0);
+        assert_eq!(cpu.registers.f.zero, true);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        cpu.registers.a = 5;
+        cpu.registers.c = 2;
+
+        cpu.execute(Instruction::XOR(ArithmeticRegisters::C));
+        assert_eq!(cpu.registers.a, 7);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_xor_immediate() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 9;
+        let immediate: u8 = 6;
+
+        cpu.execute(Instruction::XORI(immediate));
+        assert_eq!(cpu.registers.a, 15);
+
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_rlc_reg_for_all_registers() {
+        let mut cpu = CPU::new();
+
+        cpu.registers = Registers {
+            a: 0x01,
+            b: 0x02,
+            c: 0x04,
+            d: 0x08,
+            e: 0x10,
+            f: FlagsRegister::new(),
+            h: 0x20,
+            l: 0x40,
+            ..Default::default()
+        };
+
+        cpu.execute(Instruction::RLC(ArithmeticRegisters::A));
+        cpu.execute(Instruction::RLC(ArithmeticRegisters::B));
+        cpu.execute(Instruction::RLC(ArithmeticRegisters::C));
+        cpu.execute(Instruction::RLC(ArithmeticRegisters::D));
+        cpu.execute(Instruction::RLC(ArithmeticRegisters::E));
+        cpu.execute(Instruction::RLC(ArithmeticRegisters::H));
+        cpu.execute(Instruction::RLC(ArithmeticRegisters::L));
+
+        assert_eq!(cpu.registers.a, 0x02);
+        assert_eq!(cpu.registers.b, 0x04);
+        assert_eq!(cpu.registers.c, 0x08);
+        assert_eq!(cpu.registers.d, 0x10);
+        assert_eq!(cpu.registers.e, 0x20);
+        assert_eq!(cpu.registers.h, 0x40);
+        assert_eq!(cpu.registers.l, 0x80);
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_rlc_reg_moves_bit_7_into_bit_0_and_carry() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.b = 0x85;
+
+        cpu.execute(Instruction::RLC(ArithmeticRegisters::B));
+        assert_eq!(cpu.registers.b, 0x0B);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, true);
+    }
+
+    #[test]
+    fn test_execute_rlc_reg_of_zero_sets_zero() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.c = 0;
+        cpu.registers.f.carry = true;
+        cpu.registers.f.half_carry = true;
+        cpu.registers.f.substraction = true;
+
+        cpu.execute(Instruction::RLC(ArithmeticRegisters::C));
+        assert_eq!(cpu.registers.c, 0);
+        assert_eq!(cpu.registers.f.zero, true);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_rrc_reg_moves_bit_0_into_bit_7_and_carry() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.d = 0x01;
+
+        cpu.execute(Instruction::RRC(ArithmeticRegisters::D));
+        assert_eq!(cpu.registers.d, 0x80);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.execute(Instruction::RRC(ArithmeticRegisters::D));
+        assert_eq!(cpu.registers.d, 0x40);
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_rl_reg_rotates_through_carry() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.e = 0x80;
+
+        cpu.execute(Instruction::RL(ArithmeticRegisters::E));
+        assert_eq!(cpu.registers.e, 0x00);
+        assert_eq!(cpu.registers.f.zero, true);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.execute(Instruction::RL(ArithmeticRegisters::E));
+        assert_eq!(cpu.registers.e, 0x01);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_rr_reg_rotates_through_carry() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.h = 0x01;
+
+        cpu.execute(Instruction::RR(ArithmeticRegisters::H));
+        assert_eq!(cpu.registers.h, 0x00);
+        assert_eq!(cpu.registers.f.zero, true);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.execute(Instruction::RR(ArithmeticRegisters::H));
+        assert_eq!(cpu.registers.h, 0x80);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_sla_reg() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.l = 0xC1;
+        cpu.registers.f.carry = false;
+
+        cpu.execute(Instruction::SLA(ArithmeticRegisters::L));
+        assert_eq!(cpu.registers.l, 0x82);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.registers.l = 0x80;
+
+        cpu.execute(Instruction::SLA(ArithmeticRegisters::L));
+        assert_eq!(cpu.registers.l, 0x00);
+        assert_eq!(cpu.registers.f.zero, true);
+        assert_eq!(cpu.registers.f.carry, true);
+    }
+
+    #[test]
+    fn test_execute_sra_reg_keeps_bit_7() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 0x81;
+
+        cpu.execute(Instruction::SRA(ArithmeticRegisters::A));
+        assert_eq!(cpu.registers.a, 0xC0);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.registers.a = 0x01;
+
+        cpu.execute(Instruction::SRA(ArithmeticRegisters::A));
+        assert_eq!(cpu.registers.a, 0x00);
+        assert_eq!(cpu.registers.f.zero, true);
+        assert_eq!(cpu.registers.f.carry, true);
+    }
+
+    #[test]
+    fn test_execute_swap_reg() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.b = 0xA5;
+        cpu.registers.f.carry = true;
+
+        cpu.execute(Instruction::SWAP(ArithmeticRegisters::B));
+        assert_eq!(cpu.registers.b, 0x5A);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        cpu.registers.b = 0x00;
+
+        cpu.execute(Instruction::SWAP(ArithmeticRegisters::B));
+        assert_eq!(cpu.registers.b, 0x00);
+        assert_eq!(cpu.registers.f.zero, true);
+    }
+
+    #[test]
+    fn test_execute_srl_reg_clears_bit_7() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.c = 0x81;
+
+        cpu.execute(Instruction::SRL(ArithmeticRegisters::C));
+        assert_eq!(cpu.registers.c, 0x40);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.registers.c = 0x01;
+
+        cpu.execute(Instruction::SRL(ArithmeticRegisters::C));
+        assert_eq!(cpu.registers.c, 0x00);
+        assert_eq!(cpu.registers.f.zero, true);
+        assert_eq!(cpu.registers.f.carry, true);
+    }
+
+    #[test]
+    fn test_execute_bit_reg_for_all_bits() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.d = 0b1010_1010;
+
+        for bit in 0..8 {
+            cpu.execute(Instruction::BIT(bit, ArithmeticRegisters::D));
+            assert_eq!(cpu.registers.f.zero, bit % 2 == 0);
+            assert_eq!(cpu.registers.f.substraction, false);
+            assert_eq!(cpu.registers.f.half_carry, true);
+        }
+        assert_eq!(cpu.registers.d, 0b1010_1010);
+    }
+
+    #[test]
+    fn test_execute_bit_reg_preserves_carry() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.e = 0xFF;
+        cpu.registers.f.carry = true;
+
+        cpu.execute(Instruction::BIT(0, ArithmeticRegisters::E));
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.registers.f.carry = false;
+
+        cpu.execute(Instruction::BIT(0, ArithmeticRegisters::E));
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_res_reg_for_all_bits() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.h = 0xFF;
+
+        for bit in 0..8 {
+            cpu.execute(Instruction::RES(bit, ArithmeticRegisters::H));
+        }
+        assert_eq!(cpu.registers.h, 0x00);
+    }
+
+    #[test]
+    fn test_execute_set_reg_for_all_bits() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.l = 0x00;
+
+        for bit in 0..8 {
+            cpu.execute(Instruction::SET(bit, ArithmeticRegisters::L));
+        }
+        assert_eq!(cpu.registers.l, 0xFF);
+    }
+
+    #[test]
+    fn test_execute_res_and_set_reg_leave_flags_untouched() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.f = FlagsRegister {
+            zero: true,
+            substraction: true,
+            half_carry: true,
+            carry: true,
+        };
+
+        cpu.execute(Instruction::SET(3, ArithmeticRegisters::A));
+        cpu.execute(Instruction::RES(3, ArithmeticRegisters::A));
+        assert_eq!(cpu.registers.a, 0);
+        assert_eq!(cpu.registers.f.zero, true);
+        assert_eq!(cpu.registers.f.substraction, true);
+        assert_eq!(cpu.registers.f.half_carry, true);
+        assert_eq!(cpu.registers.f.carry, true);
+    }
+
+    #[test]
+    fn test_execute_addhl_reg_for_all_registers() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.set_hl(0x0001);
+        cpu.registers.set_bc(0x0002);
+        cpu.registers.set_de(0x0003);
+        cpu.registers.sp = 0x0004;
+
+        cpu.execute(Instruction::ADDHL(WideRegisters::BC));
+        assert_eq!(cpu.registers.get_hl(), 0x0003);
+
+        cpu.execute(Instruction::ADDHL(WideRegisters::DE));
+        assert_eq!(cpu.registers.get_hl(), 0x0006);
+
+        cpu.execute(Instruction::ADDHL(WideRegisters::SP));
+        assert_eq!(cpu.registers.get_hl(), 0x000A);
+
+        cpu.execute(Instruction::ADDHL(WideRegisters::HL));
+        assert_eq!(cpu.registers.get_hl(), 0x0014);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_addhl_reg_half_carry_from_bit_11() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.set_hl(0x0FFF);
+        cpu.registers.set_bc(0x0001);
+
+        cpu.execute(Instruction::ADDHL(WideRegisters::BC));
+        assert_eq!(cpu.registers.get_hl(), 0x1000);
+        assert_eq!(cpu.registers.f.half_carry, true);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        cpu.registers.set_hl(0x00FF);
+
+        cpu.execute(Instruction::ADDHL(WideRegisters::BC));
+        assert_eq!(cpu.registers.get_hl(), 0x0100);
+        assert_eq!(cpu.registers.f.half_carry, false);
+    }
+
+    #[test]
+    fn test_execute_addhl_reg_with_overflow_leaves_zero_untouched() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.set_hl(0xFFFF);
+        cpu.registers.set_de(0x0001);
+        cpu.registers.f.zero = false;
+        cpu.registers.f.substraction = true;
+
+        cpu.execute(Instruction::ADDHL(WideRegisters::DE));
+        assert_eq!(cpu.registers.get_hl(), 0x0000);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, true);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.registers.f.zero = true;
+        cpu.registers.set_de(0x0000);
+
+        cpu.execute(Instruction::ADDHL(WideRegisters::DE));
+        assert_eq!(cpu.registers.f.zero, true);
+    }
+
+    #[test]
+    fn test_execute_incw_reg_for_all_registers() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.set_bc(0x00FF);
+        cpu.registers.set_de(0x1234);
+        cpu.registers.set_hl(0xFFFF);
+        cpu.registers.sp = 0xFFFE;
+
+        cpu.execute(Instruction::INCW(WideRegisters::BC));
+        cpu.execute(Instruction::INCW(WideRegisters::DE));
+        cpu.execute(Instruction::INCW(WideRegisters::HL));
+        cpu.execute(Instruction::INCW(WideRegisters::SP));
+
+        assert_eq!(cpu.registers.get_bc(), 0x0100);
+        assert_eq!(cpu.registers.get_de(), 0x1235);
+        assert_eq!(cpu.registers.get_hl(), 0x0000);
+        assert_eq!(cpu.registers.sp, 0xFFFF);
+    }
+
+    #[test]
+    fn test_execute_decw_reg_for_all_registers() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.set_bc(0x0100);
+        cpu.registers.set_de(0x1234);
+        cpu.registers.set_hl(0x0000);
+        cpu.registers.sp = 0xFFFF;
+
+        cpu.execute(Instruction::DECW(WideRegisters::BC));
+        cpu.execute(Instruction::DECW(WideRegisters::DE));
+        cpu.execute(Instruction::DECW(WideRegisters::HL));
+        cpu.execute(Instruction::DECW(WideRegisters::SP));
+
+        assert_eq!(cpu.registers.get_bc(), 0x00FF);
+        assert_eq!(cpu.registers.get_de(), 0x1233);
+        assert_eq!(cpu.registers.get_hl(), 0xFFFF);
+        assert_eq!(cpu.registers.sp, 0xFFFE);
+    }
+
+    #[test]
+    fn test_execute_incw_and_decw_leave_flags_untouched() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.set_hl(0xFFFF);
+
+        cpu.execute(Instruction::INCW(WideRegisters::HL));
+        assert_eq!(cpu.registers.f, FlagsRegister::new());
+
+        cpu.execute(Instruction::DECW(WideRegisters::HL));
+        assert_eq!(cpu.registers.f, FlagsRegister::new());
+    }
+
+    #[test]
+    fn test_execute_rotates_and_shifts_relative() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.set_hl(0xC000);
+        cpu.bus.write_byte(0xC000, 0x81);
+
+        cpu.execute(Instruction::RLCR());
+        assert_eq!(cpu.bus.read_byte(0xC000), 0x03);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.execute(Instruction::RRCR());
+        assert_eq!(cpu.bus.read_byte(0xC000), 0x81);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.execute(Instruction::RLR());
+        assert_eq!(cpu.bus.read_byte(0xC000), 0x03);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.execute(Instruction::RRR());
+        assert_eq!(cpu.bus.read_byte(0xC000), 0x81);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.execute(Instruction::SLAR());
+        assert_eq!(cpu.bus.read_byte(0xC000), 0x02);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.execute(Instruction::SWAPR());
+        assert_eq!(cpu.bus.read_byte(0xC000), 0x20);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        cpu.execute(Instruction::SRLR());
+        assert_eq!(cpu.bus.read_byte(0xC000), 0x10);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        cpu.bus.write_byte(0xC000, 0x80);
+
+        cpu.execute(Instruction::SRAR());
+        assert_eq!(cpu.bus.read_byte(0xC000), 0xC0);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_bit_res_set_relative() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.set_hl(0xFF80);
+        cpu.registers.f.carry = true;
+
+        cpu.execute(Instruction::BITR(4));
+        assert_eq!(cpu.registers.f.zero, true);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, true);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.execute(Instruction::SETR(4));
+        assert_eq!(cpu.bus.read_byte(0xFF80), 0x10);
+
+        cpu.execute(Instruction::BITR(4));
+        assert_eq!(cpu.registers.f.zero, false);
+
+        cpu.execute(Instruction::RESR(4));
+        assert_eq!(cpu.bus.read_byte(0xFF80), 0x00);
+    }
+
+    #[test]
+    fn test_execute_add_relative() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 244;
+        cpu.registers.set_hl(0xC000);
+        cpu.bus.write_byte(0xC000, 10);
+
+        cpu.execute(Instruction::ADDR());
+        assert_eq!(cpu.registers.a, 254);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_add_relative_with_overflow() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 244;
+        cpu.registers.set_hl(0xC000);
+        cpu.bus.write_byte(0xC000, 230);
+
+        cpu.execute(Instruction::ADDR());
+        assert_eq!(cpu.registers.a, (244 + 230) as u8);
+        assert_eq!(cpu.registers.f.carry, true);
+    }
+
+    #[test]
+    fn test_execute_adc_relative() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 244;
+        cpu.registers.set_hl(0xC000);
+        cpu.bus.write_byte(0xC000, 229);
+        cpu.registers.f.carry = true;
+
+        cpu.execute(Instruction::ADCR());
+        assert_eq!(cpu.registers.a, (244 + 229 + 1) as u8);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.registers.a = 1;
+        cpu.bus.write_byte(0xC000, 2);
+
+        cpu.execute(Instruction::ADCR());
+        assert_eq!(cpu.registers.a, 4);
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_sub_relative() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 29;
+        cpu.registers.set_hl(0xC000);
+        cpu.bus.write_byte(0xC000, 27);
+
+        cpu.execute(Instruction::SUBR());
+        assert_eq!(cpu.registers.a, 2);
+        assert_eq!(cpu.registers.f.carry, false);
+        assert_eq!(cpu.registers.f.substraction, true);
+
+        cpu.execute(Instruction::SUBR());
+        assert_eq!(cpu.registers.a, (2 - 27) as u8);
+        assert_eq!(cpu.registers.f.carry, true);
+    }
+
+    #[test]
+    fn test_execute_sbc_relative() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 10;
+        cpu.registers.set_hl(0xC000);
+        cpu.bus.write_byte(0xC000, 4);
+        cpu.registers.f.carry = true;
+
+        cpu.execute(Instruction::SBCR());
+        assert_eq!(cpu.registers.a, 5);
+        assert_eq!(cpu.registers.f.carry, false);
+        assert_eq!(cpu.registers.f.substraction, true);
+
+        cpu.registers.f.carry = true;
+        cpu.bus.write_byte(0xC000, 5);
+
+        cpu.execute(Instruction::SBCR());
+        assert_eq!(cpu.registers.a, (0 - 1) as u8);
+        assert_eq!(cpu.registers.f.carry, true);
+    }
+
+    #[test]
+    fn test_execute_and_relative() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 3;
+        cpu.registers.set_hl(0xFF80);
+        cpu.bus.write_byte(0xFF80, 2);
+
+        cpu.execute(Instruction::ANDR());
+        assert_eq!(cpu.registers.a, 2);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, true);
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_or_relative() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 1;
+        cpu.registers.set_hl(0xFF80);
+        cpu.bus.write_byte(0xFF80, 4);
+
+        cpu.execute(Instruction::ORR());
+        assert_eq!(cpu.registers.a, 5);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_xor_relative() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 5;
+        cpu.registers.set_hl(0xFF80);
+        cpu.bus.write_byte(0xFF80, 5);
+
+        cpu.execute(Instruction::XORR());
+        assert_eq!(cpu.registers.a, 0);
+        assert_eq!(cpu.registers.f.zero, true);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_sub_immediate_half_carry_is_a_borrow_from_bit_4() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 0x10;
+
+        cpu.execute(Instruction::SUBI(0x01));
+        assert_eq!(cpu.registers.a, 0x0F);
+        assert_eq!(cpu.registers.f.half_carry, true);
+
+        cpu.execute(Instruction::SUBI(0x0F));
+        assert_eq!(cpu.registers.a, 0x00);
+        assert_eq!(cpu.registers.f.half_carry, false);
+    }
+
+    #[test]
+    fn test_execute_sbc_immediate_half_carry_includes_carry_in() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 0x1F;
         cpu.registers.f.carry = true;
-        cpu.execute(Instruction::ADC(ArithmeticRegisters::C));
-        assert_eq!(cpu.registers.a, 10);
+
+        cpu.execute(Instruction::SBCI(0x0F));
+        assert_eq!(cpu.registers.a, 0x0F);
+        assert_eq!(cpu.registers.f.half_carry, true);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        cpu.registers.f.carry = true;
+
+        cpu.execute(Instruction::SBCI(0x0E));
+        assert_eq!(cpu.registers.a, 0x00);
+        assert_eq!(cpu.registers.f.zero, true);
+        assert_eq!(cpu.registers.f.half_carry, false);
+    }
+
+    /// Result of an ALU operation on A, computed with plain integer arithmetic.
+    fn reference_alu(instruction: Instruction, a: u8, carry_in: bool) -> (u8, FlagsRegister) {
+        let a = a as i32;
+        let carry_in = carry_in as i32;
+
+        let (result, substraction, half_carry, carry) = match instruction {
+            Instruction::ADDI(value) => {
+                let value = value as i32;
+                (
+                    a + value,
+                    false,
+                    (a & 0xF) + (value & 0xF) > 0xF,
+                    a + value > 0xFF,
+                )
+            }
+            Instruction::ADCI(value) => {
+                let value = value as i32;
+                (
+                    a + value + carry_in,
+                    false,
+                    (a & 0xF) + (value & 0xF) + carry_in > 0xF,
+                    a + value + carry_in > 0xFF,
+                )
+            }
+            Instruction::SUBI(value) => {
+                let value = value as i32;
+                (
+                    a - value,
+                    true,
+                    (a & 0xF) - (value & 0xF) < 0,
+                    a - value < 0,
+                )
+            }
+            Instruction::SBCI(value) => {
+                let value = value as i32;
+                (
+                    a - value - carry_in,
+                    true,
+                    (a & 0xF) - (value & 0xF) - carry_in < 0,
+                    a - value - carry_in < 0,
+                )
+            }
+            Instruction::ANDI(value) => (a & value as i32, false, true, false),
+            Instruction::XORI(value) => (a ^ value as i32, false, false, false),
+            Instruction::ORI(value) => (a | value as i32, false, false, false),
+            Instruction::CPI(value) => {
+                let value = value as i32;
+                (
+                    a - value,
+                    true,
+                    (a & 0xF) - (value & 0xF) < 0,
+                    a - value < 0,
+                )
+            }
+            _ => unreachable!(),
+        };
+
+        let zero = result & 0xFF == 0;
+        let result = match instruction {
+            Instruction::CPI(_) => a as u8,
+            _ => (result & 0xFF) as u8,
+        };
+        let flags = FlagsRegister {
+            zero,
+            substraction,
+            half_carry,
+            carry,
+        };
+
+        (result, flags)
+    }
+
+    #[test]
+    fn test_execute_alu_immediates_match_reference_for_all_inputs() {
+        let operations: [fn(u8) -> Instruction; 8] = [
+            Instruction::ADDI,
+            Instruction::ADCI,
+            Instruction::SUBI,
+            Instruction::SBCI,
+            Instruction::ANDI,
+            Instruction::XORI,
+            Instruction::ORI,
+            Instruction::CPI,
+        ];
+        let mut cpu = CPU::new();
+
+        for operation in operations.iter() {
+            for a in 0..=0xFFu8 {
+                for value in 0..=0xFFu8 {
+                    for &carry_in in &[false, true] {
+                        let instruction = operation(value);
+
+                        cpu.registers.a = a;
+                        cpu.registers.f = FlagsRegister {
+                            zero: !carry_in,
+                            substraction: carry_in,
+                            half_carry: !carry_in,
+                            carry: carry_in,
+                        };
+                        cpu.execute(instruction);
+
+                        let (result, flags) = reference_alu(instruction, a, carry_in);
+                        assert_eq!(
+                            (cpu.registers.a, cpu.registers.f),
+                            (result, flags),
+                            "{:?} with A={:#04x} carry={}",
+                            instruction,
+                            a,
+                            carry_in
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_execute_cp_reg_for_all_registers() {
+        let mut cpu = CPU::new();
+
+        cpu.registers = Registers {
+            a: 5,
+            b: 2,
+            c: 5,
+            d: 6,
+            e: 0x0F,
+            f: FlagsRegister::new(),
+            h: 0x15,
+            l: 0,
+            ..Default::default()
+        };
+
+        cpu.execute(Instruction::CP(ArithmeticRegisters::B));
+        assert_eq!(cpu.registers.a, 5);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, true);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        cpu.execute(Instruction::CP(ArithmeticRegisters::C));
+        assert_eq!(cpu.registers.a, 5);
+        assert_eq!(cpu.registers.f.zero, true);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        cpu.execute(Instruction::CP(ArithmeticRegisters::D));
+        assert_eq!(cpu.registers.a, 5);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.half_carry, true);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.execute(Instruction::CP(ArithmeticRegisters::E));
+        assert_eq!(cpu.registers.a, 5);
+        assert_eq!(cpu.registers.f.half_carry, true);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.execute(Instruction::CP(ArithmeticRegisters::H));
+        assert_eq!(cpu.registers.a, 5);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.execute(Instruction::CP(ArithmeticRegisters::L));
+        assert_eq!(cpu.registers.a, 5);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        cpu.execute(Instruction::CP(ArithmeticRegisters::A));
+        assert_eq!(cpu.registers.a, 5);
+        assert_eq!(cpu.registers.f.zero, true);
+        assert_eq!(cpu.registers.f.substraction, true);
+    }
+
+    #[test]
+    fn test_execute_cp_immediate() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 0x3C;
+
+        cpu.execute(Instruction::CPI(0x40));
+        assert_eq!(cpu.registers.a, 0x3C);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, true);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.execute(Instruction::CPI(0x3C));
+        assert_eq!(cpu.registers.a, 0x3C);
+        assert_eq!(cpu.registers.f.zero, true);
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_cp_relative() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 0x3C;
+        cpu.registers.set_hl(0xC000);
+        cpu.bus.write_byte(0xC000, 0x2F);
+
+        cpu.execute(Instruction::CPR());
+        assert_eq!(cpu.registers.a, 0x3C);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, true);
+        assert_eq!(cpu.registers.f.half_carry, true);
+        assert_eq!(cpu.registers.f.carry, false);
+    }
+
+    #[test]
+    fn test_execute_inc_reg_for_all_registers() {
+        let mut cpu = CPU::new();
+
+        cpu.registers = Registers {
+            a: 1,
+            b: 2,
+            c: 3,
+            d: 4,
+            e: 5,
+            f: FlagsRegister::new(),
+            h: 6,
+            l: 7,
+            ..Default::default()
+        };
+
+        cpu.execute(Instruction::INC(ArithmeticRegisters::A));
+        cpu.execute(Instruction::INC(ArithmeticRegisters::B));
+        cpu.execute(Instruction::INC(ArithmeticRegisters::C));
+        cpu.execute(Instruction::INC(ArithmeticRegisters::D));
+        cpu.execute(Instruction::INC(ArithmeticRegisters::E));
+        cpu.execute(Instruction::INC(ArithmeticRegisters::H));
+        cpu.execute(Instruction::INC(ArithmeticRegisters::L));
+
+        assert_eq!(cpu.registers.a, 2);
+        assert_eq!(cpu.registers.b, 3);
+        assert_eq!(cpu.registers.c, 4);
+        assert_eq!(cpu.registers.d, 5);
+        assert_eq!(cpu.registers.e, 6);
+        assert_eq!(cpu.registers.h, 7);
+        assert_eq!(cpu.registers.l, 8);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+    }
+
+    #[test]
+    fn test_execute_inc_reg_with_overflow_preserves_carry() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.b = 0xFF;
+        cpu.registers.f.carry = false;
+        cpu.registers.f.substraction = true;
+
+        cpu.execute(Instruction::INC(ArithmeticRegisters::B));
+        assert_eq!(cpu.registers.b, 0x00);
+        assert_eq!(cpu.registers.f.zero, true);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, true);
+        assert_eq!(cpu.registers.f.carry, false);
+
+        cpu.registers.f.carry = true;
+
+        cpu.execute(Instruction::INC(ArithmeticRegisters::B));
+        assert_eq!(cpu.registers.b, 0x01);
+        assert_eq!(cpu.registers.f.carry, true);
+    }
+
+    #[test]
+    fn test_execute_dec_reg_for_all_registers() {
+        let mut cpu = CPU::new();
+
+        cpu.registers = Registers {
+            a: 1,
+            b: 2,
+            c: 3,
+            d: 4,
+            e: 5,
+            f: FlagsRegister::new(),
+            h: 6,
+            l: 7,
+            ..Default::default()
+        };
+
+        cpu.execute(Instruction::DEC(ArithmeticRegisters::A));
+        assert_eq!(cpu.registers.f.zero, true);
+
+        cpu.execute(Instruction::DEC(ArithmeticRegisters::B));
+        cpu.execute(Instruction::DEC(ArithmeticRegisters::C));
+        cpu.execute(Instruction::DEC(ArithmeticRegisters::D));
+        cpu.execute(Instruction::DEC(ArithmeticRegisters::E));
+        cpu.execute(Instruction::DEC(ArithmeticRegisters::H));
+        cpu.execute(Instruction::DEC(ArithmeticRegisters::L));
+
+        assert_eq!(cpu.registers.a, 0);
+        assert_eq!(cpu.registers.b, 1);
+        assert_eq!(cpu.registers.c, 2);
+        assert_eq!(cpu.registers.d, 3);
+        assert_eq!(cpu.registers.e, 4);
+        assert_eq!(cpu.registers.h, 5);
+        assert_eq!(cpu.registers.l, 6);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, true);
+        assert_eq!(cpu.registers.f.half_carry, false);
+    }
+
+    #[test]
+    fn test_execute_dec_reg_with_underflow_preserves_carry() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.c = 0x00;
+        cpu.registers.f.carry = true;
+
+        cpu.execute(Instruction::DEC(ArithmeticRegisters::C));
+        assert_eq!(cpu.registers.c, 0xFF);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, true);
+        assert_eq!(cpu.registers.f.half_carry, true);
+        assert_eq!(cpu.registers.f.carry, true);
+    }
+
+    #[test]
+    fn test_execute_inc_and_dec_relative() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.set_hl(0xC000);
+        cpu.bus.write_byte(0xC000, 0x0F);
+
+        cpu.execute(Instruction::INCR());
+        assert_eq!(cpu.bus.read_byte(0xC000), 0x10);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, true);
+
+        cpu.execute(Instruction::DECR());
+        assert_eq!(cpu.bus.read_byte(0xC000), 0x0F);
+        assert_eq!(cpu.registers.f.substraction, true);
+        assert_eq!(cpu.registers.f.half_carry, true);
+    }
+
+    /// DAA written as the correction table from the hardware documentation.
+    fn reference_daa(a: u8, substraction: bool, half_carry: bool, carry: bool) -> (u8, bool) {
+        let mut correction = 0;
+        let mut carry_out = false;
+
+        if half_carry || (!substraction && a & 0xF > 0x9) {
+            correction |= 0x06;
+        }
+        if carry || (!substraction && a > 0x99) {
+            correction |= 0x60;
+            carry_out = true;
+        }
+
+        if substraction {
+            (a.wrapping_sub(correction), carry_out)
+        } else {
+            (a.wrapping_add(correction), carry_out)
+        }
+    }
+
+    #[test]
+    fn test_execute_daa_matches_reference_for_all_inputs() {
+        let mut cpu = CPU::new();
+
+        for a in 0..=0xFFu8 {
+            for flags in 0..0x10u8 {
+                let flags = FlagsRegister::from(flags << 4);
+
+                cpu.registers.a = a;
+                cpu.registers.f = flags;
+                cpu.execute(Instruction::DAA());
+
+                let (result, carry) =
+                    reference_daa(a, flags.substraction, flags.half_carry, flags.carry);
+                assert_eq!(cpu.registers.a, result, "A={:#04x} flags={:?}", a, flags);
+                assert_eq!(cpu.registers.f.zero, result == 0);
+                assert_eq!(cpu.registers.f.substraction, flags.substraction);
+                assert_eq!(cpu.registers.f.half_carry, false);
+                assert_eq!(cpu.registers.f.carry, carry);
+            }
+        }
+    }
+
+    fn to_bcd(value: u8) -> u8 {
+        (value / 10) << 4 | (value % 10)
+    }
+
+    #[test]
+    fn test_execute_daa_after_bcd_addition_and_subtraction() {
+        let mut cpu = CPU::new();
+
+        for x in 0..100u8 {
+            for y in 0..100u8 {
+                cpu.registers.a = to_bcd(x);
+                cpu.execute(Instruction::ADDI(to_bcd(y)));
+                cpu.execute(Instruction::DAA());
+                assert_eq!(cpu.registers.a, to_bcd((x + y) % 100));
+                assert_eq!(cpu.registers.f.carry, x + y >= 100);
+
+                cpu.registers.a = to_bcd(x);
+                cpu.execute(Instruction::SUBI(to_bcd(y)));
+                cpu.execute(Instruction::DAA());
+                assert_eq!(cpu.registers.a, to_bcd((x + 100 - y) % 100));
+                assert_eq!(cpu.registers.f.carry, x < y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_execute_cpl() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 0x35;
+        cpu.registers.f.zero = true;
+        cpu.registers.f.carry = true;
+
+        cpu.execute(Instruction::CPL());
+        assert_eq!(cpu.registers.a, 0xCA);
+        assert_eq!(cpu.registers.f.zero, true);
+        assert_eq!(cpu.registers.f.substraction, true);
+        assert_eq!(cpu.registers.f.half_carry, true);
+        assert_eq!(cpu.registers.f.carry, true);
+    }
+
+    #[test]
+    fn test_execute_scf_and_ccf() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.f = FlagsRegister {
+            zero: true,
+            substraction: true,
+            half_carry: true,
+            carry: false,
+        };
+
+        cpu.execute(Instruction::SCF());
+        assert_eq!(cpu.registers.f.zero, true);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.registers.f.half_carry = true;
+
+        cpu.execute(Instruction::CCF());
+        assert_eq!(cpu.registers.f.zero, true);
+        assert_eq!(cpu.registers.f.half_carry, false);
         assert_eq!(cpu.registers.f.carry, false);
 
-        cpu.registers.f.carry = true;
-        cpu.execute(Instruction::ADC(ArithmeticRegisters::D));
-        assert_eq!(cpu.registers.a, 15);
+        cpu.execute(Instruction::CCF());
+        assert_eq!(cpu.registers.f.carry, true);
+    }
+
+    #[test]
+    fn test_execute_rlca_and_rrca() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 0x80;
+        cpu.registers.f.zero = true;
+
+        cpu.execute(Instruction::RLCA());
+        assert_eq!(cpu.registers.a, 0x01);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.execute(Instruction::RRCA());
+        assert_eq!(cpu.registers.a, 0x80);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.execute(Instruction::RRCA());
+        assert_eq!(cpu.registers.a, 0x40);
         assert_eq!(cpu.registers.f.carry, false);
+    }
 
-        cpu.registers.f.carry = true;
-        cpu.execute(Instruction::ADC(ArithmeticRegisters::E));
-        assert_eq!(cpu.registers.a, 21);
+    #[test]
+    fn test_execute_rla_and_rra_never_set_zero() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 0x80;
+
+        cpu.execute(Instruction::RLA());
+        assert_eq!(cpu.registers.a, 0x00);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.execute(Instruction::RLA());
+        assert_eq!(cpu.registers.a, 0x01);
         assert_eq!(cpu.registers.f.carry, false);
 
-        cpu.registers.f.carry = true;
-        cpu.execute(Instruction::ADC(ArithmeticRegisters::H));
-        assert_eq!(cpu.registers.a, 28);
+        cpu.execute(Instruction::RRA());
+        assert_eq!(cpu.registers.a, 0x00);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.execute(Instruction::RRA());
+        assert_eq!(cpu.registers.a, 0x80);
         assert_eq!(cpu.registers.f.carry, false);
+    }
 
-        cpu.registers.f.carry = true;
-        cpu.execute(Instruction::ADC(ArithmeticRegisters::L));
-        assert_eq!(cpu.registers.a, 36);
+    #[test]
+    fn test_execute_ld_reg_for_all_registers() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 0x42;
+
+        cpu.execute(Instruction::LD(
+            ArithmeticRegisters::B,
+            ArithmeticRegisters::A,
+        ));
+        cpu.execute(Instruction::LD(
+            ArithmeticRegisters::C,
+            ArithmeticRegisters::B,
+        ));
+        cpu.execute(Instruction::LD(
+            ArithmeticRegisters::D,
+            ArithmeticRegisters::C,
+        ));
+        cpu.execute(Instruction::LD(
+            ArithmeticRegisters::E,
+            ArithmeticRegisters::D,
+        ));
+        cpu.execute(Instruction::LD(
+            ArithmeticRegisters::H,
+            ArithmeticRegisters::E,
+        ));
+        cpu.execute(Instruction::LD(
+            ArithmeticRegisters::L,
+            ArithmeticRegisters::H,
+        ));
+
+        assert_eq!(cpu.registers.b, 0x42);
+        assert_eq!(cpu.registers.c, 0x42);
+        assert_eq!(cpu.registers.d, 0x42);
+        assert_eq!(cpu.registers.e, 0x42);
+        assert_eq!(cpu.registers.h, 0x42);
+        assert_eq!(cpu.registers.l, 0x42);
+        assert_eq!(cpu.registers.f, FlagsRegister::new());
+    }
+
+    #[test]
+    fn test_execute_ld_immediate() {
+        let mut cpu = CPU::new();
+
+        cpu.execute(Instruction::LDI(ArithmeticRegisters::E, 0x99));
+        assert_eq!(cpu.registers.e, 0x99);
+    }
+
+    #[test]
+    fn test_execute_ld_relative_and_st_reg() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.set_hl(0xC123);
+        cpu.registers.b = 0x77;
+
+        cpu.execute(Instruction::STR(ArithmeticRegisters::B));
+        assert_eq!(cpu.bus.read_byte(0xC123), 0x77);
+
+        cpu.execute(Instruction::LDR(ArithmeticRegisters::A));
+        assert_eq!(cpu.registers.a, 0x77);
+    }
+
+    #[test]
+    fn test_execute_st_immediate() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.set_hl(0xC000);
+
+        cpu.execute(Instruction::STRI(0x5A));
+        assert_eq!(cpu.bus.read_byte(0xC000), 0x5A);
+    }
+
+    #[test]
+    fn test_execute_lda_and_sta_through_register_pairs() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.set_bc(0xC000);
+        cpu.registers.set_de(0xC001);
+        cpu.registers.a = 0x11;
+
+        cpu.execute(Instruction::STA(Indirect::BC));
+        cpu.registers.a = 0x22;
+        cpu.execute(Instruction::STA(Indirect::DE));
+
+        cpu.execute(Instruction::LDA(Indirect::BC));
+        assert_eq!(cpu.registers.a, 0x11);
+
+        cpu.execute(Instruction::LDA(Indirect::DE));
+        assert_eq!(cpu.registers.a, 0x22);
+    }
+
+    #[test]
+    fn test_execute_lda_and_sta_with_hl_increment_and_decrement() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.set_hl(0xC000);
+        cpu.registers.a = 0x01;
+
+        cpu.execute(Instruction::STA(Indirect::HLIncrement));
+        assert_eq!(cpu.registers.get_hl(), 0xC001);
+        assert_eq!(cpu.bus.read_byte(0xC000), 0x01);
+
+        cpu.registers.a = 0x02;
+        cpu.execute(Instruction::STA(Indirect::HLDecrement));
+        assert_eq!(cpu.registers.get_hl(), 0xC000);
+        assert_eq!(cpu.bus.read_byte(0xC001), 0x02);
+
+        cpu.execute(Instruction::LDA(Indirect::HLIncrement));
+        assert_eq!(cpu.registers.a, 0x01);
+        assert_eq!(cpu.registers.get_hl(), 0xC001);
+
+        cpu.execute(Instruction::LDA(Indirect::HLDecrement));
+        assert_eq!(cpu.registers.a, 0x02);
+        assert_eq!(cpu.registers.get_hl(), 0xC000);
+    }
+
+    #[test]
+    fn test_execute_lda_and_sta_on_the_high_page() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 0x33;
+        cpu.registers.c = 0x81;
+
+        cpu.execute(Instruction::STA(Indirect::HighImmediate(0x80)));
+        assert_eq!(cpu.bus.read_byte(0xFF80), 0x33);
+
+        cpu.execute(Instruction::STA(Indirect::HighC));
+        assert_eq!(cpu.bus.read_byte(0xFF81), 0x33);
+
+        cpu.bus.write_byte(0xFF80, 0x44);
+        cpu.execute(Instruction::LDA(Indirect::HighImmediate(0x80)));
+        assert_eq!(cpu.registers.a, 0x44);
+
+        cpu.bus.write_byte(0xFF81, 0x55);
+        cpu.execute(Instruction::LDA(Indirect::HighC));
+        assert_eq!(cpu.registers.a, 0x55);
+    }
+
+    #[test]
+    fn test_execute_lda_and_sta_absolute() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.a = 0x66;
+
+        cpu.execute(Instruction::STA(Indirect::Immediate(0xD000)));
+        assert_eq!(cpu.bus.read_byte(0xD000), 0x66);
+
+        cpu.registers.a = 0;
+        cpu.execute(Instruction::LDA(Indirect::Immediate(0xD000)));
+        assert_eq!(cpu.registers.a, 0x66);
+    }
+
+    #[test]
+    fn test_execute_ldw_immediate_for_all_registers() {
+        let mut cpu = CPU::new();
+
+        cpu.execute(Instruction::LDW(WideRegisters::BC, 0x1234));
+        cpu.execute(Instruction::LDW(WideRegisters::DE, 0x5678));
+        cpu.execute(Instruction::LDW(WideRegisters::HL, 0x9ABC));
+        cpu.execute(Instruction::LDW(WideRegisters::SP, 0xFFFE));
+
+        assert_eq!(cpu.registers.get_bc(), 0x1234);
+        assert_eq!(cpu.registers.get_de(), 0x5678);
+        assert_eq!(cpu.registers.get_hl(), 0x9ABC);
+        assert_eq!(cpu.registers.sp, 0xFFFE);
+    }
+
+    #[test]
+    fn test_execute_stsp_writes_little_endian() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.sp = 0xBEEF;
+
+        cpu.execute(Instruction::STSP(0xC000));
+        assert_eq!(cpu.bus.read_byte(0xC000), 0xEF);
+        assert_eq!(cpu.bus.read_byte(0xC001), 0xBE);
+    }
+
+    #[test]
+    fn test_execute_ldsphl() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.set_hl(0xDFFF);
+
+        cpu.execute(Instruction::LDSPHL());
+        assert_eq!(cpu.registers.sp, 0xDFFF);
+    }
+
+    #[test]
+    fn test_execute_ldhlsp_flags_come_from_the_low_byte() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.sp = 0x00FF;
+        cpu.registers.f.zero = true;
+
+        cpu.execute(Instruction::LDHLSP(1));
+        assert_eq!(cpu.registers.get_hl(), 0x0100);
+        assert_eq!(cpu.registers.sp, 0x00FF);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.substraction, false);
+        assert_eq!(cpu.registers.f.half_carry, true);
+        assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.registers.sp = 0x1000;
+
+        cpu.execute(Instruction::LDHLSP(-1));
+        assert_eq!(cpu.registers.get_hl(), 0x0FFF);
+        assert_eq!(cpu.registers.f.half_carry, false);
         assert_eq!(cpu.registers.f.carry, false);
     }
 
     #[test]
-    fn test_execute_adc_reg_with_overflow() {
+    fn test_execute_addsp() {
         let mut cpu = CPU::new();
 
-        cpu.registers.a = 244;
-        cpu.registers.b = 229;
-        cpu.registers.f.carry = true;
+        cpu.registers.sp = 0xFFF8;
 
-        cpu.execute(Instruction::ADC(ArithmeticRegisters::B));
-        assert_eq!(cpu.registers.a, (244 + 229 + 1) as u8);
+        cpu.execute(Instruction::ADDSP(8));
+        assert_eq!(cpu.registers.sp, 0x0000);
+        assert_eq!(cpu.registers.f.zero, false);
+        assert_eq!(cpu.registers.f.half_carry, true);
         assert_eq!(cpu.registers.f.carry, true);
+
+        cpu.execute(Instruction::ADDSP(-2));
+        assert_eq!(cpu.registers.sp, 0xFFFE);
+        assert_eq!(cpu.registers.f.half_carry, false);
+        assert_eq!(cpu.registers.f.carry, false);
     }
 
     #[test]
-    fn test_execute_adc_immediate() {
+    fn test_execute_jp_unconditional() {
+        let mut cpu = CPU::new();
+
+        let cycles = cpu.execute(Instruction::JP(JumpCondition::Always, 0x0150));
+        assert_eq!(cpu.registers.pc, 0x0150);
+        assert_eq!(cycles, 4);
+    }
+
+    #[test]
+    fn test_execute_jp_conditions_taken_and_not_taken() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.pc = 0x0100;
+        cpu.registers.f.zero = true;
+        cpu.registers.f.carry = false;
+
+        let cycles = cpu.execute(Instruction::JP(JumpCondition::NotZero, 0x2000));
+        assert_eq!(cpu.registers.pc, 0x0100);
+        assert_eq!(cycles, 3);
+
+        let cycles = cpu.execute(Instruction::JP(JumpCondition::Carry, 0x2000));
+        assert_eq!(cpu.registers.pc, 0x0100);
+        assert_eq!(cycles, 3);
+
+        let cycles = cpu.execute(Instruction::JP(JumpCondition::Zero, 0x2000));
+        assert_eq!(cpu.registers.pc, 0x2000);
+        assert_eq!(cycles, 4);
+
+        let cycles = cpu.execute(Instruction::JP(JumpCondition::NotCarry, 0x3000));
+        assert_eq!(cpu.registers.pc, 0x3000);
+        assert_eq!(cycles, 4);
+    }
+
+    #[test]
+    fn test_execute_jphl() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.set_hl(0x4321);
+
+        let cycles = cpu.execute(Instruction::JPHL());
+        assert_eq!(cpu.registers.pc, 0x4321);
+        assert_eq!(cycles, 1);
+    }
+
+    #[test]
+    fn test_execute_jr_with_signed_offset() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.pc = 0x0200;
+
+        let cycles = cpu.execute(Instruction::JR(JumpCondition::Always, -0x10));
+        assert_eq!(cpu.registers.pc, 0x01F0);
+        assert_eq!(cycles, 3);
+
+        let cycles = cpu.execute(Instruction::JR(JumpCondition::Always, 0x7F));
+        assert_eq!(cpu.registers.pc, 0x026F);
+        assert_eq!(cycles, 3);
+    }
+
+    #[test]
+    fn test_execute_jr_not_taken() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.pc = 0x0200;
+        cpu.registers.f.zero = false;
+
+        let cycles = cpu.execute(Instruction::JR(JumpCondition::Zero, 0x10));
+        assert_eq!(cpu.registers.pc, 0x0200);
+        assert_eq!(cycles, 2);
+    }
+
+    #[test]
+    fn test_execute_call_and_ret() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.pc = 0x0153;
+        cpu.registers.sp = 0xFFFE;
+
+        let cycles = cpu.execute(Instruction::CALL(JumpCondition::Always, 0x4000));
+        assert_eq!(cpu.registers.pc, 0x4000);
+        assert_eq!(cpu.registers.sp, 0xFFFC);
+        assert_eq!(cpu.bus.read_byte(0xFFFD), 0x01);
+        assert_eq!(cpu.bus.read_byte(0xFFFC), 0x53);
+        assert_eq!(cycles, 6);
+
+        let cycles = cpu.execute(Instruction::RET(JumpCondition::Always));
+        assert_eq!(cpu.registers.pc, 0x0153);
+        assert_eq!(cpu.registers.sp, 0xFFFE);
+        assert_eq!(cycles, 4);
+    }
+
+    #[test]
+    fn test_execute_conditional_call_and_ret() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.pc = 0x0153;
+        cpu.registers.sp = 0xFFFE;
+        cpu.registers.f.carry = true;
+
+        let cycles = cpu.execute(Instruction::CALL(JumpCondition::NotCarry, 0x4000));
+        assert_eq!(cpu.registers.pc, 0x0153);
+        assert_eq!(cpu.registers.sp, 0xFFFE);
+        assert_eq!(cycles, 3);
+
+        let cycles = cpu.execute(Instruction::CALL(JumpCondition::Carry, 0x4000));
+        assert_eq!(cpu.registers.pc, 0x4000);
+        assert_eq!(cycles, 6);
+
+        let cycles = cpu.execute(Instruction::RET(JumpCondition::NotCarry));
+        assert_eq!(cpu.registers.pc, 0x4000);
+        assert_eq!(cycles, 2);
+
+        let cycles = cpu.execute(Instruction::RET(JumpCondition::Carry));
+        assert_eq!(cpu.registers.pc, 0x0153);
+        assert_eq!(cpu.registers.sp, 0xFFFE);
+        assert_eq!(cycles, 5);
+    }
+
+    #[test]
+    fn test_execute_reti() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.sp = 0xFFFC;
+        cpu.bus.write_byte(0xFFFC, 0x34);
+        cpu.bus.write_byte(0xFFFD, 0x12);
+
+        cpu.execute(Instruction::RETI());
+        assert_eq!(cpu.registers.pc, 0x1234);
+        assert_eq!(cpu.registers.sp, 0xFFFE);
+    }
+
+    #[test]
+    fn test_execute_rst_for_all_vectors() {
+        let mut cpu = CPU::new();
+
+        for vector in (0x00..=0x38).step_by(8) {
+            cpu.registers.pc = 0x1234;
+            cpu.registers.sp = 0xFFFE;
+
+            let cycles = cpu.execute(Instruction::RST(vector));
+            assert_eq!(cpu.registers.pc, vector as u16);
+            assert_eq!(cpu.registers.sp, 0xFFFC);
+            assert_eq!(cpu.bus.read_byte(0xFFFD), 0x12);
+            assert_eq!(cpu.bus.read_byte(0xFFFC), 0x34);
+            assert_eq!(cycles, 4);
+        }
+    }
+
+    #[test]
+    fn test_execute_push_and_pop_for_all_registers() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.sp = 0xFFFE;
+        cpu.registers.set_bc(0x1122);
+        cpu.registers.set_de(0x3344);
+        cpu.registers.set_hl(0x5566);
+        cpu.registers.set_af(0x77F0);
+
+        cpu.execute(Instruction::PUSH(StackRegisters::BC));
+        cpu.execute(Instruction::PUSH(StackRegisters::DE));
+        cpu.execute(Instruction::PUSH(StackRegisters::HL));
+        cpu.execute(Instruction::PUSH(StackRegisters::AF));
+        assert_eq!(cpu.registers.sp, 0xFFF6);
+
+        cpu.execute(Instruction::POP(StackRegisters::BC));
+        cpu.execute(Instruction::POP(StackRegisters::DE));
+        cpu.execute(Instruction::POP(StackRegisters::HL));
+        cpu.execute(Instruction::POP(StackRegisters::AF));
+        assert_eq!(cpu.registers.sp, 0xFFFE);
+
+        assert_eq!(cpu.registers.get_bc(), 0x77F0);
+        assert_eq!(cpu.registers.get_de(), 0x5566);
+        assert_eq!(cpu.registers.get_hl(), 0x3344);
+        assert_eq!(cpu.registers.get_af(), 0x1120);
+    }
+
+    #[test]
+    fn test_execute_pop_af_masks_lower_nibble_of_f() {
+        let mut cpu = CPU::new();
+
+        cpu.registers.sp = 0xFFFC;
+        cpu.bus.write_byte(0xFFFC, 0xFF);
+        cpu.bus.write_byte(0xFFFD, 0x12);
+
+        cpu.execute(Instruction::POP(StackRegisters::AF));
+        assert_eq!(cpu.registers.a, 0x12);
+        assert_eq!(cpu.registers.get_af(), 0x12F0);
+    }
+
+    fn cpu_with_program(program: &[u8]) -> CPU {
         let mut cpu = CPU::new();
 
-        cpu.registers.a = 244;
-        let immediate: u8 = 0;
-        cpu.registers.f.carry = true;
+        cpu.bus.load_rom(program.to_vec(), 0);
+        cpu.registers.sp = 0xFFFE;
+
+        cpu
+    }
+
+    fn cpu_with_assembly(source: &str) -> CPU {
+        cpu_with_program(&assemble(source).unwrap().image())
+    }
+
+    fn run_until_halt(cpu: &mut CPU) {
+        while !cpu.halted {
+            cpu.step().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_step_fetches_decodes_and_executes() {
+        let mut cpu = cpu_with_program(&[0x3E, 0x05, 0xC6, 0x03, 0xCB, 0x37]);
+
+        assert_eq!(cpu.step(), Ok(2));
+        assert_eq!(cpu.registers.a, 0x05);
+        assert_eq!(cpu.step(), Ok(2));
+        assert_eq!(cpu.registers.a, 0x08);
+        assert_eq!(cpu.step(), Ok(2));
+        assert_eq!(cpu.registers.a, 0x80);
+        assert_eq!(cpu.registers.pc, 6);
+    }
+
+    #[test]
+    fn test_step_decoded_matches_step() {
+        let source = "
+                ld hl, $C000
+                ld b, 7
+                ld c, 6
+                xor a
+            .loop:
+                add a, b
+                ld [hl+], a
+                swap a
+                bit 3, a
+                dec c
+                jr nz, .loop
+                call .done
+            .done:
+                halt
+            ";
+        let mut tables = cpu_with_assembly(source);
+        let mut decoded = cpu_with_assembly(source);
+
+        while !tables.halted {
+            assert_eq!(decoded.step_decoded(), tables.step());
+            assert_eq!(decoded.registers, tables.registers);
+        }
+        assert_eq!(decoded.halted, true);
+        assert_eq!(decoded.cycles, tables.cycles);
+        assert_eq!(decoded.bus.read_byte(0xC005), tables.bus.read_byte(0xC005));
+
+        let mut cpu = cpu_with_program(&[0xD3]);
+        assert_eq!(
+            cpu.step_decoded(),
+            Err(ExecutionError::IllegalOpcode {
+                pc: 0x0000,
+                opcode: 0xD3
+            })
+        );
+    }
+
+    #[test]
+    fn test_step_dispatches_enabled_interrupt() {
+        let mut cpu = cpu_with_program(&[0x00]);
+
+        cpu.ime = true;
+        cpu.registers.pc = 0x0123;
+        cpu.bus.write_byte(INTERRUPT_ENABLE_ADDRESS, 0x1F);
+        cpu.bus.request_interrupt(Interrupt::VBlank);
+
+        assert_eq!(cpu.step(), Ok(INTERRUPT_DISPATCH_CYCLES));
+        assert_eq!(cpu.registers.pc, 0x0040);
+        assert_eq!(cpu.registers.sp, 0xFFFC);
+        assert_eq!(cpu.bus.read_byte(0xFFFD), 0x01);
+        assert_eq!(cpu.bus.read_byte(0xFFFC), 0x23);
+        assert_eq!(cpu.bus.read_byte(INTERRUPT_FLAG_ADDRESS), 0xE0);
+        assert_eq!(cpu.ime, false);
+    }
+
+    #[test]
+    fn test_step_dispatches_highest_priority_interrupt_first() {
+        let mut cpu = cpu_with_program(&[0x00]);
+
+        cpu.ime = true;
+        cpu.bus.write_byte(INTERRUPT_ENABLE_ADDRESS, 0x1F);
+        cpu.bus.request_interrupt(Interrupt::Joypad);
+        cpu.bus.request_interrupt(Interrupt::Timer);
+        cpu.bus.request_interrupt(Interrupt::LcdStat);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.pc, 0x0048);
+        assert_eq!(
+            cpu.bus.read_byte(INTERRUPT_FLAG_ADDRESS),
+            0xE0 | Interrupt::Timer.mask() | Interrupt::Joypad.mask()
+        );
+    }
+
+    #[test]
+    fn test_step_ignores_interrupts_not_enabled_in_ie() {
+        let mut cpu = cpu_with_program(&[0x00]);
+
+        cpu.ime = true;
+        cpu.bus
+            .write_byte(INTERRUPT_ENABLE_ADDRESS, Interrupt::Serial.mask());
+        cpu.bus.request_interrupt(Interrupt::VBlank);
+
+        assert_eq!(cpu.step(), Ok(1));
+        assert_eq!(cpu.registers.pc, 0x0001);
+    }
+
+    #[test]
+    fn test_ei_enables_interrupts_after_the_next_instruction() {
+        let mut cpu = cpu_with_program(&[0xFB, 0x00, 0x00]);
+
+        cpu.bus.write_byte(INTERRUPT_ENABLE_ADDRESS, 0x1F);
+        cpu.bus.request_interrupt(Interrupt::Timer);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.ime, false);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.pc, 0x0002);
+        assert_eq!(cpu.ime, true);
 
-        cpu.execute(Instruction::ADCI(immediate));
-        assert_eq!(cpu.registers.a, 245);
-        assert_eq!(cpu.registers.f.carry, false);
+        assert_eq!(cpu.step(), Ok(INTERRUPT_DISPATCH_CYCLES));
+        assert_eq!(cpu.registers.pc, 0x0050);
+        assert_eq!(cpu.bus.read_byte(0xFFFC), 0x02);
     }
 
     #[test]
-    fn test_execute_adc_immediate_with_overflow() {
-        let mut cpu = CPU::new();
+    fn test_di_cancels_scheduled_ei() {
+        let mut cpu = cpu_with_program(&[0xFB, 0xF3, 0x00]);
 
-        cpu.registers.a = 244;
-        let immediate: u8 = 229;
-        cpu.registers.f.carry = true;
+        cpu.bus.write_byte(INTERRUPT_ENABLE_ADDRESS, 0x1F);
+        cpu.bus.request_interrupt(Interrupt::Timer);
 
-        cpu.execute(Instruction::ADCI(immediate));
-        assert_eq!(cpu.registers.a, (244 + 229 + 1) as u8);
-        assert_eq!(cpu.registers.f.carry, true);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.ime, false);
+        assert_eq!(cpu.registers.pc, 0x0003);
     }
 
     #[test]
-    fn test_execute_sub_reg_for_all_registers() {
+    fn test_reti_enables_interrupts_immediately() {
         let mut cpu = CPU::new();
 
-        cpu.registers = Registers {
-            a: 29,
-            b: 2,
-            c: 3,
-            d: 4,
-            e: 5,
-            f: FlagsRegister::new(),
-            h: 6,
-            l: 7,
-        };
+        cpu.registers.sp = 0xFFFC;
 
-        cpu.execute(Instruction::SUB(ArithmeticRegisters::B));
-        assert_eq!(cpu.registers.a, 27);
+        cpu.execute(Instruction::RETI());
+        assert_eq!(cpu.ime, true);
+        assert_eq!(cpu.ime_scheduled, false);
+    }
 
-        cpu.execute(Instruction::SUB(ArithmeticRegisters::C));
-        assert_eq!(cpu.registers.a, 24);
+    #[test]
+    fn test_halt_waits_for_interrupt() {
+        let mut cpu = cpu_with_program(&[0x76, 0x00]);
 
-        cpu.execute(Instruction::SUB(ArithmeticRegisters::D));
-        assert_eq!(cpu.registers.a, 20);
+        cpu.ime = true;
+        cpu.bus.write_byte(INTERRUPT_ENABLE_ADDRESS, 0x1F);
 
-        cpu.execute(Instruction::SUB(ArithmeticRegisters::E));
-        assert_eq!(cpu.registers.a, 15);
+        cpu.step().unwrap();
+        assert_eq!(cpu.halted, true);
 
-        cpu.execute(Instruction::SUB(ArithmeticRegisters::H));
-        assert_eq!(cpu.registers.a, 9);
+        assert_eq!(cpu.step(), Ok(1));
+        assert_eq!(cpu.step(), Ok(1));
+        assert_eq!(cpu.registers.pc, 0x0001);
 
-        cpu.execute(Instruction::SUB(ArithmeticRegisters::L));
-        assert_eq!(cpu.registers.a, 2);
-        assert_eq!(cpu.registers.f.carry, false);
-        assert_eq!(cpu.registers.f.substraction, true);
+        cpu.bus.request_interrupt(Interrupt::Serial);
+
+        assert_eq!(cpu.step(), Ok(INTERRUPT_DISPATCH_CYCLES));
+        assert_eq!(cpu.halted, false);
+        assert_eq!(cpu.registers.pc, 0x0058);
+        assert_eq!(cpu.bus.read_byte(0xFFFC), 0x01);
     }
 
     #[test]
-    fn test_execute_sub_reg_from_itself_gives_zero() {
-        let mut cpu = CPU::new();
+    fn test_halt_with_ime_off_wakes_without_dispatch() {
+        let mut cpu = cpu_with_program(&[0x76, 0x3C]);
 
-        cpu.registers.a = 255;
+        cpu.bus.write_byte(INTERRUPT_ENABLE_ADDRESS, 0x1F);
 
-        cpu.execute(Instruction::SUB(ArithmeticRegisters::A));
-        assert_eq!(cpu.registers.a, 0);
-        assert_eq!(cpu.registers.f.zero, true);
-        assert_eq!(cpu.registers.f.substraction, true);
+        cpu.step().unwrap();
+        assert_eq!(cpu.halted, true);
+
+        cpu.bus.request_interrupt(Interrupt::Joypad);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.halted, false);
+        assert_eq!(cpu.registers.a, 1);
+        assert_eq!(cpu.registers.pc, 0x0002);
+        assert_eq!(cpu.registers.sp, 0xFFFE);
     }
 
     #[test]
-    fn test_execute_sub_reg_with_overflow() {
-        let mut cpu = CPU::new();
+    fn test_halt_bug_reads_next_byte_twice() {
+        let mut cpu = cpu_with_program(&[0x76, 0x3C, 0x00]);
 
-        cpu.registers.a = 2;
-        cpu.registers.b = 230;
+        cpu.bus.write_byte(INTERRUPT_ENABLE_ADDRESS, 0x1F);
+        cpu.bus.request_interrupt(Interrupt::VBlank);
 
-        cpu.execute(Instruction::SUB(ArithmeticRegisters::B));
-        assert_eq!(cpu.registers.a, (2 - 230) as u8);
-        assert_eq!(cpu.registers.f.carry, true);
+        cpu.step().unwrap();
+        assert_eq!(cpu.halted, false);
+        assert_eq!(cpu.registers.pc, 0x0001);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.a, 1);
+        assert_eq!(cpu.registers.pc, 0x0001);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.a, 2);
+        assert_eq!(cpu.registers.pc, 0x0002);
     }
 
     #[test]
-    fn test_execute_sub_immediate() {
-        let mut cpu = CPU::new();
+    fn test_halt_bug_after_ei_returns_to_halt() {
+        let mut cpu = cpu_with_program(&[0xFB, 0x76, 0x00]);
 
-        cpu.registers.a = 244;
-        let immediate: u8 = 1;
+        cpu.bus.write_byte(INTERRUPT_ENABLE_ADDRESS, 0x1F);
+        cpu.bus.request_interrupt(Interrupt::VBlank);
 
-        cpu.execute(Instruction::SUBI(immediate));
-        assert_eq!(cpu.registers.a, 243);
-        assert_eq!(cpu.registers.f.substraction, true);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.ime, true);
+
+        assert_eq!(cpu.step(), Ok(INTERRUPT_DISPATCH_CYCLES));
+        assert_eq!(cpu.registers.pc, 0x0040);
+        assert_eq!(cpu.bus.read_byte(0xFFFC), 0x01);
+        assert_eq!(cpu.halt_bug, false);
     }
 
     #[test]
-    fn test_execute_sbc_reg_for_all_registers() {
-        let mut cpu = CPU::new();
+    fn test_illegal_opcode_locks_up_the_cpu() {
+        let mut cpu = cpu_with_program(&[0xD3, 0x3C]);
 
-        cpu.registers = Registers {
-            a: 36,
-            b: 2,
-            c: 3,
-            d: 4,
-            e: 5,
-            f: FlagsRegister::new(),
-            h: 6,
-            l: 7,
-        };
+        cpu.ime = true;
+        cpu.error_policy = ErrorPolicy::Hardware;
 
-        cpu.registers.f.carry = true;
-        cpu.execute(Instruction::SBC(ArithmeticRegisters::B));
-        assert_eq!(cpu.registers.a, 33);
-        assert_eq!(cpu.registers.f.carry, false);
+        cpu.step().unwrap();
+        assert_eq!(cpu.locked_up, Some((0x0000, 0xD3)));
 
-        cpu.registers.f.carry = true;
-        cpu.execute(Instruction::SBC(ArithmeticRegisters::C));
-        assert_eq!(cpu.registers.a, 29);
-        assert_eq!(cpu.registers.f.carry, false);
+        cpu.bus.write_byte(INTERRUPT_ENABLE_ADDRESS, 0x1F);
+        cpu.bus.request_interrupt(Interrupt::VBlank);
 
-        cpu.registers.f.carry = true;
-        cpu.execute(Instruction::SBC(ArithmeticRegisters::D));
-        assert_eq!(cpu.registers.a, 24);
-        assert_eq!(cpu.registers.f.carry, false);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.pc, 0x0001);
+        assert_eq!(cpu.registers.a, 0);
+    }
 
-        cpu.registers.f.carry = true;
-        cpu.execute(Instruction::SBC(ArithmeticRegisters::E));
-        assert_eq!(cpu.registers.a, 18);
-        assert_eq!(cpu.registers.f.carry, false);
+    #[test]
+    fn test_report_policy_returns_illegal_opcode_then_locked_up() {
+        let mut cpu = cpu_with_program(&[0x00, 0xDD, 0x3C]);
+
+        cpu.step().unwrap();
+        assert_eq!(
+            cpu.step(),
+            Err(ExecutionError::IllegalOpcode {
+                pc: 0x0001,
+                opcode: 0xDD
+            })
+        );
+        assert_eq!(
+            cpu.step(),
+            Err(ExecutionError::LockedUp {
+                pc: 0x0001,
+                opcode: 0xDD
+            })
+        );
+        assert_eq!(cpu.registers.a, 0);
+        assert_eq!(cpu.cycles, 3);
+    }
 
-        cpu.registers.f.carry = true;
-        cpu.execute(Instruction::SBC(ArithmeticRegisters::H));
-        assert_eq!(cpu.registers.a, 11);
-        assert_eq!(cpu.registers.f.carry, false);
+    #[test]
+    fn test_nop_policy_skips_illegal_opcodes() {
+        let mut cpu = cpu_with_program(&[0xDD, 0x3C]);
 
-        cpu.registers.f.carry = true;
-        cpu.execute(Instruction::SBC(ArithmeticRegisters::L));
-        assert_eq!(cpu.registers.a, 3);
-        assert_eq!(cpu.registers.f.carry, false);
+        cpu.error_policy = ErrorPolicy::Nop;
 
-        assert_eq!(cpu.registers.f.substraction, true);
+        assert_eq!(cpu.step(), Ok(1));
+        assert_eq!(cpu.step(), Ok(1));
+        assert_eq!(cpu.registers.a, 1);
+        assert_eq!(cpu.locked_up, None);
     }
 
     #[test]
-    fn test_execute_sbc_immediate() {
-        let mut cpu = CPU::new();
+    fn test_report_policy_returns_stop_encountered() {
+        let mut cpu = cpu_with_program(&[0x00, 0x10, 0x00, 0x3C]);
+
+        cpu.step().unwrap();
+        assert_eq!(
+            cpu.step(),
+            Err(ExecutionError::StopEncountered {
+                pc: 0x0001,
+                opcode: 0x10
+            })
+        );
+        assert_eq!(cpu.registers.pc, 0x0003);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.a, 1);
+    }
 
-        cpu.registers.a = 244;
-        let immediate: u8 = 100;
-        cpu.registers.f.carry = true;
+    #[test]
+    fn test_other_policies_run_through_stop() {
+        for &policy in &[ErrorPolicy::Nop, ErrorPolicy::Hardware] {
+            let mut cpu = cpu_with_program(&[0x10, 0x00, 0x3C]);
 
-        cpu.execute(Instruction::SBCI(immediate));
-        assert_eq!(cpu.registers.a, 143);
-        assert_eq!(cpu.registers.f.carry, false);
+            cpu.error_policy = policy;
+
+            assert_eq!(cpu.step(), Ok(2), "{:?}", policy);
+            assert_eq!(cpu.step(), Ok(1), "{:?}", policy);
+            assert_eq!(cpu.registers.a, 1, "{:?}", policy);
+        }
     }
 
     #[test]
-    fn test_execute_sbc_immediate_with_overflow() {
-        let mut cpu = CPU::new();
+    fn test_execution_error_display() {
+        let error = ExecutionError::IllegalOpcode {
+            pc: 0x0150,
+            opcode: 0xFC,
+        };
 
-        cpu.registers.a = 10;
-        let immediate: u8 = 10;
-        cpu.registers.f.carry = true;
+        assert_eq!(error.to_string(), "illegal opcode 0xfc at 0x0150");
+    }
 
-        cpu.execute(Instruction::SBCI(immediate));
-        assert_eq!(cpu.registers.a, (0 - 1) as u8);
-        assert_eq!(cpu.registers.f.carry, true);
+    #[test]
+    fn test_step_keeps_running_cycle_count() {
+        let mut cpu = cpu_with_program(&[0x00, 0x3E, 0x01, 0xC3, 0x00, 0x00]);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.cycles, 1);
+        cpu.step().unwrap();
+        assert_eq!(cpu.cycles, 3);
+        cpu.step().unwrap();
+        assert_eq!(cpu.cycles, 7);
+        cpu.step().unwrap();
+        assert_eq!(cpu.cycles, 8);
     }
 
     #[test]
-    fn test_execute_and_reg() {
-        let mut cpu = CPU::new();
+    fn test_step_counts_interrupt_dispatch_and_halted_cycles() {
+        let mut cpu = cpu_with_program(&[0x76]);
 
-        cpu.registers.a = 1;
-        cpu.registers.b = 3;
-        cpu.registers.c = 0;
+        cpu.ime = true;
+        cpu.bus.write_byte(INTERRUPT_ENABLE_ADDRESS, 0x1F);
 
-        cpu.execute(Instruction::AND(ArithmeticRegisters::B));
-        assert_eq!(cpu.registers.a, 1);
-        assert_eq!(cpu.registers.f.zero, false);
-        assert_eq!(cpu.registers.f.substraction, false);
-        assert_eq!(cpu.registers.f.half_carry, true);
-        assert_eq!(cpu.registers.f.carry, false);
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        cpu.step().unwrap();
+        assert_eq!(cpu.cycles, 3);
 
-        cpu.execute(Instruction::AND(ArithmeticRegisters::C));
-        assert_eq!(cpu.registers.a, 0);
-        assert_eq!(cpu.registers.f.zero, true);
-        assert_eq!(cpu.registers.f.substraction, false);
-        assert_eq!(cpu.registers.f.half_carry, true);
-        assert_eq!(cpu.registers.f.carry, false);
+        cpu.bus.request_interrupt(Interrupt::VBlank);
+
+        cpu.step().unwrap();
+        assert_eq!(cpu.cycles, 3 + INTERRUPT_DISPATCH_CYCLES as u64);
     }
 
-    #[test]
-    fn test_execute_and_immediate() {
-        let mut cpu = CPU::new();
+    /// Clock cycles of every unprefixed opcode as published in the instruction tables, with the
+    /// not-taken timing for conditional branches. Illegal opcodes and the CB prefix are zero.
+    /// STOP is listed with 4 cycles but also fetches the padding byte that follows it.
+    #[rustfmt::skip]
+    const OPCODE_TIMINGS: [u8; 256] = [
+        4, 12,  8,  8,  4,  4,  8,  4, 20,  8,  8,  8,  4,  4,  8,  4,
+        8, 12,  8,  8,  4,  4,  8,  4, 12,  8,  8,  8,  4,  4,  8,  4,
+        8, 12,  8,  8,  4,  4,  8,  4,  8,  8,  8,  8,  4,  4,  8,  4,
+        8, 12,  8,  8, 12, 12, 12,  4,  8,  8,  8,  8,  4,  4,  8,  4,
+        4,  4,  4,  4,  4,  4,  8,  4,  4,  4,  4,  4,  4,  4,  8,  4,
+        4,  4,  4,  4,  4,  4,  8,  4,  4,  4,  4,  4,  4,  4,  8,  4,
+        4,  4,  4,  4,  4,  4,  8,  4,  4,  4,  4,  4,  4,  4,  8,  4,
+        8,  8,  8,  8,  8,  8,  4,  8,  4,  4,  4,  4,  4,  4,  8,  4,
+        4,  4,  4,  4,  4,  4,  8,  4,  4,  4,  4,  4,  4,  4,  8,  4,
+        4,  4,  4,  4,  4,  4,  8,  4,  4,  4,  4,  4,  4,  4,  8,  4,
+        4,  4,  4,  4,  4,  4,  8,  4,  4,  4,  4,  4,  4,  4,  8,  4,
+        4,  4,  4,  4,  4,  4,  8,  4,  4,  4,  4,  4,  4,  4,  8,  4,
+        8, 12, 12, 16, 12, 16,  8, 16,  8, 16, 12,  0, 12, 24,  8, 16,
+        8, 12, 12,  0, 12, 16,  8, 16,  8, 16, 12,  0, 12,  0,  8, 16,
+       12, 12,  8,  0,  0, 16,  8, 16, 16,  4, 16,  0,  0,  0,  8, 16,
+       12, 12,  8,  4,  0, 16,  8, 16, 12,  8, 16,  4,  0,  0,  8, 16,
+    ];
+
+    /// Clock cycles of the conditional branches when the branch is taken.
+    const TAKEN_BRANCH_TIMINGS: [(u8, u8); 16] = [
+        (0x20, 12),
+        (0x28, 12),
+        (0x30, 12),
+        (0x38, 12),
+        (0xC0, 20),
+        (0xC8, 20),
+        (0xD0, 20),
+        (0xD8, 20),
+        (0xC2, 16),
+        (0xCA, 16),
+        (0xD2, 16),
+        (0xDA, 16),
+        (0xC4, 24),
+        (0xCC, 24),
+        (0xD4, 24),
+        (0xDC, 24),
+    ];
+
+    fn is_branch_taken(opcode: u8, flags: FlagsRegister) -> bool {
+        match (opcode >> 3) & 0b11 {
+            0 => !flags.zero,
+            1 => flags.zero,
+            2 => !flags.carry,
+            _ => flags.carry,
+        }
+    }
 
-        cpu.registers.a = 3;
-        let immediate: u8 = 2;
+    /// Clock cycles `step` takes on `program`, with HL pointing into work RAM.
+    fn measure_clock_cycles(program: &[u8], flags: FlagsRegister) -> u8 {
+        let mut cpu = cpu_with_program(program);
 
-        cpu.execute(Instruction::ANDI(immediate));
-        assert_eq!(cpu.registers.a, 2);
+        cpu.error_policy = ErrorPolicy::Hardware;
+        cpu.registers.f = flags;
+        cpu.registers.set_hl(0xC000);
 
-        assert_eq!(cpu.registers.f.zero, false);
-        assert_eq!(cpu.registers.f.substraction, false);
-        assert_eq!(cpu.registers.f.half_carry, true);
-        assert_eq!(cpu.registers.f.carry, false);
+        cpu.step().unwrap() * 4
     }
 
     #[test]
-    fn test_execute_or_reg() {
-        let mut cpu = CPU::new();
-
-        cpu.registers.a = 1;
-        cpu.registers.b = 4;
+    fn test_step_timing_of_every_unprefixed_opcode() {
+        for opcode in 0..=0xFFu8 {
+            if ILLEGAL_OPCODES.contains(&opcode) || opcode == PREFIX_BYTE {
+                continue;
+            }
 
-        cpu.execute(Instruction::OR(ArithmeticRegisters::B));
-        assert_eq!(cpu.registers.a, 5);
-        assert_eq!(cpu.registers.f.zero, false);
-        assert_eq!(cpu.registers.f.substraction, false);
-        assert_eq!(cpu.registers.f.half_carry, false);
-        assert_eq!(cpu.registers.f.carry, false);
+            for &flags in &[FlagsRegister::from(0x00), FlagsRegister::from(0xF0)] {
+                let taken = TAKEN_BRANCH_TIMINGS
+                    .iter()
+                    .find(|&&(branch, _)| branch == opcode)
+                    .filter(|_| is_branch_taken(opcode, flags));
+                let expected = match taken {
+                    Some(&(_, cycles)) => cycles,
+                    None => OPCODE_TIMINGS[opcode as usize],
+                };
+
+                assert_eq!(
+                    measure_clock_cycles(&[opcode, 0x00, 0x00], flags),
+                    expected,
+                    "opcode {:#04x} with flags {:?}",
+                    opcode,
+                    flags
+                );
+            }
+        }
+    }
 
-        cpu.registers.a = 0;
-        cpu.registers.c = 0;
+    #[test]
+    fn test_step_timing_of_every_prefixed_opcode() {
+        for opcode in 0..=0xFFu8 {
+            let expected = match (opcode >> 6, opcode & 0b111) {
+                (1, 6) => 12,
+                (_, 6) => 16,
+                _ => 8,
+            };
+
+            assert_eq!(
+                measure_clock_cycles(&[PREFIX_BYTE, opcode], FlagsRegister::new()),
+                expected,
+                "opcode 0xcb {:#04x}",
+                opcode
+            );
+        }
+    }
 
-        cpu.execute(Instruction::OR(ArithmeticRegisters::C));
-        assert_eq!(cpu.registers.a, 0);
-        assert_eq!(cpu.registers.f.zero, true);
-        assert_eq!(cpu.registers.f.substraction, false);
-        assert_eq!(cpu.registers.f.half_carry, false);
-        assert_eq!(cpu.registers.f.carry, false);
+    /// 61 NOPs followed by LDH A,(DIV), whose read happens on its third machine cycle, the 64th
+    /// since power on.
+    fn div_read_program() -> Vec<u8> {
+        let mut program = vec![0x00; 61];
+        program.extend_from_slice(&[0xF0, 0x04]);
+        program
     }
 
     #[test]
-    fn test_execute_or_immediate() {
-        let mut cpu = CPU::new();
-
-        cpu.registers.a = 1;
-        let immediate: u8 = 2;
+    fn test_precise_timing_reads_div_on_the_cycle_of_the_access() {
+        let mut cpu = cpu_with_program(&div_read_program());
+        cpu.timing_mode = TimingMode::Precise;
 
-        cpu.execute(Instruction::ORI(immediate));
-        assert_eq!(cpu.registers.a, 3);
+        for _ in 0..62 {
+            cpu.step().unwrap();
+        }
 
-        assert_eq!(cpu.registers.f.zero, false);
-        assert_eq!(cpu.registers.f.substraction, false);
-        assert_eq!(cpu.registers.f.half_carry, false);
-        assert_eq!(cpu.registers.f.carry, false);
+        assert_eq!(cpu.registers.a, 1);
     }
 
     #[test]
-    fn test_execute_xor_reg() {
-        let mut cpu = CPU::new();
+    fn test_fast_timing_reads_div_before_the_instruction_catches_up() {
+        let mut cpu = cpu_with_program(&div_read_program());
 
-        cpu.registers.a = 5;
-        cpu.registers.b = 5;
+        for _ in 0..62 {
+            cpu.step().unwrap();
+        }
 
-        cpu.execute(Instruction::XOR(ArithmeticRegisters::B));
         assert_eq!(cpu.registers.a, 0);
-        assert_eq!(cpu.registers.f.zero, true);
-        assert_eq!(cpu.registers.f.substraction, false);
-        assert_eq!(cpu.registers.f.half_carry, false);
-        assert_eq!(cpu.registers.f.carry, false);
+        assert_eq!(cpu.bus.read_byte(DIV_ADDRESS), 1);
+    }
 
-        cpu.registers.a = 5;
-        cpu.registers.c = 2;
+    #[test]
+    fn test_both_timing_modes_tick_the_bus_for_every_cycle() {
+        for &mode in &[TimingMode::Fast, TimingMode::Precise] {
+            let mut cpu = cpu_with_program(&[0xC3, 0x00, 0x00]);
+            cpu.timing_mode = mode;
 
-        cpu.execute(Instruction::XOR(ArithmeticRegisters::C));
-        assert_eq!(cpu.registers.a, 7);
-        assert_eq!(cpu.registers.f.zero, false);
-        assert_eq!(cpu.registers.f.substraction, false);
-        assert_eq!(cpu.registers.f.half_carry, false);
-        assert_eq!(cpu.registers.f.carry, false);
+            while cpu.cycles < 256 {
+                cpu.step().unwrap();
+            }
+
+            assert_eq!(cpu.cycles, 256, "{:?}", mode);
+            assert_eq!(cpu.bus.read_byte(DIV_ADDRESS), 4, "{:?}", mode);
+        }
     }
 
     #[test]
-    fn test_execute_xor_immediate() {
-        let mut cpu = CPU::new();
+    fn test_assembled_multiplication_loop() {
+        let mut cpu = cpu_with_assembly(
+            "
+                ld b, 7
+                ld c, 6
+                xor a
+            .loop:
+                add a, b
+                dec c
+                jr nz, .loop
+                halt
+            ",
+        );
+
+        run_until_halt(&mut cpu);
+
+        assert_eq!(cpu.registers.a, 42);
+        assert_eq!(cpu.registers.f.zero, true);
+    }
 
-        cpu.registers.a = 9;
-        let immediate: u8 = 6;
+    #[test]
+    fn test_assembled_subroutine_copies_memory() {
+        let mut cpu = cpu_with_assembly(
+            r#"
+                ld hl, Source
+                ld de, $C000
+                ld c, Source.end - Source
+                call Copy
+                halt
+
+            Copy:
+                ld a, [hl+]
+                ld [de], a
+                inc de
+                dec c
+                jr nz, Copy
+                ret
+
+            Source:
+                db "SM83"
+            .end:
+            "#,
+        );
+
+        run_until_halt(&mut cpu);
+
+        let copied: Vec<u8> = (0xC000..0xC004).map(|a| cpu.bus.read_byte(a)).collect();
+        assert_eq!(copied, b"SM83".to_vec());
+        assert_eq!(cpu.registers.sp, 0xFFFE);
+    }
 
-        cpu.execute(Instruction::XORI(immediate));
-        assert_eq!(cpu.registers.a, 15);
+    /// Xorshift generator, so the flag test sees plenty of operands and stays reproducible.
+    struct Random(u32);
 
-        assert_eq!(cpu.registers.f.zero, false);
-        assert_eq!(cpu.registers.f.substraction, false);
-        assert_eq!(cpu.registers.f.half_carry, false);
-        assert_eq!(cpu.registers.f.carry, false);
+    impl Random {
+        fn byte(&mut self) -> u8 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 17;
+            self.0 ^= self.0 << 5;
+            (self.0 >> 24) as u8
+        }
+
+        /// Mostly values on the edge of a carry or a zero result, otherwise any byte.
+        fn operand(&mut self) -> u8 {
+            const EDGES: [u8; 8] = [0x00, 0x01, 0x0F, 0x10, 0x7F, 0x80, 0xF0, 0xFF];
+            match self.byte() & 0b11 {
+                0 => self.byte(),
+                _ => EDGES[(self.byte() & 0b111) as usize],
+            }
+        }
     }
 
+    #[test]
+    fn test_execute_only_touches_flags_listed_in_opcode_table() {
+        const FLAG_NAMES: [&str; 4] = ["zero", "substraction", "half_carry", "carry"];
+
+        let mut random = Random(0x2545_F491);
+        let mut cpu = CPU::new();
+        cpu.bus
+            .load_rom((0..0x8000).map(|_| random.byte()).collect(), 0);
+
+        for &prefixed in &[false, true] {
+            for opcode in 0..=0xFFu8 {
+                let info = if prefixed {
+                    &PREFIXED_OPCODES[opcode as usize]
+                } else {
+                    match &UNPREFIXED_OPCODES[opcode as usize] {
+                        Some(info) => info,
+                        None => continue,
+                    }
+                };
+                let effects = [
+                    info.flags.zero,
+                    info.flags.substraction,
+                    info.flags.half_carry,
+                    info.flags.carry,
+                ];
+                let mut outcomes = [[false; 2]; 4];
+
+                for _ in 0..1024 {
+                    let instruction =
+                        Instruction::from_byte(opcode, prefixed, || random.operand()).unwrap();
+                    cpu.registers = Registers {
+                        a: random.operand(),
+                        b: random.operand(),
+                        c: random.operand(),
+                        d: random.operand(),
+                        e: random.operand(),
+                        f: FlagsRegister::from(random.byte() & 0xF0),
+                        h: random.operand(),
+                        l: random.operand(),
+                        sp: u16::from(random.byte()) << 8 | u16::from(random.operand()),
+                        pc: 0x0100,
+                    };
+                    let hl = cpu.registers.get_hl();
+                    let sp = cpu.registers.sp;
+                    cpu.bus.write_byte(hl, random.operand());
+                    cpu.bus.write_byte(sp, random.byte());
+                    cpu.bus.write_byte(sp.wrapping_add(1), random.byte());
+                    cpu.step_cycles = 0;
+
+                    let before = cpu.registers.f;
+                    cpu.execute(instruction);
+                    let after = cpu.registers.f;
+
+                    let flags = [
+                        (before.zero, after.zero),
+                        (before.substraction, after.substraction),
+                        (before.half_carry, after.half_carry),
+                        (before.carry, after.carry),
+                    ];
+                    for (index, &(before, after)) in flags.iter().enumerate() {
+                        let expected = match effects[index] {
+                            FlagEffect::Unaffected => before,
+                            FlagEffect::Set => true,
+                            FlagEffect::Reset => false,
+                            FlagEffect::Modified => {
+                                outcomes[index][after as usize] = true;
+                                after
+                            }
+                        };
+                        assert_eq!(
+                            after, expected,
+                            "{} flag of {}",
+                            FLAG_NAMES[index], instruction
+                        );
+                    }
+                }
+
+                for (index, &effect) in effects.iter().enumerate() {
+                    if effect == FlagEffect::Modified {
+                        assert_eq!(
+                            outcomes[index],
+                            [true, true],
+                            "{} flag of {} never changes",
+                            FLAG_NAMES[index],
+                            info.mnemonic
+                        );
+                    }
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,275 @@
+use super::cartridge::{rom_offset, NINTENDO_LOGO, RAM_BANK_SIZE, ROM_BANK_SIZE};
+use super::memorybus::{EXTERNAL_RAM_START, OPEN_BUS_VALUE};
+
+/// Bank a multicart's second game starts at. Its header sits at the same offset within the
+/// bank as the header of the first game.
+const MULTICART_SECOND_GAME_BANK: usize = 0x10;
+const LOGO_OFFSET: usize = 0x0104;
+
+/// The MBC1. Writes to the ROM select banks:
+/// - 0x0000-0x1FFF enables RAM when the lower nibble is 0xA.
+/// - 0x2000-0x3FFF sets the five bit BANK1 register, the lower bits of the ROM bank. Zero is
+///   turned into one, so banks 0x00, 0x20, 0x40 and 0x60 never show up at 0x4000.
+/// - 0x4000-0x5FFF sets the two bit BANK2 register, the upper bits of the ROM bank.
+/// - 0x6000-0x7FFF sets the mode. In mode 1 BANK2 also selects the bank at 0x0000 and the RAM
+///   bank.
+pub struct Mbc1 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    ram_enabled: bool,
+    bank1: u8,
+    bank2: u8,
+    advanced_banking: bool,
+    /// MBC1M wiring, where BANK1 drives four ROM address lines instead of five and BANK2 picks
+    /// one of four 256 KiB games.
+    multicart: bool,
+}
+
+impl Mbc1 {
+    pub fn new(rom: Vec<u8>, ram_size: usize) -> Self {
+        let multicart = is_multicart(&rom);
+        Mbc1 {
+            rom,
+            ram: vec![0; ram_size],
+            ram_enabled: false,
+            bank1: 1,
+            bank2: 0,
+            advanced_banking: false,
+            multicart,
+        }
+    }
+
+    pub fn is_multicart(&self) -> bool {
+        self.multicart
+    }
+
+    pub fn read_rom(&self, address: u16) -> u8 {
+        let bank = if address < ROM_BANK_SIZE as u16 {
+            if self.advanced_banking {
+                self.upper_bank_bits()
+            } else {
+                0
+            }
+        } else {
+            self.upper_bank_bits() | self.lower_bank_bits()
+        };
+
+        let offset = rom_offset(self.rom.len(), bank, address);
+        self.rom.get(offset).copied().unwrap_or(OPEN_BUS_VALUE)
+    }
+
+    pub fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enabled = value & 0x0F == 0x0A,
+            0x2000..=0x3FFF => self.bank1 = (value & 0x1F).max(1),
+            0x4000..=0x5FFF => self.bank2 = value & 0b11,
+            _ => self.advanced_banking = value & 1 != 0,
+        }
+    }
+
+    pub fn read_ram(&self, address: u16) -> u8 {
+        match self.ram_offset(address) {
+            Some(offset) => self.ram[offset],
+            None => OPEN_BUS_VALUE,
+        }
+    }
+
+    pub fn write_ram(&mut self, address: u16, value: u8) {
+        if let Some(offset) = self.ram_offset(address) {
+            self.ram[offset] = value;
+        }
+    }
+
+    fn lower_bank_bits(&self) -> usize {
+        if self.multicart {
+            (self.bank1 & 0x0F) as usize
+        } else {
+            self.bank1 as usize
+        }
+    }
+
+    fn upper_bank_bits(&self) -> usize {
+        if self.multicart {
+            (self.bank2 as usize) << 4
+        } else {
+            (self.bank2 as usize) << 5
+        }
+    }
+
+    fn ram_offset(&self, address: u16) -> Option<usize> {
+        if !self.ram_enabled || self.ram.is_empty() {
+            return None;
+        }
+
+        let bank = if self.advanced_banking {
+            self.bank2 as usize
+        } else {
+            0
+        };
+        let offset = bank * RAM_BANK_SIZE + (address - EXTERNAL_RAM_START) as usize;
+        Some(offset % self.ram.len())
+    }
+}
+
+/// MBC1M multicarts are 1 MiB and carry a second game, with a header and logo of its own, in
+/// bank 0x10.
+fn is_multicart(rom: &[u8]) -> bool {
+    let logo = MULTICART_SECOND_GAME_BANK * ROM_BANK_SIZE + LOGO_OFFSET;
+    rom.len() == 0x10_0000 && rom[logo..logo + NINTENDO_LOGO.len()] == NINTENDO_LOGO
+}
+
+#[cfg(test)]
+mod mbc1_tests {
+    use super::*;
+    use crate::cartridge::numbered_rom;
+
+    fn select(mbc: &mut Mbc1, bank1: u8, bank2: u8) {
+        mbc.write_rom(0x2000, bank1);
+        mbc.write_rom(0x4000, bank2);
+    }
+
+    #[test]
+    fn test_bank_zero_maps_to_one() {
+        let mut mbc = Mbc1::new(numbered_rom(128), 0);
+
+        assert_eq!(mbc.read_rom(0x4000), 0x01);
+
+        select(&mut mbc, 0x00, 0);
+        assert_eq!(mbc.read_rom(0x4000), 0x01);
+
+        select(&mut mbc, 0x20, 1);
+        assert_eq!(mbc.read_rom(0x4000), 0x21);
+
+        select(&mut mbc, 0x1F, 1);
+        assert_eq!(mbc.read_rom(0x7FFF), 0x3F);
+        assert_eq!(mbc.read_rom(0x0000), 0x00);
+    }
+
+    #[test]
+    fn test_banking_wraps_on_2_mib_rom() {
+        let mut mbc = Mbc1::new(numbered_rom(128), 0);
+
+        select(&mut mbc, 0x1F, 3);
+        assert_eq!(mbc.read_rom(0x4000), 0x7F);
+        assert_eq!(mbc.read_rom(0x0000), 0x00);
+
+        mbc.write_rom(0x6000, 1);
+        assert_eq!(mbc.read_rom(0x0000), 0x60);
+        assert_eq!(mbc.read_rom(0x3FFF), 0x60);
+        assert_eq!(mbc.read_rom(0x4000), 0x7F);
+
+        select(&mut mbc, 0x00, 2);
+        assert_eq!(mbc.read_rom(0x0000), 0x40);
+        assert_eq!(mbc.read_rom(0x4000), 0x41);
+    }
+
+    #[test]
+    fn test_banking_wraps_on_1_mib_rom() {
+        let mut mbc = Mbc1::new(numbered_rom(64), 0);
+
+        select(&mut mbc, 0x05, 2);
+        assert_eq!(mbc.read_rom(0x4000), 0x05);
+
+        select(&mut mbc, 0x05, 3);
+        assert_eq!(mbc.read_rom(0x4000), 0x25);
+
+        mbc.write_rom(0x6000, 1);
+        assert_eq!(mbc.read_rom(0x0000), 0x20);
+
+        select(&mut mbc, 0x05, 2);
+        assert_eq!(mbc.read_rom(0x0000), 0x00);
+    }
+
+    #[test]
+    fn test_banking_wraps_on_small_rom() {
+        let mut mbc = Mbc1::new(numbered_rom(4), 0);
+
+        select(&mut mbc, 0x06, 1);
+        assert_eq!(mbc.read_rom(0x4000), 0x02);
+
+        select(&mut mbc, 0x04, 0);
+        assert_eq!(mbc.read_rom(0x4000), 0x00);
+    }
+
+    #[test]
+    fn test_ram_needs_enabling() {
+        let mut mbc = Mbc1::new(numbered_rom(4), 0x2000);
+
+        mbc.write_ram(0xA000, 0x42);
+        assert_eq!(mbc.read_ram(0xA000), OPEN_BUS_VALUE);
+
+        mbc.write_rom(0x0000, 0x0A);
+        mbc.write_ram(0xA000, 0x42);
+        assert_eq!(mbc.read_ram(0xA000), 0x42);
+
+        mbc.write_rom(0x1FFF, 0x1B);
+        assert_eq!(mbc.read_ram(0xA000), OPEN_BUS_VALUE);
+    }
+
+    #[test]
+    fn test_ram_banks_switch_in_mode_1_only() {
+        let mut mbc = Mbc1::new(numbered_rom(4), 0x8000);
+        mbc.write_rom(0x0000, 0x0A);
+
+        mbc.write_ram(0xA000, 0x10);
+        mbc.write_rom(0x4000, 2);
+        mbc.write_ram(0xA000, 0x12);
+        assert_eq!(mbc.read_ram(0xA000), 0x12);
+
+        mbc.write_rom(0x6000, 1);
+        assert_eq!(mbc.read_ram(0xA000), 0x00);
+        mbc.write_ram(0xBFFF, 0x22);
+
+        mbc.write_rom(0x4000, 0);
+        assert_eq!(mbc.read_ram(0xA000), 0x12);
+
+        mbc.write_rom(0x6000, 0);
+        mbc.write_rom(0x4000, 2);
+        assert_eq!(mbc.read_ram(0xBFFF), 0x00);
+        mbc.write_rom(0x6000, 1);
+        assert_eq!(mbc.read_ram(0xBFFF), 0x22);
+    }
+
+    #[test]
+    fn test_small_ram_mirrors() {
+        let mut mbc = Mbc1::new(numbered_rom(4), 0x800);
+        mbc.write_rom(0x0000, 0x0A);
+
+        mbc.write_ram(0xA000, 0x42);
+
+        assert_eq!(mbc.read_ram(0xA800), 0x42);
+        assert_eq!(mbc.read_ram(0xB800), 0x42);
+    }
+
+    #[test]
+    fn test_multicart_banking() {
+        let mut rom = numbered_rom(64);
+        for &bank in &[0x00, 0x10] {
+            let logo = bank * ROM_BANK_SIZE + LOGO_OFFSET;
+            rom[logo..logo + NINTENDO_LOGO.len()].copy_from_slice(&NINTENDO_LOGO);
+        }
+        let mut mbc = Mbc1::new(rom, 0);
+        assert_eq!(mbc.is_multicart(), true);
+
+        select(&mut mbc, 0x01, 1);
+        assert_eq!(mbc.read_rom(0x4000), 0x11);
+
+        select(&mut mbc, 0x12, 1);
+        assert_eq!(mbc.read_rom(0x4000), 0x12);
+
+        select(&mut mbc, 0x10, 3);
+        assert_eq!(mbc.read_rom(0x4000), 0x30);
+
+        mbc.write_rom(0x6000, 1);
+        select(&mut mbc, 0x01, 2);
+        assert_eq!(mbc.read_rom(0x0000), 0x20);
+        assert_eq!(mbc.read_rom(0x4000), 0x21);
+    }
+
+    #[test]
+    fn test_plain_1_mib_rom_is_not_multicart() {
+        let mbc = Mbc1::new(numbered_rom(64), 0);
+
+        assert_eq!(mbc.is_multicart(), false);
+    }
+}
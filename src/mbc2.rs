@@ -0,0 +1,137 @@
+use super::cartridge::{rom_offset, ROM_BANK_SIZE};
+use super::memorybus::{EXTERNAL_RAM_START, OPEN_BUS_VALUE};
+
+/// Half bytes of RAM built into the MBC2.
+pub const RAM_SIZE: usize = 512;
+
+/// Bit 8 of addresses in 0x0000-0x3FFF picks the register a write goes to.
+const REGISTER_SELECT: u16 = 1 << 8;
+
+/// The MBC2, with up to 256 KiB of ROM and 512 half bytes of RAM inside the controller. A
+/// write to 0x0000-0x3FFF enables RAM when bit 8 of the address is clear, and selects the ROM
+/// bank at 0x4000 from its lower nibble when it is set. Bank 0 selects bank 1.
+pub struct Mbc2 {
+    rom: Vec<u8>,
+    /// Only the lower nibble of each byte is stored, the upper one reads as set.
+    ram: Vec<u8>,
+    ram_enabled: bool,
+    rom_bank: u8,
+}
+
+impl Mbc2 {
+    pub fn new(rom: Vec<u8>) -> Self {
+        Mbc2 {
+            rom,
+            ram: vec![0; RAM_SIZE],
+            ram_enabled: false,
+            rom_bank: 1,
+        }
+    }
+
+    pub fn read_rom(&self, address: u16) -> u8 {
+        let bank = if address < ROM_BANK_SIZE as u16 {
+            0
+        } else {
+            self.rom_bank as usize
+        };
+
+        let offset = rom_offset(self.rom.len(), bank, address);
+        self.rom.get(offset).copied().unwrap_or(OPEN_BUS_VALUE)
+    }
+
+    pub fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x3FFF if address & REGISTER_SELECT == 0 => {
+                self.ram_enabled = value & 0x0F == 0x0A;
+            }
+            0x0000..=0x3FFF => self.rom_bank = (value & 0x0F).max(1),
+            _ => {}
+        }
+    }
+
+    pub fn read_ram(&self, address: u16) -> u8 {
+        if self.ram_enabled {
+            self.ram[ram_offset(address)] | 0xF0
+        } else {
+            OPEN_BUS_VALUE
+        }
+    }
+
+    pub fn write_ram(&mut self, address: u16, value: u8) {
+        if self.ram_enabled {
+            self.ram[ram_offset(address)] = value & 0x0F;
+        }
+    }
+}
+
+/// The RAM only decodes nine address lines, so it repeats through 0xA000-0xBFFF.
+fn ram_offset(address: u16) -> usize {
+    (address - EXTERNAL_RAM_START) as usize % RAM_SIZE
+}
+
+#[cfg(test)]
+mod mbc2_tests {
+    use super::*;
+    use crate::cartridge::numbered_rom;
+
+    #[test]
+    fn test_address_bit_8_selects_register() {
+        let mut mbc = Mbc2::new(numbered_rom(16));
+
+        mbc.write_rom(0x0000, 0x05);
+        assert_eq!(mbc.read_rom(0x4000), 0x01);
+
+        mbc.write_rom(0x0100, 0x05);
+        assert_eq!(mbc.read_rom(0x4000), 0x05);
+
+        mbc.write_rom(0x3FFF, 0x0A);
+        assert_eq!(mbc.read_rom(0x4000), 0x0A);
+        assert_eq!(mbc.read_ram(0xA000), OPEN_BUS_VALUE);
+
+        mbc.write_rom(0x3EFF, 0x0A);
+        assert_eq!(mbc.read_rom(0x4000), 0x0A);
+        assert_eq!(mbc.read_ram(0xA000), 0xF0);
+    }
+
+    #[test]
+    fn test_rom_bank_zero_maps_to_one_and_wraps() {
+        let mut mbc = Mbc2::new(numbered_rom(4));
+
+        mbc.write_rom(0x2100, 0x00);
+        assert_eq!(mbc.read_rom(0x4000), 0x01);
+
+        mbc.write_rom(0x2100, 0x17);
+        assert_eq!(mbc.read_rom(0x7FFF), 0x03);
+        assert_eq!(mbc.read_rom(0x3FFF), 0x00);
+
+        mbc.write_rom(0x4100, 0x02);
+        assert_eq!(mbc.read_rom(0x4000), 0x03);
+    }
+
+    #[test]
+    fn test_ram_stores_lower_nibble_and_mirrors() {
+        let mut mbc = Mbc2::new(numbered_rom(2));
+        mbc.write_rom(0x0000, 0x0A);
+
+        mbc.write_ram(0xA000, 0x5C);
+        mbc.write_ram(0xA1FF, 0x03);
+
+        assert_eq!(mbc.read_ram(0xA000), 0xFC);
+        assert_eq!(mbc.read_ram(0xA200), 0xFC);
+        assert_eq!(mbc.read_ram(0xBE00), 0xFC);
+        assert_eq!(mbc.read_ram(0xBFFF), 0xF3);
+
+        mbc.write_ram(0xB001, 0x07);
+        assert_eq!(mbc.read_ram(0xA001), 0xF7);
+    }
+
+    #[test]
+    fn test_disabled_ram_ignores_writes() {
+        let mut mbc = Mbc2::new(numbered_rom(2));
+
+        mbc.write_ram(0xA000, 0x05);
+        mbc.write_rom(0x0000, 0x0A);
+
+        assert_eq!(mbc.read_ram(0xA000), 0xF0);
+    }
+}
@@ -0,0 +1,602 @@
+use std::time::{Duration, Instant};
+
+use super::cartridge::{rom_offset, RAM_BANK_SIZE, ROM_BANK_SIZE};
+use super::memorybus::{EXTERNAL_RAM_START, OPEN_BUS_VALUE};
+
+/// Machine cycles in one second of emulated time.
+pub const CYCLES_PER_SECOND: u64 = 1 << 20;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+/// The day counter is nine bits wide.
+const DAYS: u64 = 512;
+
+const RTC_SECONDS: u8 = 0x08;
+const RTC_MINUTES: u8 = 0x09;
+const RTC_HOURS: u8 = 0x0A;
+const RTC_DAY_LOW: u8 = 0x0B;
+const RTC_DAY_HIGH: u8 = 0x0C;
+
+const DAY_HIGH_BIT: u8 = 1 << 0;
+const HALT_BIT: u8 = 1 << 6;
+const DAY_CARRY_BIT: u8 = 1 << 7;
+
+/// Where the real time clock gets the passage of time from.
+pub trait Clock {
+    /// Time elapsed since some fixed point. It must never go backwards.
+    fn now(&self) -> Duration;
+
+    /// Called on every machine cycle of emulation. Clocks that follow real time ignore it.
+    fn tick(&mut self) {}
+}
+
+/// Counts emulated machine cycles, so the RTC runs at the speed of the emulation and stands
+/// still while it is paused. This is the default.
+#[derive(Debug, Default)]
+pub struct CycleClock {
+    cycles: u64,
+}
+
+impl Clock for CycleClock {
+    fn now(&self) -> Duration {
+        let seconds = self.cycles / CYCLES_PER_SECOND;
+        let nanos = (self.cycles % CYCLES_PER_SECOND) * 1_000_000_000 / CYCLES_PER_SECOND;
+        Duration::new(seconds, nanos as u32)
+    }
+
+    fn tick(&mut self) {
+        self.cycles += 1;
+    }
+}
+
+/// Follows the host's wall clock, whatever the emulation speed.
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        SystemClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// The counters of the RTC, as the registers selected with 0x08-0x0C show them.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RtcRegisters {
+    pub seconds: u8,
+    pub minutes: u8,
+    pub hours: u8,
+    /// Nine bits, the ninth one being bit 0 of the day high register.
+    pub days: u16,
+    pub halted: bool,
+    /// Set when the day counter overflows, until the game clears it.
+    pub day_carry: bool,
+}
+
+impl RtcRegisters {
+    fn read(&self, register: u8) -> u8 {
+        match register {
+            RTC_SECONDS => self.seconds,
+            RTC_MINUTES => self.minutes,
+            RTC_HOURS => self.hours,
+            RTC_DAY_LOW => self.days as u8,
+            _ => {
+                let mut value = (self.days >> 8) as u8 & DAY_HIGH_BIT;
+                if self.halted {
+                    value |= HALT_BIT;
+                }
+                if self.day_carry {
+                    value |= DAY_CARRY_BIT;
+                }
+                value
+            }
+        }
+    }
+
+    fn write(&mut self, register: u8, value: u8) {
+        match register {
+            RTC_SECONDS => self.seconds = value & 0x3F,
+            RTC_MINUTES => self.minutes = value & 0x3F,
+            RTC_HOURS => self.hours = value & 0x1F,
+            RTC_DAY_LOW => self.days = (self.days & 0x100) | value as u16,
+            _ => {
+                self.days = (self.days & 0xFF) | ((value & DAY_HIGH_BIT) as u16) << 8;
+                self.halted = value & HALT_BIT != 0;
+                self.day_carry = value & DAY_CARRY_BIT != 0;
+            }
+        }
+    }
+
+    /// Counts `seconds` forward. Every counter wraps at the width of its register, and only
+    /// carries into the next one when it reaches its usual limit, so values a game wrote out of
+    /// range take the long way round.
+    fn advance(&mut self, mut seconds: u64) {
+        while seconds > 0 && !self.in_range() {
+            self.advance_one_second();
+            seconds -= 1;
+        }
+        if seconds == 0 {
+            return;
+        }
+
+        let total = self.seconds as u64
+            + 60 * self.minutes as u64
+            + 60 * 60 * self.hours as u64
+            + SECONDS_PER_DAY * self.days as u64
+            + seconds;
+        let days = total / SECONDS_PER_DAY;
+        self.seconds = (total % 60) as u8;
+        self.minutes = (total / 60 % 60) as u8;
+        self.hours = (total / (60 * 60) % 24) as u8;
+        self.days = (days % DAYS) as u16;
+        if days >= DAYS {
+            self.day_carry = true;
+        }
+    }
+
+    fn in_range(&self) -> bool {
+        self.seconds < 60 && self.minutes < 60 && self.hours < 24
+    }
+
+    fn advance_one_second(&mut self) {
+        self.seconds = (self.seconds + 1) & 0x3F;
+        if self.seconds != 60 {
+            return;
+        }
+        self.seconds = 0;
+
+        self.minutes = (self.minutes + 1) & 0x3F;
+        if self.minutes != 60 {
+            return;
+        }
+        self.minutes = 0;
+
+        self.hours = (self.hours + 1) & 0x1F;
+        if self.hours != 24 {
+            return;
+        }
+        self.hours = 0;
+
+        self.days += 1;
+        if self.days as u64 == DAYS {
+            self.days = 0;
+            self.day_carry = true;
+        }
+    }
+}
+
+/// The real time clock of MBC3 cartridges. The game reads a latched copy of the counters,
+/// taken when it writes 0x00 and then 0x01 to 0x6000-0x7FFF, while writes go to the running
+/// counters.
+pub struct Rtc {
+    clock: Box<dyn Clock>,
+    registers: RtcRegisters,
+    latched: RtcRegisters,
+    /// Time from the clock the counters were last brought up to.
+    last_update: Duration,
+    /// Time counted towards the next second.
+    subsecond: Duration,
+    latch_armed: bool,
+}
+
+impl Default for Rtc {
+    fn default() -> Self {
+        Rtc::new()
+    }
+}
+
+impl Rtc {
+    /// An RTC driven by emulated cycles.
+    pub fn new() -> Self {
+        Rtc::with_clock(Box::new(CycleClock::default()))
+    }
+
+    pub fn with_clock(clock: Box<dyn Clock>) -> Self {
+        let last_update = clock.now();
+        Rtc {
+            clock,
+            registers: RtcRegisters::default(),
+            latched: RtcRegisters::default(),
+            last_update,
+            subsecond: Duration::from_secs(0),
+            latch_armed: false,
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.clock.tick();
+    }
+
+    /// The running counters, brought up to date.
+    pub fn registers(&mut self) -> RtcRegisters {
+        self.update();
+        self.registers
+    }
+
+    pub fn latched_registers(&self) -> RtcRegisters {
+        self.latched
+    }
+
+    fn read(&self, register: u8) -> u8 {
+        self.latched.read(register)
+    }
+
+    fn write(&mut self, register: u8, value: u8) {
+        self.update();
+        if register == RTC_SECONDS {
+            self.subsecond = Duration::from_secs(0);
+        }
+        self.registers.write(register, value);
+    }
+
+    fn write_latch(&mut self, value: u8) {
+        if self.latch_armed && value == 0x01 {
+            self.update();
+            self.latched = self.registers;
+        }
+        self.latch_armed = value == 0x00;
+    }
+
+    fn update(&mut self) {
+        let now = self.clock.now();
+        let elapsed = now.saturating_sub(self.last_update);
+        self.last_update = now;
+        if self.registers.halted {
+            return;
+        }
+
+        self.subsecond += elapsed;
+        let seconds = self.subsecond.as_secs();
+        self.subsecond -= Duration::from_secs(seconds);
+        self.registers.advance(seconds);
+    }
+}
+
+/// The MBC3, and the MBC30 with twice the ROM and RAM banks.
+/// - 0x0000-0x1FFF enables RAM and the RTC registers when the lower nibble is 0xA.
+/// - 0x2000-0x3FFF selects the ROM bank at 0x4000, seven bits wide, eight on the MBC30. Bank 0
+///   selects bank 1.
+/// - 0x4000-0x5FFF maps a RAM bank, 0x00-0x03 or 0x00-0x07 on the MBC30, or an RTC register,
+///   0x08-0x0C, to 0xA000-0xBFFF.
+/// - 0x6000-0x7FFF latches the RTC.
+pub struct Mbc3 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    rtc: Option<Rtc>,
+    ram_enabled: bool,
+    rom_bank: u8,
+    ram_select: u8,
+    mbc30: bool,
+}
+
+impl Mbc3 {
+    /// Cartridges with more than 2 MiB of ROM or 32 KiB of RAM are taken to be MBC30s.
+    pub fn new(rom: Vec<u8>, ram_size: usize, rtc: Option<Rtc>) -> Self {
+        let mbc30 = rom.len() > 128 * ROM_BANK_SIZE || ram_size > 4 * RAM_BANK_SIZE;
+        Mbc3 {
+            rom,
+            ram: vec![0; ram_size],
+            rtc,
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_select: 0,
+            mbc30,
+        }
+    }
+
+    pub fn is_mbc30(&self) -> bool {
+        self.mbc30
+    }
+
+    pub fn rtc(&mut self) -> Option<&mut Rtc> {
+        self.rtc.as_mut()
+    }
+
+    pub fn tick(&mut self) {
+        if let Some(rtc) = &mut self.rtc {
+            rtc.tick();
+        }
+    }
+
+    pub fn read_rom(&self, address: u16) -> u8 {
+        let bank = if address < ROM_BANK_SIZE as u16 {
+            0
+        } else {
+            self.rom_bank as usize
+        };
+
+        let offset = rom_offset(self.rom.len(), bank, address);
+        self.rom.get(offset).copied().unwrap_or(OPEN_BUS_VALUE)
+    }
+
+    pub fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enabled = value & 0x0F == 0x0A,
+            0x2000..=0x3FFF => {
+                let mask = if self.mbc30 { 0xFF } else { 0x7F };
+                self.rom_bank = (value & mask).max(1);
+            }
+            0x4000..=0x5FFF => self.ram_select = value & 0x0F,
+            _ => {
+                if let Some(rtc) = &mut self.rtc {
+                    rtc.write_latch(value);
+                }
+            }
+        }
+    }
+
+    pub fn read_ram(&self, address: u16) -> u8 {
+        if !self.ram_enabled {
+            return OPEN_BUS_VALUE;
+        }
+
+        match (self.ram_select, &self.rtc) {
+            (RTC_SECONDS..=RTC_DAY_HIGH, Some(rtc)) => rtc.read(self.ram_select),
+            _ => match self.ram_offset(address) {
+                Some(offset) => self.ram[offset],
+                None => OPEN_BUS_VALUE,
+            },
+        }
+    }
+
+    pub fn write_ram(&mut self, address: u16, value: u8) {
+        if !self.ram_enabled {
+            return;
+        }
+
+        match (self.ram_select, &mut self.rtc) {
+            (RTC_SECONDS..=RTC_DAY_HIGH, Some(rtc)) => rtc.write(self.ram_select, value),
+            _ => {
+                if let Some(offset) = self.ram_offset(address) {
+                    self.ram[offset] = value;
+                }
+            }
+        }
+    }
+
+    fn ram_offset(&self, address: u16) -> Option<usize> {
+        let banks = if self.mbc30 { 8 } else { 4 };
+        if self.ram.is_empty() || self.ram_select >= banks {
+            return None;
+        }
+
+        let offset =
+            self.ram_select as usize * RAM_BANK_SIZE + (address - EXTERNAL_RAM_START) as usize;
+        Some(offset % self.ram.len())
+    }
+}
+
+#[cfg(test)]
+mod mbc3_tests {
+    use super::*;
+    use crate::cartridge::numbered_rom;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    /// A clock the test moves forward by hand.
+    struct ManualClock(Rc<Cell<Duration>>);
+
+    impl Clock for ManualClock {
+        fn now(&self) -> Duration {
+            self.0.get()
+        }
+    }
+
+    /// An MBC3 with RAM enabled and an RTC on a manual clock, which is returned as well.
+    fn mbc3_with_manual_clock() -> (Mbc3, Rc<Cell<Duration>>) {
+        let time = Rc::new(Cell::new(Duration::from_secs(1000)));
+        let rtc = Rtc::with_clock(Box::new(ManualClock(time.clone())));
+        let mut mbc = Mbc3::new(numbered_rom(4), 0x8000, Some(rtc));
+        mbc.write_rom(0x0000, 0x0A);
+        (mbc, time)
+    }
+
+    fn advance(time: &Cell<Duration>, seconds: u64) {
+        time.set(time.get() + Duration::from_secs(seconds));
+    }
+
+    fn latch(mbc: &mut Mbc3) {
+        mbc.write_rom(0x6000, 0x00);
+        mbc.write_rom(0x6000, 0x01);
+    }
+
+    fn read_rtc(mbc: &mut Mbc3, register: u8) -> u8 {
+        mbc.write_rom(0x4000, register);
+        mbc.read_ram(0xA000)
+    }
+
+    fn write_rtc(mbc: &mut Mbc3, register: u8, value: u8) {
+        mbc.write_rom(0x4000, register);
+        mbc.write_ram(0xA000, value);
+    }
+
+    #[test]
+    fn test_rom_banking() {
+        let mut mbc = Mbc3::new(numbered_rom(128), 0, None);
+
+        assert_eq!(mbc.read_rom(0x4000), 0x01);
+
+        mbc.write_rom(0x2000, 0x00);
+        assert_eq!(mbc.read_rom(0x4000), 0x01);
+
+        mbc.write_rom(0x2000, 0x20);
+        assert_eq!(mbc.read_rom(0x4000), 0x20);
+
+        mbc.write_rom(0x3FFF, 0xFF);
+        assert_eq!(mbc.read_rom(0x7FFF), 0x7F);
+        assert_eq!(mbc.read_rom(0x0000), 0x00);
+        assert_eq!(mbc.is_mbc30(), false);
+    }
+
+    #[test]
+    fn test_mbc30_has_eight_bit_rom_bank_and_eight_ram_banks() {
+        let mut mbc = Mbc3::new(numbered_rom(256), 0x1_0000, None);
+        mbc.write_rom(0x0000, 0x0A);
+        assert_eq!(mbc.is_mbc30(), true);
+
+        mbc.write_rom(0x2000, 0xC3);
+        assert_eq!(mbc.read_rom(0x4000), 0xC3);
+
+        for bank in 0..8 {
+            mbc.write_rom(0x4000, bank);
+            mbc.write_ram(0xA000, bank + 0x10);
+        }
+        for bank in 0..8 {
+            mbc.write_rom(0x4000, bank);
+            assert_eq!(mbc.read_ram(0xA000), bank + 0x10);
+        }
+    }
+
+    #[test]
+    fn test_ram_banking() {
+        let mut mbc = Mbc3::new(numbered_rom(4), 0x8000, None);
+
+        mbc.write_ram(0xA000, 0x42);
+        assert_eq!(mbc.read_ram(0xA000), OPEN_BUS_VALUE);
+
+        mbc.write_rom(0x0000, 0x0A);
+        mbc.write_rom(0x4000, 0x03);
+        mbc.write_ram(0xBFFF, 0x42);
+        mbc.write_rom(0x4000, 0x00);
+        assert_eq!(mbc.read_ram(0xBFFF), 0x00);
+        mbc.write_rom(0x4000, 0x03);
+        assert_eq!(mbc.read_ram(0xBFFF), 0x42);
+
+        // Without an RTC nothing answers on the register numbers.
+        mbc.write_rom(0x4000, RTC_SECONDS);
+        assert_eq!(mbc.read_ram(0xA000), OPEN_BUS_VALUE);
+    }
+
+    #[test]
+    fn test_rtc_reads_latched_time() {
+        let (mut mbc, time) = mbc3_with_manual_clock();
+
+        advance(&time, 2 * SECONDS_PER_DAY + 3 * 3600 + 4 * 60 + 5);
+        assert_eq!(read_rtc(&mut mbc, RTC_SECONDS), 0);
+
+        latch(&mut mbc);
+        assert_eq!(read_rtc(&mut mbc, RTC_SECONDS), 5);
+        assert_eq!(read_rtc(&mut mbc, RTC_MINUTES), 4);
+        assert_eq!(read_rtc(&mut mbc, RTC_HOURS), 3);
+        assert_eq!(read_rtc(&mut mbc, RTC_DAY_LOW), 2);
+        assert_eq!(read_rtc(&mut mbc, RTC_DAY_HIGH), 0);
+
+        advance(&time, 10);
+        assert_eq!(read_rtc(&mut mbc, RTC_SECONDS), 5);
+    }
+
+    #[test]
+    fn test_rtc_latches_only_on_zero_then_one() {
+        let (mut mbc, time) = mbc3_with_manual_clock();
+
+        advance(&time, 7);
+        mbc.write_rom(0x6000, 0x01);
+        assert_eq!(read_rtc(&mut mbc, RTC_SECONDS), 0);
+
+        mbc.write_rom(0x6000, 0x00);
+        mbc.write_rom(0x6000, 0x02);
+        mbc.write_rom(0x6000, 0x01);
+        assert_eq!(read_rtc(&mut mbc, RTC_SECONDS), 0);
+
+        latch(&mut mbc);
+        assert_eq!(read_rtc(&mut mbc, RTC_SECONDS), 7);
+    }
+
+    #[test]
+    fn test_rtc_day_counter_overflows_into_carry() {
+        let (mut mbc, time) = mbc3_with_manual_clock();
+
+        write_rtc(&mut mbc, RTC_DAY_LOW, 0xFF);
+        write_rtc(&mut mbc, RTC_DAY_HIGH, DAY_HIGH_BIT);
+        write_rtc(&mut mbc, RTC_HOURS, 23);
+        write_rtc(&mut mbc, RTC_MINUTES, 59);
+        write_rtc(&mut mbc, RTC_SECONDS, 59);
+        latch(&mut mbc);
+        assert_eq!(read_rtc(&mut mbc, RTC_DAY_HIGH), DAY_HIGH_BIT);
+
+        advance(&time, 1);
+        latch(&mut mbc);
+        assert_eq!(read_rtc(&mut mbc, RTC_DAY_LOW), 0);
+        assert_eq!(read_rtc(&mut mbc, RTC_DAY_HIGH), DAY_CARRY_BIT);
+
+        advance(&time, SECONDS_PER_DAY);
+        write_rtc(&mut mbc, RTC_DAY_HIGH, 0);
+        latch(&mut mbc);
+        assert_eq!(read_rtc(&mut mbc, RTC_DAY_LOW), 1);
+        assert_eq!(read_rtc(&mut mbc, RTC_DAY_HIGH), 0);
+    }
+
+    #[test]
+    fn test_rtc_halt_stops_counting() {
+        let (mut mbc, time) = mbc3_with_manual_clock();
+
+        advance(&time, 3);
+        write_rtc(&mut mbc, RTC_DAY_HIGH, HALT_BIT);
+        advance(&time, 100);
+        latch(&mut mbc);
+        assert_eq!(read_rtc(&mut mbc, RTC_SECONDS), 3);
+        assert_eq!(read_rtc(&mut mbc, RTC_DAY_HIGH), HALT_BIT);
+
+        write_rtc(&mut mbc, RTC_DAY_HIGH, 0);
+        advance(&time, 2);
+        latch(&mut mbc);
+        assert_eq!(read_rtc(&mut mbc, RTC_SECONDS), 5);
+    }
+
+    #[test]
+    fn test_rtc_writing_seconds_restarts_the_second() {
+        let (mut mbc, time) = mbc3_with_manual_clock();
+
+        time.set(time.get() + Duration::from_millis(900));
+        write_rtc(&mut mbc, RTC_SECONDS, 10);
+        time.set(time.get() + Duration::from_millis(900));
+        latch(&mut mbc);
+        assert_eq!(read_rtc(&mut mbc, RTC_SECONDS), 10);
+
+        time.set(time.get() + Duration::from_millis(100));
+        latch(&mut mbc);
+        assert_eq!(read_rtc(&mut mbc, RTC_SECONDS), 11);
+    }
+
+    #[test]
+    fn test_rtc_out_of_range_values_wrap_without_carry() {
+        let (mut mbc, time) = mbc3_with_manual_clock();
+
+        write_rtc(&mut mbc, RTC_SECONDS, 62);
+        advance(&time, 3);
+        latch(&mut mbc);
+        assert_eq!(read_rtc(&mut mbc, RTC_SECONDS), 1);
+        assert_eq!(read_rtc(&mut mbc, RTC_MINUTES), 0);
+
+        write_rtc(&mut mbc, RTC_HOURS, 31);
+        write_rtc(&mut mbc, RTC_MINUTES, 59);
+        write_rtc(&mut mbc, RTC_SECONDS, 59);
+        advance(&time, 1);
+        latch(&mut mbc);
+        assert_eq!(read_rtc(&mut mbc, RTC_HOURS), 0);
+        assert_eq!(read_rtc(&mut mbc, RTC_DAY_LOW), 0);
+    }
+
+    #[test]
+    fn test_rtc_follows_emulated_cycles_by_default() {
+        let mut mbc = Mbc3::new(numbered_rom(4), 0, Some(Rtc::new()));
+        mbc.write_rom(0x0000, 0x0A);
+
+        for _ in 0..CYCLES_PER_SECOND * 2 - 1 {
+            mbc.tick();
+        }
+        latch(&mut mbc);
+        assert_eq!(read_rtc(&mut mbc, RTC_SECONDS), 1);
+
+        mbc.tick();
+        latch(&mut mbc);
+        assert_eq!(read_rtc(&mut mbc, RTC_SECONDS), 2);
+    }
+}
@@ -0,0 +1,194 @@
+use super::cartridge::{rom_offset, RAM_BANK_SIZE, ROM_BANK_SIZE};
+use super::memorybus::{EXTERNAL_RAM_START, OPEN_BUS_VALUE};
+
+/// On rumble cartridges bit 3 of the RAM bank register drives the motor instead of RAM.
+const MOTOR_BIT: u8 = 1 << 3;
+
+/// The MBC5, with up to 8 MiB of ROM and 128 KiB of RAM.
+/// - 0x0000-0x1FFF enables RAM when written 0x0A.
+/// - 0x2000-0x2FFF sets the lower eight bits of the ROM bank at 0x4000.
+/// - 0x3000-0x3FFF sets its ninth bit. Unlike earlier controllers bank 0 can be selected.
+/// - 0x4000-0x5FFF selects one of 16 RAM banks, or one of 8 and the rumble motor.
+pub struct Mbc5 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    ram_enabled: bool,
+    rom_bank: u16,
+    ram_bank: u8,
+    /// Whether the cartridge has a rumble motor wired to `MOTOR_BIT`.
+    has_rumble: bool,
+    motor_on: bool,
+    /// Set when the motor was switched since the last `take_motor_change`.
+    motor_changed: bool,
+}
+
+impl Mbc5 {
+    pub fn new(rom: Vec<u8>, ram_size: usize, has_rumble: bool) -> Self {
+        Mbc5 {
+            rom,
+            ram: vec![0; ram_size],
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            has_rumble,
+            motor_on: false,
+            motor_changed: false,
+        }
+    }
+
+    pub fn motor_on(&self) -> bool {
+        self.motor_on
+    }
+
+    /// The state the motor was switched to, if it was switched since the last call.
+    pub fn take_motor_change(&mut self) -> Option<bool> {
+        if self.motor_changed {
+            self.motor_changed = false;
+            Some(self.motor_on)
+        } else {
+            None
+        }
+    }
+
+    pub fn read_rom(&self, address: u16) -> u8 {
+        let bank = if address < ROM_BANK_SIZE as u16 {
+            0
+        } else {
+            self.rom_bank as usize
+        };
+
+        let offset = rom_offset(self.rom.len(), bank, address);
+        self.rom.get(offset).copied().unwrap_or(OPEN_BUS_VALUE)
+    }
+
+    pub fn write_rom(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enabled = value == 0x0A,
+            0x2000..=0x2FFF => self.rom_bank = (self.rom_bank & 0x100) | value as u16,
+            0x3000..=0x3FFF => self.rom_bank = (self.rom_bank & 0xFF) | ((value & 1) as u16) << 8,
+            0x4000..=0x5FFF if self.has_rumble => {
+                self.ram_bank = value & 0x07;
+                let motor_on = value & MOTOR_BIT != 0;
+                if motor_on != self.motor_on {
+                    self.motor_on = motor_on;
+                    self.motor_changed = true;
+                }
+            }
+            0x4000..=0x5FFF => self.ram_bank = value & 0x0F,
+            _ => {}
+        }
+    }
+
+    pub fn read_ram(&self, address: u16) -> u8 {
+        match self.ram_offset(address) {
+            Some(offset) => self.ram[offset],
+            None => OPEN_BUS_VALUE,
+        }
+    }
+
+    pub fn write_ram(&mut self, address: u16, value: u8) {
+        if let Some(offset) = self.ram_offset(address) {
+            self.ram[offset] = value;
+        }
+    }
+
+    fn ram_offset(&self, address: u16) -> Option<usize> {
+        if !self.ram_enabled || self.ram.is_empty() {
+            return None;
+        }
+
+        let offset =
+            self.ram_bank as usize * RAM_BANK_SIZE + (address - EXTERNAL_RAM_START) as usize;
+        Some(offset % self.ram.len())
+    }
+}
+
+#[cfg(test)]
+mod mbc5_tests {
+    use super::*;
+    use crate::cartridge::numbered_rom;
+
+    fn bank_at_0x4000(mbc: &Mbc5) -> u16 {
+        u16::from_le_bytes([mbc.read_rom(0x4000), mbc.read_rom(0x4001)])
+    }
+
+    #[test]
+    fn test_nine_bit_rom_bank() {
+        let mut mbc = Mbc5::new(numbered_rom(512), 0, false);
+        assert_eq!(bank_at_0x4000(&mbc), 1);
+
+        mbc.write_rom(0x2000, 0x00);
+        assert_eq!(bank_at_0x4000(&mbc), 0);
+
+        mbc.write_rom(0x3000, 0x01);
+        assert_eq!(bank_at_0x4000(&mbc), 0x100);
+
+        mbc.write_rom(0x2FFF, 0xA5);
+        assert_eq!(bank_at_0x4000(&mbc), 0x1A5);
+
+        mbc.write_rom(0x3FFF, 0xFE);
+        assert_eq!(bank_at_0x4000(&mbc), 0x0A5);
+        assert_eq!(mbc.read_rom(0x0000), 0x00);
+    }
+
+    #[test]
+    fn test_rom_bank_wraps_on_small_rom() {
+        let mut mbc = Mbc5::new(numbered_rom(8), 0, false);
+
+        mbc.write_rom(0x2000, 0x0B);
+        mbc.write_rom(0x3000, 0x01);
+        assert_eq!(bank_at_0x4000(&mbc), 0x03);
+    }
+
+    #[test]
+    fn test_sixteen_ram_banks() {
+        let mut mbc = Mbc5::new(numbered_rom(2), 0x2_0000, false);
+
+        mbc.write_ram(0xA000, 0x42);
+        assert_eq!(mbc.read_ram(0xA000), OPEN_BUS_VALUE);
+
+        mbc.write_rom(0x0000, 0x0A);
+        for bank in 0..16 {
+            mbc.write_rom(0x4000, bank);
+            mbc.write_ram(0xBFFF, bank + 0x20);
+        }
+        for bank in 0..16 {
+            mbc.write_rom(0x4000, bank);
+            assert_eq!(mbc.read_ram(0xBFFF), bank + 0x20);
+        }
+
+        mbc.write_rom(0x0000, 0x1A);
+        assert_eq!(mbc.read_ram(0xBFFF), OPEN_BUS_VALUE);
+    }
+
+    #[test]
+    fn test_rumble_motor_takes_bit_3_of_ram_bank() {
+        let mut mbc = Mbc5::new(numbered_rom(2), 0x1_0000, true);
+        mbc.write_rom(0x0000, 0x0A);
+        mbc.write_rom(0x4000, 0x01);
+        mbc.write_ram(0xA000, 0x11);
+
+        mbc.write_rom(0x4000, 0x09);
+        assert_eq!(mbc.motor_on(), true);
+        assert_eq!(mbc.read_ram(0xA000), 0x11);
+        assert_eq!(mbc.take_motor_change(), Some(true));
+        assert_eq!(mbc.take_motor_change(), None);
+
+        mbc.write_rom(0x4000, 0x0F);
+        assert_eq!(mbc.take_motor_change(), None);
+
+        mbc.write_rom(0x4000, 0x01);
+        assert_eq!(mbc.motor_on(), false);
+        assert_eq!(mbc.take_motor_change(), Some(false));
+    }
+
+    #[test]
+    fn test_no_motor_without_rumble() {
+        let mut mbc = Mbc5::new(numbered_rom(2), 0x2_0000, false);
+
+        mbc.write_rom(0x4000, 0x08);
+
+        assert_eq!(mbc.motor_on(), false);
+        assert_eq!(mbc.take_motor_change(), None);
+    }
+}
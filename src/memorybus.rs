@@ -0,0 +1,354 @@
+use super::cartridge::{Cartridge, RomOnly};
+use super::interrupts::{Interrupt, INTERRUPT_FLAG_ADDRESS, INTERRUPT_MASK};
+use super::joypad::{Buttons, Joypad, JOYPAD_ADDRESS};
+use super::timer::{Timer, DIV_ADDRESS, TAC_ADDRESS};
+
+pub const ROM_START: u16 = 0x0000;
+pub const ROM_END: u16 = 0x7FFF;
+pub const VRAM_START: u16 = 0x8000;
+pub const VRAM_END: u16 = 0x9FFF;
+pub const EXTERNAL_RAM_START: u16 = 0xA000;
+pub const EXTERNAL_RAM_END: u16 = 0xBFFF;
+pub const WRAM_START: u16 = 0xC000;
+pub const WRAM_END: u16 = 0xDFFF;
+pub const ECHO_RAM_START: u16 = 0xE000;
+pub const ECHO_RAM_END: u16 = 0xFDFF;
+pub const OAM_START: u16 = 0xFE00;
+pub const OAM_END: u16 = 0xFE9F;
+pub const UNUSABLE_START: u16 = 0xFEA0;
+pub const UNUSABLE_END: u16 = 0xFEFF;
+pub const IO_START: u16 = 0xFF00;
+pub const IO_END: u16 = 0xFF7F;
+pub const HRAM_START: u16 = 0xFF80;
+pub const HRAM_END: u16 = 0xFFFE;
+pub const INTERRUPT_ENABLE_ADDRESS: u16 = 0xFFFF;
+
+const VRAM_SIZE: usize = (VRAM_END - VRAM_START + 1) as usize;
+const WRAM_SIZE: usize = (WRAM_END - WRAM_START + 1) as usize;
+const OAM_SIZE: usize = (OAM_END - OAM_START + 1) as usize;
+const IO_SIZE: usize = (IO_END - IO_START + 1) as usize;
+const HRAM_SIZE: usize = (HRAM_END - HRAM_START + 1) as usize;
+
+/// Value read from addresses that nothing drives.
+pub const OPEN_BUS_VALUE: u8 = 0xFF;
+
+/// Routes the 16-bit address space of the DMG to the memory behind each region.
+pub struct MemoryBus {
+    cartridge: Cartridge,
+    vram: [u8; VRAM_SIZE],
+    wram: [u8; WRAM_SIZE],
+    oam: [u8; OAM_SIZE],
+    io: [u8; IO_SIZE],
+    hram: [u8; HRAM_SIZE],
+    joypad: Joypad,
+    timer: Timer,
+    interrupt_flag: u8,
+    interrupt_enable: u8,
+}
+
+impl Default for MemoryBus {
+    fn default() -> Self {
+        MemoryBus::new()
+    }
+}
+
+impl MemoryBus {
+    pub fn new() -> Self {
+        MemoryBus {
+            cartridge: Cartridge::RomOnly(RomOnly::new(Vec::new(), 0)),
+            vram: [0; VRAM_SIZE],
+            wram: [0; WRAM_SIZE],
+            oam: [0; OAM_SIZE],
+            io: [0; IO_SIZE],
+            hram: [0; HRAM_SIZE],
+            joypad: Joypad::new(),
+            timer: Timer::new(),
+            interrupt_flag: 0,
+            interrupt_enable: 0,
+        }
+    }
+
+    /// Maps `rom` at 0x0000-0x7FFF and gives it `external_ram_size` bytes of cartridge RAM.
+    pub fn load_rom(&mut self, rom: Vec<u8>, external_ram_size: usize) {
+        self.cartridge = Cartridge::RomOnly(RomOnly::new(rom, external_ram_size));
+    }
+
+    /// Maps `cartridge` at 0x0000-0x7FFF and 0xA000-0xBFFF.
+    pub fn insert_cartridge(&mut self, cartridge: Cartridge) {
+        self.cartridge = cartridge;
+    }
+
+    pub fn cartridge_mut(&mut self) -> &mut Cartridge {
+        &mut self.cartridge
+    }
+
+    /// Advances the peripherals on the bus by `cycles` machine cycles.
+    pub fn tick(&mut self, cycles: u8) {
+        for _ in 0..cycles {
+            self.cartridge.tick();
+            if self.timer.tick() {
+                self.request_interrupt(Interrupt::Timer);
+            }
+        }
+    }
+
+    pub fn set_buttons(&mut self, buttons: Buttons) {
+        if self.joypad.set_buttons(buttons) {
+            self.request_interrupt(Interrupt::Joypad);
+        }
+    }
+
+    pub fn request_interrupt(&mut self, interrupt: Interrupt) {
+        self.interrupt_flag |= interrupt.mask();
+    }
+
+    pub fn acknowledge_interrupt(&mut self, interrupt: Interrupt) {
+        self.interrupt_flag &= !interrupt.mask();
+    }
+
+    /// Interrupts that are both requested in IF and enabled in IE.
+    pub fn pending_interrupts(&self) -> u8 {
+        self.interrupt_flag & self.interrupt_enable & INTERRUPT_MASK
+    }
+
+    // Registers with behaviour of their own are matched ahead of the generic I/O range.
+    #[allow(clippy::match_overlapping_arm)]
+    pub fn read_byte(&self, address: u16) -> u8 {
+        match address {
+            ROM_START..=ROM_END => self.cartridge.read_rom(address),
+            VRAM_START..=VRAM_END => self.vram[(address - VRAM_START) as usize],
+            EXTERNAL_RAM_START..=EXTERNAL_RAM_END => self.cartridge.read_ram(address),
+            WRAM_START..=WRAM_END => self.wram[(address - WRAM_START) as usize],
+            ECHO_RAM_START..=ECHO_RAM_END => self.wram[(address - ECHO_RAM_START) as usize],
+            OAM_START..=OAM_END => self.oam[(address - OAM_START) as usize],
+            // The DMG reads zero here as long as the PPU is not blocking OAM.
+            UNUSABLE_START..=UNUSABLE_END => 0x00,
+            JOYPAD_ADDRESS => self.joypad.read_byte(),
+            DIV_ADDRESS..=TAC_ADDRESS => self.timer.read_byte(address),
+            // The unused upper bits of IF always read as set.
+            INTERRUPT_FLAG_ADDRESS => self.interrupt_flag | !INTERRUPT_MASK,
+            IO_START..=IO_END => {
+                if is_unmapped_io(address) {
+                    OPEN_BUS_VALUE
+                } else {
+                    self.io[(address - IO_START) as usize]
+                }
+            }
+            HRAM_START..=HRAM_END => self.hram[(address - HRAM_START) as usize],
+            INTERRUPT_ENABLE_ADDRESS => self.interrupt_enable,
+        }
+    }
+
+    // Registers with behaviour of their own are matched ahead of the generic I/O range.
+    #[allow(clippy::match_overlapping_arm)]
+    pub fn write_byte(&mut self, address: u16, value: u8) {
+        match address {
+            ROM_START..=ROM_END => self.cartridge.write_rom(address, value),
+            VRAM_START..=VRAM_END => self.vram[(address - VRAM_START) as usize] = value,
+            EXTERNAL_RAM_START..=EXTERNAL_RAM_END => self.cartridge.write_ram(address, value),
+            WRAM_START..=WRAM_END => self.wram[(address - WRAM_START) as usize] = value,
+            ECHO_RAM_START..=ECHO_RAM_END => self.wram[(address - ECHO_RAM_START) as usize] = value,
+            OAM_START..=OAM_END => self.oam[(address - OAM_START) as usize] = value,
+            UNUSABLE_START..=UNUSABLE_END => {}
+            JOYPAD_ADDRESS => self.joypad.write_byte(value),
+            DIV_ADDRESS..=TAC_ADDRESS => {
+                if self.timer.write_byte(address, value) {
+                    self.request_interrupt(Interrupt::Timer);
+                }
+            }
+            INTERRUPT_FLAG_ADDRESS => self.interrupt_flag = value & INTERRUPT_MASK,
+            IO_START..=IO_END => {
+                if !is_unmapped_io(address) {
+                    self.io[(address - IO_START) as usize] = value;
+                }
+            }
+            HRAM_START..=HRAM_END => self.hram[(address - HRAM_START) as usize] = value,
+            INTERRUPT_ENABLE_ADDRESS => self.interrupt_enable = value,
+        }
+    }
+}
+
+/// I/O addresses with no register behind them on the DMG.
+fn is_unmapped_io(address: u16) -> bool {
+    matches!(
+        address,
+        0xFF03 | 0xFF08..=0xFF0E | 0xFF15 | 0xFF1F | 0xFF27..=0xFF2F | 0xFF4C..=0xFF7F
+    )
+}
+
+#[cfg(test)]
+mod memorybus_tests {
+    use super::*;
+
+    #[test]
+    fn test_read_rom() {
+        let mut bus = MemoryBus::new();
+
+        bus.load_rom(vec![0x12, 0x34], 0);
+
+        assert_eq!(bus.read_byte(0x0000), 0x12);
+        assert_eq!(bus.read_byte(0x0001), 0x34);
+    }
+
+    #[test]
+    fn test_read_past_end_of_rom_is_open_bus() {
+        let mut bus = MemoryBus::new();
+
+        bus.load_rom(vec![0x12, 0x34], 0);
+
+        assert_eq!(bus.read_byte(0x0002), OPEN_BUS_VALUE);
+        assert_eq!(bus.read_byte(ROM_END), OPEN_BUS_VALUE);
+    }
+
+    #[test]
+    fn test_write_to_rom_is_ignored() {
+        let mut bus = MemoryBus::new();
+
+        bus.load_rom(vec![0x12], 0);
+        bus.write_byte(0x0000, 0xFF);
+
+        assert_eq!(bus.read_byte(0x0000), 0x12);
+    }
+
+    #[test]
+    fn test_read_write_ram_regions() {
+        let mut bus = MemoryBus::new();
+
+        for &address in &[
+            VRAM_START,
+            VRAM_END,
+            WRAM_START,
+            WRAM_END,
+            OAM_START,
+            OAM_END,
+            HRAM_START,
+            HRAM_END,
+            INTERRUPT_ENABLE_ADDRESS,
+        ] {
+            bus.write_byte(address, 0x5A);
+            assert_eq!(bus.read_byte(address), 0x5A, "address {:#06x}", address);
+        }
+    }
+
+    #[test]
+    fn test_external_ram_without_cartridge_ram_is_open_bus() {
+        let mut bus = MemoryBus::new();
+
+        bus.write_byte(EXTERNAL_RAM_START, 0x00);
+
+        assert_eq!(bus.read_byte(EXTERNAL_RAM_START), OPEN_BUS_VALUE);
+        assert_eq!(bus.read_byte(EXTERNAL_RAM_END), OPEN_BUS_VALUE);
+    }
+
+    #[test]
+    fn test_external_ram_with_cartridge_ram() {
+        let mut bus = MemoryBus::new();
+
+        bus.load_rom(Vec::new(), 0x2000);
+        bus.write_byte(EXTERNAL_RAM_START, 0x42);
+        bus.write_byte(EXTERNAL_RAM_END, 0x24);
+
+        assert_eq!(bus.read_byte(EXTERNAL_RAM_START), 0x42);
+        assert_eq!(bus.read_byte(EXTERNAL_RAM_END), 0x24);
+    }
+
+    #[test]
+    fn test_echo_ram_mirrors_wram() {
+        let mut bus = MemoryBus::new();
+
+        bus.write_byte(WRAM_START, 0x11);
+        assert_eq!(bus.read_byte(ECHO_RAM_START), 0x11);
+
+        bus.write_byte(ECHO_RAM_END, 0x22);
+        assert_eq!(bus.read_byte(0xDDFF), 0x22);
+    }
+
+    #[test]
+    fn test_unusable_region_reads_zero_and_ignores_writes() {
+        let mut bus = MemoryBus::new();
+
+        bus.write_byte(UNUSABLE_START, 0x33);
+
+        assert_eq!(bus.read_byte(UNUSABLE_START), 0x00);
+        assert_eq!(bus.read_byte(UNUSABLE_END), 0x00);
+    }
+
+    #[test]
+    fn test_unmapped_io_reads_open_bus() {
+        let mut bus = MemoryBus::new();
+
+        bus.write_byte(0xFF03, 0x00);
+        bus.write_byte(0xFF7F, 0x00);
+
+        assert_eq!(bus.read_byte(0xFF03), OPEN_BUS_VALUE);
+        assert_eq!(bus.read_byte(0xFF7F), OPEN_BUS_VALUE);
+    }
+
+    #[test]
+    fn test_mapped_io_keeps_written_value() {
+        let mut bus = MemoryBus::new();
+
+        bus.write_byte(0xFF40, 0x91);
+
+        assert_eq!(bus.read_byte(0xFF40), 0x91);
+    }
+
+    #[test]
+    fn test_interrupt_flag_upper_bits_read_as_set() {
+        let mut bus = MemoryBus::new();
+
+        assert_eq!(bus.read_byte(INTERRUPT_FLAG_ADDRESS), 0xE0);
+
+        bus.write_byte(INTERRUPT_FLAG_ADDRESS, 0xFF);
+        assert_eq!(bus.read_byte(INTERRUPT_FLAG_ADDRESS), 0xFF);
+
+        bus.write_byte(INTERRUPT_FLAG_ADDRESS, 0x00);
+        bus.request_interrupt(Interrupt::Timer);
+        assert_eq!(bus.read_byte(INTERRUPT_FLAG_ADDRESS), 0xE4);
+    }
+
+    #[test]
+    fn test_pending_interrupts_require_enable_and_flag() {
+        let mut bus = MemoryBus::new();
+
+        bus.request_interrupt(Interrupt::VBlank);
+        bus.request_interrupt(Interrupt::Serial);
+        assert_eq!(bus.pending_interrupts(), 0);
+
+        bus.write_byte(INTERRUPT_ENABLE_ADDRESS, 0xFF);
+        assert_eq!(bus.pending_interrupts(), 0b0_1001);
+
+        bus.acknowledge_interrupt(Interrupt::VBlank);
+        assert_eq!(bus.pending_interrupts(), 0b0_1000);
+    }
+
+    #[test]
+    fn test_tick_requests_timer_interrupt_on_overflow() {
+        let mut bus = MemoryBus::new();
+
+        bus.write_byte(INTERRUPT_ENABLE_ADDRESS, 0x1F);
+        bus.write_byte(0xFF05, 0xFF);
+        bus.write_byte(TAC_ADDRESS, 0b101);
+
+        bus.tick(3);
+        assert_eq!(bus.pending_interrupts(), 0);
+
+        bus.tick(1);
+        assert_eq!(bus.pending_interrupts(), Interrupt::Timer.mask());
+    }
+
+    #[test]
+    fn test_pressing_a_selected_button_requests_joypad_interrupt() {
+        let mut bus = MemoryBus::new();
+
+        bus.write_byte(INTERRUPT_ENABLE_ADDRESS, 0x1F);
+        bus.write_byte(JOYPAD_ADDRESS, 0x10);
+        bus.set_buttons(Buttons {
+            a: true,
+            ..Buttons::default()
+        });
+
+        assert_eq!(bus.read_byte(JOYPAD_ADDRESS), 0xDE);
+        assert_eq!(bus.pending_interrupts(), Interrupt::Joypad.mask());
+    }
+}
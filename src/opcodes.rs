@@ -0,0 +1,758 @@
+use super::instructions::{Instruction, PREFIX_BYTE};
+
+/// What an instruction does to one flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlagEffect {
+    Unaffected,
+    Set,
+    Reset,
+    /// Depends on the operands.
+    Modified,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlagEffects {
+    pub zero: FlagEffect,
+    pub substraction: FlagEffect,
+    pub half_carry: FlagEffect,
+    pub carry: FlagEffect,
+}
+
+/// What an opcode costs and which flags it touches, known without running it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpcodeInfo {
+    /// The instruction with its immediate operand written as `n8`, `n16`, `a8`, `a16` or `e8`.
+    pub mnemonic: &'static str,
+    /// Bytes of machine code, the CB prefix included.
+    pub length: u8,
+    /// Machine cycles taken, or taken when a conditional branch is not.
+    pub cycles: u8,
+    /// Machine cycles taken when the condition of a conditional branch holds.
+    pub taken_cycles: Option<u8>,
+    pub flags: FlagEffects,
+}
+
+impl OpcodeInfo {
+    /// Machine cycles the instruction takes, the same as `Instruction::cycles`.
+    pub fn cycles(&self, branch_taken: bool) -> u8 {
+        match self.taken_cycles {
+            Some(taken_cycles) if branch_taken => taken_cycles,
+            _ => self.cycles,
+        }
+    }
+}
+
+impl Instruction {
+    /// The table entry of the opcode the instruction encodes to. Panics on operands `encode`
+    /// panics on.
+    pub fn info(&self) -> &'static OpcodeInfo {
+        let encoding = self.encoding();
+        let bytes = encoding.as_slice();
+        if bytes[0] == PREFIX_BYTE {
+            &PREFIXED_OPCODES[bytes[1] as usize]
+        } else {
+            UNPREFIXED_OPCODES[bytes[0] as usize]
+                .as_ref()
+                .expect("every instruction has an unprefixed opcode")
+        }
+    }
+}
+
+/// The flags in Z N H C order, written the way opcode tables usually do: the flag's letter
+/// when it depends on the operands, 0 or 1 when it is always reset or set and - when left
+/// alone.
+const fn flags(effects: &str) -> FlagEffects {
+    let effects = effects.as_bytes();
+    FlagEffects {
+        zero: flag(effects[0]),
+        substraction: flag(effects[1]),
+        half_carry: flag(effects[2]),
+        carry: flag(effects[3]),
+    }
+}
+
+const fn flag(effect: u8) -> FlagEffect {
+    match effect {
+        b'-' => FlagEffect::Unaffected,
+        b'0' => FlagEffect::Reset,
+        b'1' => FlagEffect::Set,
+        _ => FlagEffect::Modified,
+    }
+}
+
+const fn op(mnemonic: &'static str, length: u8, cycles: u8, effects: &str) -> OpcodeInfo {
+    OpcodeInfo {
+        mnemonic,
+        length,
+        cycles,
+        taken_cycles: None,
+        flags: flags(effects),
+    }
+}
+
+const fn branch(
+    mnemonic: &'static str,
+    length: u8,
+    cycles: u8,
+    taken_cycles: u8,
+    effects: &str,
+) -> OpcodeInfo {
+    OpcodeInfo {
+        taken_cycles: Some(taken_cycles),
+        ..op(mnemonic, length, cycles, effects)
+    }
+}
+
+/// Opcodes of the unprefixed page. The illegal opcodes and the CB prefix have no entry.
+pub static UNPREFIXED_OPCODES: [Option<OpcodeInfo>; 256] = [
+    // 0x00
+    Some(op("NOP", 1, 1, "----")),
+    Some(op("LD BC,n16", 3, 3, "----")),
+    Some(op("LD (BC),A", 1, 2, "----")),
+    Some(op("INC BC", 1, 2, "----")),
+    Some(op("INC B", 1, 1, "Z0H-")),
+    Some(op("DEC B", 1, 1, "Z1H-")),
+    Some(op("LD B,n8", 2, 2, "----")),
+    Some(op("RLCA", 1, 1, "000C")),
+    Some(op("LD (a16),SP", 3, 5, "----")),
+    Some(op("ADD HL,BC", 1, 2, "-0HC")),
+    Some(op("LD A,(BC)", 1, 2, "----")),
+    Some(op("DEC BC", 1, 2, "----")),
+    Some(op("INC C", 1, 1, "Z0H-")),
+    Some(op("DEC C", 1, 1, "Z1H-")),
+    Some(op("LD C,n8", 2, 2, "----")),
+    Some(op("RRCA", 1, 1, "000C")),
+    // 0x10
+    Some(op("STOP", 2, 2, "----")),
+    Some(op("LD DE,n16", 3, 3, "----")),
+    Some(op("LD (DE),A", 1, 2, "----")),
+    Some(op("INC DE", 1, 2, "----")),
+    Some(op("INC D", 1, 1, "Z0H-")),
+    Some(op("DEC D", 1, 1, "Z1H-")),
+    Some(op("LD D,n8", 2, 2, "----")),
+    Some(op("RLA", 1, 1, "000C")),
+    Some(op("JR e8", 2, 3, "----")),
+    Some(op("ADD HL,DE", 1, 2, "-0HC")),
+    Some(op("LD A,(DE)", 1, 2, "----")),
+    Some(op("DEC DE", 1, 2, "----")),
+    Some(op("INC E", 1, 1, "Z0H-")),
+    Some(op("DEC E", 1, 1, "Z1H-")),
+    Some(op("LD E,n8", 2, 2, "----")),
+    Some(op("RRA", 1, 1, "000C")),
+    // 0x20
+    Some(branch("JR NZ,e8", 2, 2, 3, "----")),
+    Some(op("LD HL,n16", 3, 3, "----")),
+    Some(op("LD (HL+),A", 1, 2, "----")),
+    Some(op("INC HL", 1, 2, "----")),
+    Some(op("INC H", 1, 1, "Z0H-")),
+    Some(op("DEC H", 1, 1, "Z1H-")),
+    Some(op("LD H,n8", 2, 2, "----")),
+    Some(op("DAA", 1, 1, "Z-0C")),
+    Some(branch("JR Z,e8", 2, 2, 3, "----")),
+    Some(op("ADD HL,HL", 1, 2, "-0HC")),
+    Some(op("LD A,(HL+)", 1, 2, "----")),
+    Some(op("DEC HL", 1, 2, "----")),
+    Some(op("INC L", 1, 1, "Z0H-")),
+    Some(op("DEC L", 1, 1, "Z1H-")),
+    Some(op("LD L,n8", 2, 2, "----")),
+    Some(op("CPL", 1, 1, "-11-")),
+    // 0x30
+    Some(branch("JR NC,e8", 2, 2, 3, "----")),
+    Some(op("LD SP,n16", 3, 3, "----")),
+    Some(op("LD (HL-),A", 1, 2, "----")),
+    Some(op("INC SP", 1, 2, "----")),
+    Some(op("INC (HL)", 1, 3, "Z0H-")),
+    Some(op("DEC (HL)", 1, 3, "Z1H-")),
+    Some(op("LD (HL),n8", 2, 3, "----")),
+    Some(op("SCF", 1, 1, "-001")),
+    Some(branch("JR C,e8", 2, 2, 3, "----")),
+    Some(op("ADD HL,SP", 1, 2, "-0HC")),
+    Some(op("LD A,(HL-)", 1, 2, "----")),
+    Some(op("DEC SP", 1, 2, "----")),
+    Some(op("INC A", 1, 1, "Z0H-")),
+    Some(op("DEC A", 1, 1, "Z1H-")),
+    Some(op("LD A,n8", 2, 2, "----")),
+    Some(op("CCF", 1, 1, "-00C")),
+    // 0x40
+    Some(op("LD B,B", 1, 1, "----")),
+    Some(op("LD B,C", 1, 1, "----")),
+    Some(op("LD B,D", 1, 1, "----")),
+    Some(op("LD B,E", 1, 1, "----")),
+    Some(op("LD B,H", 1, 1, "----")),
+    Some(op("LD B,L", 1, 1, "----")),
+    Some(op("LD B,(HL)", 1, 2, "----")),
+    Some(op("LD B,A", 1, 1, "----")),
+    Some(op("LD C,B", 1, 1, "----")),
+    Some(op("LD C,C", 1, 1, "----")),
+    Some(op("LD C,D", 1, 1, "----")),
+    Some(op("LD C,E", 1, 1, "----")),
+    Some(op("LD C,H", 1, 1, "----")),
+    Some(op("LD C,L", 1, 1, "----")),
+    Some(op("LD C,(HL)", 1, 2, "----")),
+    Some(op("LD C,A", 1, 1, "----")),
+    // 0x50
+    Some(op("LD D,B", 1, 1, "----")),
+    Some(op("LD D,C", 1, 1, "----")),
+    Some(op("LD D,D", 1, 1, "----")),
+    Some(op("LD D,E", 1, 1, "----")),
+    Some(op("LD D,H", 1, 1, "----")),
+    Some(op("LD D,L", 1, 1, "----")),
+    Some(op("LD D,(HL)", 1, 2, "----")),
+    Some(op("LD D,A", 1, 1, "----")),
+    Some(op("LD E,B", 1, 1, "----")),
+    Some(op("LD E,C", 1, 1, "----")),
+    Some(op("LD E,D", 1, 1, "----")),
+    Some(op("LD E,E", 1, 1, "----")),
+    Some(op("LD E,H", 1, 1, "----")),
+    Some(op("LD E,L", 1, 1, "----")),
+    Some(op("LD E,(HL)", 1, 2, "----")),
+    Some(op("LD E,A", 1, 1, "----")),
+    // 0x60
+    Some(op("LD H,B", 1, 1, "----")),
+    Some(op("LD H,C", 1, 1, "----")),
+    Some(op("LD H,D", 1, 1, "----")),
+    Some(op("LD H,E", 1, 1, "----")),
+    Some(op("LD H,H", 1, 1, "----")),
+    Some(op("LD H,L", 1, 1, "----")),
+    Some(op("LD H,(HL)", 1, 2, "----")),
+    Some(op("LD H,A", 1, 1, "----")),
+    Some(op("LD L,B", 1, 1, "----")),
+    Some(op("LD L,C", 1, 1, "----")),
+    Some(op("LD L,D", 1, 1, "----")),
+    Some(op("LD L,E", 1, 1, "----")),
+    Some(op("LD L,H", 1, 1, "----")),
+    Some(op("LD L,L", 1, 1, "----")),
+    Some(op("LD L,(HL)", 1, 2, "----")),
+    Some(op("LD L,A", 1, 1, "----")),
+    // 0x70
+    Some(op("LD (HL),B", 1, 2, "----")),
+    Some(op("LD (HL),C", 1, 2, "----")),
+    Some(op("LD (HL),D", 1, 2, "----")),
+    Some(op("LD (HL),E", 1, 2, "----")),
+    Some(op("LD (HL),H", 1, 2, "----")),
+    Some(op("LD (HL),L", 1, 2, "----")),
+    Some(op("HALT", 1, 1, "----")),
+    Some(op("LD (HL),A", 1, 2, "----")),
+    Some(op("LD A,B", 1, 1, "----")),
+    Some(op("LD A,C", 1, 1, "----")),
+    Some(op("LD A,D", 1, 1, "----")),
+    Some(op("LD A,E", 1, 1, "----")),
+    Some(op("LD A,H", 1, 1, "----")),
+    Some(op("LD A,L", 1, 1, "----")),
+    Some(op("LD A,(HL)", 1, 2, "----")),
+    Some(op("LD A,A", 1, 1, "----")),
+    // 0x80
+    Some(op("ADD A,B", 1, 1, "Z0HC")),
+    Some(op("ADD A,C", 1, 1, "Z0HC")),
+    Some(op("ADD A,D", 1, 1, "Z0HC")),
+    Some(op("ADD A,E", 1, 1, "Z0HC")),
+    Some(op("ADD A,H", 1, 1, "Z0HC")),
+    Some(op("ADD A,L", 1, 1, "Z0HC")),
+    Some(op("ADD A,(HL)", 1, 2, "Z0HC")),
+    Some(op("ADD A,A", 1, 1, "Z0HC")),
+    Some(op("ADC A,B", 1, 1, "Z0HC")),
+    Some(op("ADC A,C", 1, 1, "Z0HC")),
+    Some(op("ADC A,D", 1, 1, "Z0HC")),
+    Some(op("ADC A,E", 1, 1, "Z0HC")),
+    Some(op("ADC A,H", 1, 1, "Z0HC")),
+    Some(op("ADC A,L", 1, 1, "Z0HC")),
+    Some(op("ADC A,(HL)", 1, 2, "Z0HC")),
+    Some(op("ADC A,A", 1, 1, "Z0HC")),
+    // 0x90
+    Some(op("SUB B", 1, 1, "Z1HC")),
+    Some(op("SUB C", 1, 1, "Z1HC")),
+    Some(op("SUB D", 1, 1, "Z1HC")),
+    Some(op("SUB E", 1, 1, "Z1HC")),
+    Some(op("SUB H", 1, 1, "Z1HC")),
+    Some(op("SUB L", 1, 1, "Z1HC")),
+    Some(op("SUB (HL)", 1, 2, "Z1HC")),
+    Some(op("SUB A", 1, 1, "1100")),
+    Some(op("SBC A,B", 1, 1, "Z1HC")),
+    Some(op("SBC A,C", 1, 1, "Z1HC")),
+    Some(op("SBC A,D", 1, 1, "Z1HC")),
+    Some(op("SBC A,E", 1, 1, "Z1HC")),
+    Some(op("SBC A,H", 1, 1, "Z1HC")),
+    Some(op("SBC A,L", 1, 1, "Z1HC")),
+    Some(op("SBC A,(HL)", 1, 2, "Z1HC")),
+    Some(op("SBC A,A", 1, 1, "Z1HC")),
+    // 0xA0
+    Some(op("AND B", 1, 1, "Z010")),
+    Some(op("AND C", 1, 1, "Z010")),
+    Some(op("AND D", 1, 1, "Z010")),
+    Some(op("AND E", 1, 1, "Z010")),
+    Some(op("AND H", 1, 1, "Z010")),
+    Some(op("AND L", 1, 1, "Z010")),
+    Some(op("AND (HL)", 1, 2, "Z010")),
+    Some(op("AND A", 1, 1, "Z010")),
+    Some(op("XOR B", 1, 1, "Z000")),
+    Some(op("XOR C", 1, 1, "Z000")),
+    Some(op("XOR D", 1, 1, "Z000")),
+    Some(op("XOR E", 1, 1, "Z000")),
+    Some(op("XOR H", 1, 1, "Z000")),
+    Some(op("XOR L", 1, 1, "Z000")),
+    Some(op("XOR (HL)", 1, 2, "Z000")),
+    Some(op("XOR A", 1, 1, "1000")),
+    // 0xB0
+    Some(op("OR B", 1, 1, "Z000")),
+    Some(op("OR C", 1, 1, "Z000")),
+    Some(op("OR D", 1, 1, "Z000")),
+    Some(op("OR E", 1, 1, "Z000")),
+    Some(op("OR H", 1, 1, "Z000")),
+    Some(op("OR L", 1, 1, "Z000")),
+    Some(op("OR (HL)", 1, 2, "Z000")),
+    Some(op("OR A", 1, 1, "Z000")),
+    Some(op("CP B", 1, 1, "Z1HC")),
+    Some(op("CP C", 1, 1, "Z1HC")),
+    Some(op("CP D", 1, 1, "Z1HC")),
+    Some(op("CP E", 1, 1, "Z1HC")),
+    Some(op("CP H", 1, 1, "Z1HC")),
+    Some(op("CP L", 1, 1, "Z1HC")),
+    Some(op("CP (HL)", 1, 2, "Z1HC")),
+    Some(op("CP A", 1, 1, "1100")),
+    // 0xC0
+    Some(branch("RET NZ", 1, 2, 5, "----")),
+    Some(op("POP BC", 1, 3, "----")),
+    Some(branch("JP NZ,a16", 3, 3, 4, "----")),
+    Some(op("JP a16", 3, 4, "----")),
+    Some(branch("CALL NZ,a16", 3, 3, 6, "----")),
+    Some(op("PUSH BC", 1, 4, "----")),
+    Some(op("ADD A,n8", 2, 2, "Z0HC")),
+    Some(op("RST $00", 1, 4, "----")),
+    Some(branch("RET Z", 1, 2, 5, "----")),
+    Some(op("RET", 1, 4, "----")),
+    Some(branch("JP Z,a16", 3, 3, 4, "----")),
+    None,
+    Some(branch("CALL Z,a16", 3, 3, 6, "----")),
+    Some(op("CALL a16", 3, 6, "----")),
+    Some(op("ADC A,n8", 2, 2, "Z0HC")),
+    Some(op("RST $08", 1, 4, "----")),
+    // 0xD0
+    Some(branch("RET NC", 1, 2, 5, "----")),
+    Some(op("POP DE", 1, 3, "----")),
+    Some(branch("JP NC,a16", 3, 3, 4, "----")),
+    None,
+    Some(branch("CALL NC,a16", 3, 3, 6, "----")),
+    Some(op("PUSH DE", 1, 4, "----")),
+    Some(op("SUB n8", 2, 2, "Z1HC")),
+    Some(op("RST $10", 1, 4, "----")),
+    Some(branch("RET C", 1, 2, 5, "----")),
+    Some(op("RETI", 1, 4, "----")),
+    Some(branch("JP C,a16", 3, 3, 4, "----")),
+    None,
+    Some(branch("CALL C,a16", 3, 3, 6, "----")),
+    None,
+    Some(op("SBC A,n8", 2, 2, "Z1HC")),
+    Some(op("RST $18", 1, 4, "----")),
+    // 0xE0
+    Some(op("LDH (a8),A", 2, 3, "----")),
+    Some(op("POP HL", 1, 3, "----")),
+    Some(op("LDH (C),A", 1, 2, "----")),
+    None,
+    None,
+    Some(op("PUSH HL", 1, 4, "----")),
+    Some(op("AND n8", 2, 2, "Z010")),
+    Some(op("RST $20", 1, 4, "----")),
+    Some(op("ADD SP,e8", 2, 4, "00HC")),
+    Some(op("JP HL", 1, 1, "----")),
+    Some(op("LD (a16),A", 3, 4, "----")),
+    None,
+    None,
+    None,
+    Some(op("XOR n8", 2, 2, "Z000")),
+    Some(op("RST $28", 1, 4, "----")),
+    // 0xF0
+    Some(op("LDH A,(a8)", 2, 3, "----")),
+    Some(op("POP AF", 1, 3, "ZNHC")),
+    Some(op("LDH A,(C)", 1, 2, "----")),
+    Some(op("DI", 1, 1, "----")),
+    None,
+    Some(op("PUSH AF", 1, 4, "----")),
+    Some(op("OR n8", 2, 2, "Z000")),
+    Some(op("RST $30", 1, 4, "----")),
+    Some(op("LD HL,SP+e8", 2, 3, "00HC")),
+    Some(op("LD SP,HL", 1, 2, "----")),
+    Some(op("LD A,(a16)", 3, 4, "----")),
+    Some(op("EI", 1, 1, "----")),
+    None,
+    None,
+    Some(op("CP n8", 2, 2, "Z1HC")),
+    Some(op("RST $38", 1, 4, "----")),
+];
+
+/// Opcodes following the CB prefix.
+pub static PREFIXED_OPCODES: [OpcodeInfo; 256] = [
+    // 0x00
+    op("RLC B", 2, 2, "Z00C"),
+    op("RLC C", 2, 2, "Z00C"),
+    op("RLC D", 2, 2, "Z00C"),
+    op("RLC E", 2, 2, "Z00C"),
+    op("RLC H", 2, 2, "Z00C"),
+    op("RLC L", 2, 2, "Z00C"),
+    op("RLC (HL)", 2, 4, "Z00C"),
+    op("RLC A", 2, 2, "Z00C"),
+    op("RRC B", 2, 2, "Z00C"),
+    op("RRC C", 2, 2, "Z00C"),
+    op("RRC D", 2, 2, "Z00C"),
+    op("RRC E", 2, 2, "Z00C"),
+    op("RRC H", 2, 2, "Z00C"),
+    op("RRC L", 2, 2, "Z00C"),
+    op("RRC (HL)", 2, 4, "Z00C"),
+    op("RRC A", 2, 2, "Z00C"),
+    // 0x10
+    op("RL B", 2, 2, "Z00C"),
+    op("RL C", 2, 2, "Z00C"),
+    op("RL D", 2, 2, "Z00C"),
+    op("RL E", 2, 2, "Z00C"),
+    op("RL H", 2, 2, "Z00C"),
+    op("RL L", 2, 2, "Z00C"),
+    op("RL (HL)", 2, 4, "Z00C"),
+    op("RL A", 2, 2, "Z00C"),
+    op("RR B", 2, 2, "Z00C"),
+    op("RR C", 2, 2, "Z00C"),
+    op("RR D", 2, 2, "Z00C"),
+    op("RR E", 2, 2, "Z00C"),
+    op("RR H", 2, 2, "Z00C"),
+    op("RR L", 2, 2, "Z00C"),
+    op("RR (HL)", 2, 4, "Z00C"),
+    op("RR A", 2, 2, "Z00C"),
+    // 0x20
+    op("SLA B", 2, 2, "Z00C"),
+    op("SLA C", 2, 2, "Z00C"),
+    op("SLA D", 2, 2, "Z00C"),
+    op("SLA E", 2, 2, "Z00C"),
+    op("SLA H", 2, 2, "Z00C"),
+    op("SLA L", 2, 2, "Z00C"),
+    op("SLA (HL)", 2, 4, "Z00C"),
+    op("SLA A", 2, 2, "Z00C"),
+    op("SRA B", 2, 2, "Z00C"),
+    op("SRA C", 2, 2, "Z00C"),
+    op("SRA D", 2, 2, "Z00C"),
+    op("SRA E", 2, 2, "Z00C"),
+    op("SRA H", 2, 2, "Z00C"),
+    op("SRA L", 2, 2, "Z00C"),
+    op("SRA (HL)", 2, 4, "Z00C"),
+    op("SRA A", 2, 2, "Z00C"),
+    // 0x30
+    op("SWAP B", 2, 2, "Z000"),
+    op("SWAP C", 2, 2, "Z000"),
+    op("SWAP D", 2, 2, "Z000"),
+    op("SWAP E", 2, 2, "Z000"),
+    op("SWAP H", 2, 2, "Z000"),
+    op("SWAP L", 2, 2, "Z000"),
+    op("SWAP (HL)", 2, 4, "Z000"),
+    op("SWAP A", 2, 2, "Z000"),
+    op("SRL B", 2, 2, "Z00C"),
+    op("SRL C", 2, 2, "Z00C"),
+    op("SRL D", 2, 2, "Z00C"),
+    op("SRL E", 2, 2, "Z00C"),
+    op("SRL H", 2, 2, "Z00C"),
+    op("SRL L", 2, 2, "Z00C"),
+    op("SRL (HL)", 2, 4, "Z00C"),
+    op("SRL A", 2, 2, "Z00C"),
+    // 0x40
+    op("BIT 0,B", 2, 2, "Z01-"),
+    op("BIT 0,C", 2, 2, "Z01-"),
+    op("BIT 0,D", 2, 2, "Z01-"),
+    op("BIT 0,E", 2, 2, "Z01-"),
+    op("BIT 0,H", 2, 2, "Z01-"),
+    op("BIT 0,L", 2, 2, "Z01-"),
+    op("BIT 0,(HL)", 2, 3, "Z01-"),
+    op("BIT 0,A", 2, 2, "Z01-"),
+    op("BIT 1,B", 2, 2, "Z01-"),
+    op("BIT 1,C", 2, 2, "Z01-"),
+    op("BIT 1,D", 2, 2, "Z01-"),
+    op("BIT 1,E", 2, 2, "Z01-"),
+    op("BIT 1,H", 2, 2, "Z01-"),
+    op("BIT 1,L", 2, 2, "Z01-"),
+    op("BIT 1,(HL)", 2, 3, "Z01-"),
+    op("BIT 1,A", 2, 2, "Z01-"),
+    // 0x50
+    op("BIT 2,B", 2, 2, "Z01-"),
+    op("BIT 2,C", 2, 2, "Z01-"),
+    op("BIT 2,D", 2, 2, "Z01-"),
+    op("BIT 2,E", 2, 2, "Z01-"),
+    op("BIT 2,H", 2, 2, "Z01-"),
+    op("BIT 2,L", 2, 2, "Z01-"),
+    op("BIT 2,(HL)", 2, 3, "Z01-"),
+    op("BIT 2,A", 2, 2, "Z01-"),
+    op("BIT 3,B", 2, 2, "Z01-"),
+    op("BIT 3,C", 2, 2, "Z01-"),
+    op("BIT 3,D", 2, 2, "Z01-"),
+    op("BIT 3,E", 2, 2, "Z01-"),
+    op("BIT 3,H", 2, 2, "Z01-"),
+    op("BIT 3,L", 2, 2, "Z01-"),
+    op("BIT 3,(HL)", 2, 3, "Z01-"),
+    op("BIT 3,A", 2, 2, "Z01-"),
+    // 0x60
+    op("BIT 4,B", 2, 2, "Z01-"),
+    op("BIT 4,C", 2, 2, "Z01-"),
+    op("BIT 4,D", 2, 2, "Z01-"),
+    op("BIT 4,E", 2, 2, "Z01-"),
+    op("BIT 4,H", 2, 2, "Z01-"),
+    op("BIT 4,L", 2, 2, "Z01-"),
+    op("BIT 4,(HL)", 2, 3, "Z01-"),
+    op("BIT 4,A", 2, 2, "Z01-"),
+    op("BIT 5,B", 2, 2, "Z01-"),
+    op("BIT 5,C", 2, 2, "Z01-"),
+    op("BIT 5,D", 2, 2, "Z01-"),
+    op("BIT 5,E", 2, 2, "Z01-"),
+    op("BIT 5,H", 2, 2, "Z01-"),
+    op("BIT 5,L", 2, 2, "Z01-"),
+    op("BIT 5,(HL)", 2, 3, "Z01-"),
+    op("BIT 5,A", 2, 2, "Z01-"),
+    // 0x70
+    op("BIT 6,B", 2, 2, "Z01-"),
+    op("BIT 6,C", 2, 2, "Z01-"),
+    op("BIT 6,D", 2, 2, "Z01-"),
+    op("BIT 6,E", 2, 2, "Z01-"),
+    op("BIT 6,H", 2, 2, "Z01-"),
+    op("BIT 6,L", 2, 2, "Z01-"),
+    op("BIT 6,(HL)", 2, 3, "Z01-"),
+    op("BIT 6,A", 2, 2, "Z01-"),
+    op("BIT 7,B", 2, 2, "Z01-"),
+    op("BIT 7,C", 2, 2, "Z01-"),
+    op("BIT 7,D", 2, 2, "Z01-"),
+    op("BIT 7,E", 2, 2, "Z01-"),
+    op("BIT 7,H", 2, 2, "Z01-"),
+    op("BIT 7,L", 2, 2, "Z01-"),
+    op("BIT 7,(HL)", 2, 3, "Z01-"),
+    op("BIT 7,A", 2, 2, "Z01-"),
+    // 0x80
+    op("RES 0,B", 2, 2, "----"),
+    op("RES 0,C", 2, 2, "----"),
+    op("RES 0,D", 2, 2, "----"),
+    op("RES 0,E", 2, 2, "----"),
+    op("RES 0,H", 2, 2, "----"),
+    op("RES 0,L", 2, 2, "----"),
+    op("RES 0,(HL)", 2, 4, "----"),
+    op("RES 0,A", 2, 2, "----"),
+    op("RES 1,B", 2, 2, "----"),
+    op("RES 1,C", 2, 2, "----"),
+    op("RES 1,D", 2, 2, "----"),
+    op("RES 1,E", 2, 2, "----"),
+    op("RES 1,H", 2, 2, "----"),
+    op("RES 1,L", 2, 2, "----"),
+    op("RES 1,(HL)", 2, 4, "----"),
+    op("RES 1,A", 2, 2, "----"),
+    // 0x90
+    op("RES 2,B", 2, 2, "----"),
+    op("RES 2,C", 2, 2, "----"),
+    op("RES 2,D", 2, 2, "----"),
+    op("RES 2,E", 2, 2, "----"),
+    op("RES 2,H", 2, 2, "----"),
+    op("RES 2,L", 2, 2, "----"),
+    op("RES 2,(HL)", 2, 4, "----"),
+    op("RES 2,A", 2, 2, "----"),
+    op("RES 3,B", 2, 2, "----"),
+    op("RES 3,C", 2, 2, "----"),
+    op("RES 3,D", 2, 2, "----"),
+    op("RES 3,E", 2, 2, "----"),
+    op("RES 3,H", 2, 2, "----"),
+    op("RES 3,L", 2, 2, "----"),
+    op("RES 3,(HL)", 2, 4, "----"),
+    op("RES 3,A", 2, 2, "----"),
+    // 0xA0
+    op("RES 4,B", 2, 2, "----"),
+    op("RES 4,C", 2, 2, "----"),
+    op("RES 4,D", 2, 2, "----"),
+    op("RES 4,E", 2, 2, "----"),
+    op("RES 4,H", 2, 2, "----"),
+    op("RES 4,L", 2, 2, "----"),
+    op("RES 4,(HL)", 2, 4, "----"),
+    op("RES 4,A", 2, 2, "----"),
+    op("RES 5,B", 2, 2, "----"),
+    op("RES 5,C", 2, 2, "----"),
+    op("RES 5,D", 2, 2, "----"),
+    op("RES 5,E", 2, 2, "----"),
+    op("RES 5,H", 2, 2, "----"),
+    op("RES 5,L", 2, 2, "----"),
+    op("RES 5,(HL)", 2, 4, "----"),
+    op("RES 5,A", 2, 2, "----"),
+    // 0xB0
+    op("RES 6,B", 2, 2, "----"),
+    op("RES 6,C", 2, 2, "----"),
+    op("RES 6,D", 2, 2, "----"),
+    op("RES 6,E", 2, 2, "----"),
+    op("RES 6,H", 2, 2, "----"),
+    op("RES 6,L", 2, 2, "----"),
+    op("RES 6,(HL)", 2, 4, "----"),
+    op("RES 6,A", 2, 2, "----"),
+    op("RES 7,B", 2, 2, "----"),
+    op("RES 7,C", 2, 2, "----"),
+    op("RES 7,D", 2, 2, "----"),
+    op("RES 7,E", 2, 2, "----"),
+    op("RES 7,H", 2, 2, "----"),
+    op("RES 7,L", 2, 2, "----"),
+    op("RES 7,(HL)", 2, 4, "----"),
+    op("RES 7,A", 2, 2, "----"),
+    // 0xC0
+    op("SET 0,B", 2, 2, "----"),
+    op("SET 0,C", 2, 2, "----"),
+    op("SET 0,D", 2, 2, "----"),
+    op("SET 0,E", 2, 2, "----"),
+    op("SET 0,H", 2, 2, "----"),
+    op("SET 0,L", 2, 2, "----"),
+    op("SET 0,(HL)", 2, 4, "----"),
+    op("SET 0,A", 2, 2, "----"),
+    op("SET 1,B", 2, 2, "----"),
+    op("SET 1,C", 2, 2, "----"),
+    op("SET 1,D", 2, 2, "----"),
+    op("SET 1,E", 2, 2, "----"),
+    op("SET 1,H", 2, 2, "----"),
+    op("SET 1,L", 2, 2, "----"),
+    op("SET 1,(HL)", 2, 4, "----"),
+    op("SET 1,A", 2, 2, "----"),
+    // 0xD0
+    op("SET 2,B", 2, 2, "----"),
+    op("SET 2,C", 2, 2, "----"),
+    op("SET 2,D", 2, 2, "----"),
+    op("SET 2,E", 2, 2, "----"),
+    op("SET 2,H", 2, 2, "----"),
+    op("SET 2,L", 2, 2, "----"),
+    op("SET 2,(HL)", 2, 4, "----"),
+    op("SET 2,A", 2, 2, "----"),
+    op("SET 3,B", 2, 2, "----"),
+    op("SET 3,C", 2, 2, "----"),
+    op("SET 3,D", 2, 2, "----"),
+    op("SET 3,E", 2, 2, "----"),
+    op("SET 3,H", 2, 2, "----"),
+    op("SET 3,L", 2, 2, "----"),
+    op("SET 3,(HL)", 2, 4, "----"),
+    op("SET 3,A", 2, 2, "----"),
+    // 0xE0
+    op("SET 4,B", 2, 2, "----"),
+    op("SET 4,C", 2, 2, "----"),
+    op("SET 4,D", 2, 2, "----"),
+    op("SET 4,E", 2, 2, "----"),
+    op("SET 4,H", 2, 2, "----"),
+    op("SET 4,L", 2, 2, "----"),
+    op("SET 4,(HL)", 2, 4, "----"),
+    op("SET 4,A", 2, 2, "----"),
+    op("SET 5,B", 2, 2, "----"),
+    op("SET 5,C", 2, 2, "----"),
+    op("SET 5,D", 2, 2, "----"),
+    op("SET 5,E", 2, 2, "----"),
+    op("SET 5,H", 2, 2, "----"),
+    op("SET 5,L", 2, 2, "----"),
+    op("SET 5,(HL)", 2, 4, "----"),
+    op("SET 5,A", 2, 2, "----"),
+    // 0xF0
+    op("SET 6,B", 2, 2, "----"),
+    op("SET 6,C", 2, 2, "----"),
+    op("SET 6,D", 2, 2, "----"),
+    op("SET 6,E", 2, 2, "----"),
+    op("SET 6,H", 2, 2, "----"),
+    op("SET 6,L", 2, 2, "----"),
+    op("SET 6,(HL)", 2, 4, "----"),
+    op("SET 6,A", 2, 2, "----"),
+    op("SET 7,B", 2, 2, "----"),
+    op("SET 7,C", 2, 2, "----"),
+    op("SET 7,D", 2, 2, "----"),
+    op("SET 7,E", 2, 2, "----"),
+    op("SET 7,H", 2, 2, "----"),
+    op("SET 7,L", 2, 2, "----"),
+    op("SET 7,(HL)", 2, 4, "----"),
+    op("SET 7,A", 2, 2, "----"),
+];
+
+#[cfg(test)]
+mod opcodes_tests {
+    use super::*;
+    use crate::instructions::ILLEGAL_OPCODES;
+
+    fn decode(opcode: u8, prefixed: bool) -> Option<Instruction> {
+        let mut operands = [0x12, 0x34].iter();
+        Instruction::from_byte(opcode, prefixed, || *operands.next().unwrap()).ok()
+    }
+
+    fn check_entry(info: &OpcodeInfo, instruction: Instruction) {
+        assert_eq!(
+            info.length as usize,
+            instruction.length(),
+            "{}",
+            info.mnemonic
+        );
+        match info.taken_cycles {
+            Some(taken_cycles) => {
+                assert_eq!(info.cycles, instruction.cycles(false), "{}", info.mnemonic);
+                assert_eq!(taken_cycles, instruction.cycles(true), "{}", info.mnemonic);
+            }
+            // Unconditional jumps, calls and returns are always taken.
+            None => assert_eq!(info.cycles, instruction.cycles(true), "{}", info.mnemonic),
+        }
+
+        let display = instruction.to_string();
+        assert_eq!(
+            info.mnemonic.split(' ').next(),
+            display.split(' ').next(),
+            "{}",
+            display
+        );
+        assert_eq!(instruction.info(), info, "{}", display);
+    }
+
+    #[test]
+    fn test_unprefixed_table_matches_instructions() {
+        for opcode in 0..=0xFF {
+            let entry = UNPREFIXED_OPCODES[opcode as usize];
+            if opcode == PREFIX_BYTE || ILLEGAL_OPCODES.contains(&opcode) {
+                assert_eq!(entry, None, "{:#04x}", opcode);
+                continue;
+            }
+
+            check_entry(&entry.unwrap(), decode(opcode, false).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_prefixed_table_matches_instructions() {
+        for opcode in 0..=0xFF {
+            check_entry(
+                &PREFIXED_OPCODES[opcode as usize],
+                decode(opcode, true).unwrap(),
+            );
+        }
+    }
+
+    #[test]
+    fn test_only_conditional_branches_have_taken_cycles() {
+        let branches: Vec<_> = UNPREFIXED_OPCODES
+            .iter()
+            .flatten()
+            .filter(|info| info.taken_cycles.is_some())
+            .map(|info| info.mnemonic)
+            .collect();
+
+        assert_eq!(branches.len(), 16);
+        for mnemonic in branches {
+            let condition = mnemonic.split([' ', ',']).nth(1);
+            assert!(
+                matches!(condition, Some("NZ") | Some("Z") | Some("NC") | Some("C")),
+                "{}",
+                mnemonic
+            );
+        }
+    }
+
+    #[test]
+    fn test_flag_notation() {
+        assert_eq!(
+            UNPREFIXED_OPCODES[0x27].unwrap().flags,
+            FlagEffects {
+                zero: FlagEffect::Modified,
+                substraction: FlagEffect::Unaffected,
+                half_carry: FlagEffect::Reset,
+                carry: FlagEffect::Modified,
+            }
+        );
+        assert_eq!(
+            PREFIXED_OPCODES[0x7E].flags,
+            FlagEffects {
+                zero: FlagEffect::Modified,
+                substraction: FlagEffect::Reset,
+                half_carry: FlagEffect::Set,
+                carry: FlagEffect::Unaffected,
+            }
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use super::flagsregister::FlagsRegister;
-use super::instructions::ArithmeticRegisters;
+use super::instructions::{ArithmeticRegisters, WideRegisters};
 
-#[derive(Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct Registers {
     pub a: u8,
     pub b: u8,
@@ -11,6 +11,8 @@ pub struct Registers {
     pub f: FlagsRegister,
     pub h: u8,
     pub l: u8,
+    pub sp: u16,
+    pub pc: u16,
 }
 
 impl Registers {
@@ -24,6 +26,8 @@ impl Registers {
             f: FlagsRegister::new(),
             h: 0,
             l: 0,
+            sp: 0,
+            pc: 0,
         }
     }
 
@@ -38,6 +42,73 @@ impl Registers {
             ArithmeticRegisters::L => self.l,
         }
     }
+
+    pub fn store(&mut self, reg: ArithmeticRegisters, value: u8) {
+        match reg {
+            ArithmeticRegisters::A => self.a = value,
+            ArithmeticRegisters::B => self.b = value,
+            ArithmeticRegisters::C => self.c = value,
+            ArithmeticRegisters::D => self.d = value,
+            ArithmeticRegisters::E => self.e = value,
+            ArithmeticRegisters::H => self.h = value,
+            ArithmeticRegisters::L => self.l = value,
+        }
+    }
+
+    pub fn load_wide(&self, reg: WideRegisters) -> u16 {
+        match reg {
+            WideRegisters::BC => self.get_bc(),
+            WideRegisters::DE => self.get_de(),
+            WideRegisters::HL => self.get_hl(),
+            WideRegisters::SP => self.sp,
+        }
+    }
+
+    pub fn store_wide(&mut self, reg: WideRegisters, value: u16) {
+        match reg {
+            WideRegisters::BC => self.set_bc(value),
+            WideRegisters::DE => self.set_de(value),
+            WideRegisters::HL => self.set_hl(value),
+            WideRegisters::SP => self.sp = value,
+        }
+    }
+
+    pub fn get_af(&self) -> u16 {
+        (self.a as u16) << 8 | u8::from(self.f) as u16
+    }
+
+    /// The lower nibble of F does not exist in hardware and is dropped.
+    pub fn set_af(&mut self, value: u16) {
+        self.a = (value >> 8) as u8;
+        self.f = FlagsRegister::from(value as u8);
+    }
+
+    pub fn get_bc(&self) -> u16 {
+        (self.b as u16) << 8 | self.c as u16
+    }
+
+    pub fn set_bc(&mut self, value: u16) {
+        self.b = (value >> 8) as u8;
+        self.c = value as u8;
+    }
+
+    pub fn get_de(&self) -> u16 {
+        (self.d as u16) << 8 | self.e as u16
+    }
+
+    pub fn set_de(&mut self, value: u16) {
+        self.d = (value >> 8) as u8;
+        self.e = value as u8;
+    }
+
+    pub fn get_hl(&self) -> u16 {
+        (self.h as u16) << 8 | self.l as u16
+    }
+
+    pub fn set_hl(&mut self, value: u16) {
+        self.h = (value >> 8) as u8;
+        self.l = value as u8;
+    }
 }
 
 #[cfg(test)]
@@ -100,4 +171,83 @@ mod registers_tests {
         assert_eq!(registers.load(ArithmeticRegisters::L), registers.l);
     }
 
+    #[test]
+    fn test_store_works_for_all_registers() {
+        let mut registers: Registers = Default::default();
+
+        registers.store(ArithmeticRegisters::A, 1);
+        registers.store(ArithmeticRegisters::B, 2);
+        registers.store(ArithmeticRegisters::C, 3);
+        registers.store(ArithmeticRegisters::D, 4);
+        registers.store(ArithmeticRegisters::E, 5);
+        registers.store(ArithmeticRegisters::H, 6);
+        registers.store(ArithmeticRegisters::L, 7);
+
+        assert_eq!(registers.a, 1);
+        assert_eq!(registers.b, 2);
+        assert_eq!(registers.c, 3);
+        assert_eq!(registers.d, 4);
+        assert_eq!(registers.e, 5);
+        assert_eq!(registers.h, 6);
+        assert_eq!(registers.l, 7);
+    }
+
+    #[test]
+    fn test_get_pairs_combine_high_and_low_registers() {
+        let mut registers: Registers = Default::default();
+
+        registers.b = 0x12;
+        registers.c = 0x34;
+        registers.d = 0x56;
+        registers.e = 0x78;
+        registers.h = 0x9A;
+        registers.l = 0xBC;
+
+        assert_eq!(registers.get_bc(), 0x1234);
+        assert_eq!(registers.get_de(), 0x5678);
+        assert_eq!(registers.get_hl(), 0x9ABC);
+    }
+
+    #[test]
+    fn test_set_pairs_split_into_high_and_low_registers() {
+        let mut registers: Registers = Default::default();
+
+        registers.set_bc(0x1234);
+        registers.set_de(0x5678);
+        registers.set_hl(0x9ABC);
+
+        assert_eq!((registers.b, registers.c), (0x12, 0x34));
+        assert_eq!((registers.d, registers.e), (0x56, 0x78));
+        assert_eq!((registers.h, registers.l), (0x9A, 0xBC));
+    }
+
+    #[test]
+    fn test_set_af_masks_lower_nibble_of_f() {
+        let mut registers: Registers = Default::default();
+
+        registers.set_af(0x12FF);
+
+        assert_eq!(registers.a, 0x12);
+        assert_eq!(registers.f.zero, true);
+        assert_eq!(registers.f.substraction, true);
+        assert_eq!(registers.f.half_carry, true);
+        assert_eq!(registers.f.carry, true);
+        assert_eq!(registers.get_af(), 0x12F0);
+    }
+
+    #[test]
+    fn test_load_and_store_wide_for_all_registers() {
+        let mut registers: Registers = Default::default();
+
+        registers.store_wide(WideRegisters::BC, 0x0102);
+        registers.store_wide(WideRegisters::DE, 0x0304);
+        registers.store_wide(WideRegisters::HL, 0x0506);
+        registers.store_wide(WideRegisters::SP, 0x0708);
+
+        assert_eq!(registers.load_wide(WideRegisters::BC), 0x0102);
+        assert_eq!(registers.load_wide(WideRegisters::DE), 0x0304);
+        assert_eq!(registers.load_wide(WideRegisters::HL), 0x0506);
+        assert_eq!(registers.load_wide(WideRegisters::SP), 0x0708);
+        assert_eq!(registers.sp, 0x0708);
+    }
 }
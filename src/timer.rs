@@ -0,0 +1,169 @@
+pub const DIV_ADDRESS: u16 = 0xFF04;
+pub const TIMA_ADDRESS: u16 = 0xFF05;
+pub const TMA_ADDRESS: u16 = 0xFF06;
+pub const TAC_ADDRESS: u16 = 0xFF07;
+
+const TAC_ENABLE: u8 = 0b100;
+
+/// DIV, TIMA, TMA and TAC. DIV is the upper byte of a 16-bit counter that advances every clock
+/// cycle, and TIMA counts the falling edges of the counter bit selected by TAC.
+#[derive(Default)]
+pub struct Timer {
+    counter: u16,
+    tima: u8,
+    tma: u8,
+    tac: u8,
+}
+
+impl Timer {
+    pub fn new() -> Self {
+        Timer {
+            counter: 0,
+            tima: 0,
+            tma: 0,
+            tac: 0,
+        }
+    }
+
+    /// Advances the timer by one machine cycle and returns whether TIMA overflowed, which
+    /// requests the timer interrupt.
+    pub fn tick(&mut self) -> bool {
+        let counter = self.counter.wrapping_add(4);
+        self.update(counter, self.tac)
+    }
+
+    pub fn read_byte(&self, address: u16) -> u8 {
+        match address {
+            DIV_ADDRESS => (self.counter >> 8) as u8,
+            TIMA_ADDRESS => self.tima,
+            TMA_ADDRESS => self.tma,
+            TAC_ADDRESS => self.tac | !0b111,
+            _ => panic!("{:#06x} is not a timer register", address),
+        }
+    }
+
+    /// Writes a timer register and returns whether TIMA overflowed as a consequence.
+    pub fn write_byte(&mut self, address: u16, value: u8) -> bool {
+        match address {
+            // Any write resets the whole counter, not just DIV.
+            DIV_ADDRESS => self.update(0, self.tac),
+            TIMA_ADDRESS => {
+                self.tima = value;
+                false
+            }
+            TMA_ADDRESS => {
+                self.tma = value;
+                false
+            }
+            TAC_ADDRESS => self.update(self.counter, value & 0b111),
+            _ => panic!("{:#06x} is not a timer register", address),
+        }
+    }
+
+    /// Changes the counter and TAC, incrementing TIMA if that makes the selected counter bit
+    /// fall. Resetting DIV or changing TAC can therefore increment TIMA just like ticking.
+    fn update(&mut self, counter: u16, tac: u8) -> bool {
+        let old_signal = timer_signal(self.counter, self.tac);
+        self.counter = counter;
+        self.tac = tac;
+
+        if old_signal && !timer_signal(self.counter, self.tac) {
+            let (new_value, overflow) = self.tima.overflowing_add(1);
+            self.tima = if overflow { self.tma } else { new_value };
+            overflow
+        } else {
+            false
+        }
+    }
+}
+
+fn timer_signal(counter: u16, tac: u8) -> bool {
+    let bit = match tac & 0b11 {
+        0b00 => 9,
+        0b01 => 3,
+        0b10 => 5,
+        _ => 7,
+    };
+    tac & TAC_ENABLE != 0 && counter & (1 << bit) != 0
+}
+
+#[cfg(test)]
+mod timer_tests {
+    use super::*;
+
+    fn tick_times(timer: &mut Timer, cycles: u32) -> u32 {
+        (0..cycles).filter(|_| timer.tick()).count() as u32
+    }
+
+    #[test]
+    fn test_div_increments_every_64_machine_cycles() {
+        let mut timer = Timer::new();
+
+        tick_times(&mut timer, 63);
+        assert_eq!(timer.read_byte(DIV_ADDRESS), 0);
+
+        tick_times(&mut timer, 1);
+        assert_eq!(timer.read_byte(DIV_ADDRESS), 1);
+
+        tick_times(&mut timer, 64 * 255);
+        assert_eq!(timer.read_byte(DIV_ADDRESS), 0);
+    }
+
+    #[test]
+    fn test_write_to_div_resets_it() {
+        let mut timer = Timer::new();
+
+        tick_times(&mut timer, 200);
+        timer.write_byte(DIV_ADDRESS, 0x55);
+
+        assert_eq!(timer.read_byte(DIV_ADDRESS), 0);
+    }
+
+    #[test]
+    fn test_tima_does_not_count_when_disabled() {
+        let mut timer = Timer::new();
+
+        timer.write_byte(TAC_ADDRESS, 0b01);
+        tick_times(&mut timer, 1000);
+
+        assert_eq!(timer.read_byte(TIMA_ADDRESS), 0);
+        assert_eq!(timer.read_byte(TAC_ADDRESS), 0xF9);
+    }
+
+    #[test]
+    fn test_tima_frequencies() {
+        for &(tac, period) in &[(0b100, 256), (0b101, 4), (0b110, 16), (0b111, 64)] {
+            let mut timer = Timer::new();
+
+            timer.write_byte(TAC_ADDRESS, tac);
+            tick_times(&mut timer, period * 3);
+
+            assert_eq!(timer.read_byte(TIMA_ADDRESS), 3, "TAC {:#04b}", tac);
+        }
+    }
+
+    #[test]
+    fn test_tima_overflow_reloads_tma_and_reports_interrupt() {
+        let mut timer = Timer::new();
+
+        timer.write_byte(TMA_ADDRESS, 0xF0);
+        timer.write_byte(TIMA_ADDRESS, 0xFF);
+        timer.write_byte(TAC_ADDRESS, 0b101);
+
+        assert_eq!(tick_times(&mut timer, 3), 0);
+        assert_eq!(tick_times(&mut timer, 1), 1);
+        assert_eq!(timer.read_byte(TIMA_ADDRESS), 0xF0);
+    }
+
+    #[test]
+    fn test_resetting_div_on_a_high_selected_bit_increments_tima() {
+        let mut timer = Timer::new();
+
+        timer.write_byte(TAC_ADDRESS, 0b101);
+        tick_times(&mut timer, 2);
+        assert_eq!(timer.read_byte(TIMA_ADDRESS), 0);
+
+        timer.write_byte(DIV_ADDRESS, 0);
+        assert_eq!(timer.read_byte(TIMA_ADDRESS), 1);
+    }
+}
@@ -0,0 +1,305 @@
+#![allow(clippy::bool_assert_comparison)]
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
+
+use oxi_boy::cartridge::{header_checksum, LoadError};
+use oxi_boy::gameboy::{CYCLES_PER_FRAME, SCREEN_HEIGHT, SCREEN_WIDTH};
+use oxi_boy::mbc3::Clock;
+use oxi_boy::{Buttons, ErrorPolicy, ExecutionError, GameBoy};
+
+/// A 32 KiB ROM with `program` at the cartridge entry point.
+fn rom_with_program(program: &[u8]) -> Vec<u8> {
+    let mut rom = vec![0x00; 0x8000];
+    rom[0x0100..0x0100 + program.len()].copy_from_slice(program);
+    rom[0x014D] = header_checksum(&rom);
+    rom
+}
+
+fn gameboy_with_program(program: &[u8]) -> GameBoy {
+    let mut gameboy = GameBoy::new();
+    gameboy.load_rom(rom_with_program(program)).unwrap();
+    gameboy
+}
+
+#[test]
+fn test_load_rom_starts_at_entry_point_with_post_boot_registers() {
+    let gameboy = gameboy_with_program(&[]);
+
+    let registers = gameboy.registers();
+    assert_eq!(registers.pc, 0x0100);
+    assert_eq!(registers.sp, 0xFFFE);
+    assert_eq!(registers.get_af(), 0x01B0);
+    assert_eq!(registers.get_bc(), 0x0013);
+    assert_eq!(registers.get_de(), 0x00D8);
+    assert_eq!(registers.get_hl(), 0x014D);
+
+    let flags = gameboy.flags();
+    assert_eq!(flags.zero, true);
+    assert_eq!(flags.substraction, false);
+    assert_eq!(flags.half_carry, true);
+    assert_eq!(flags.carry, true);
+}
+
+#[test]
+fn test_step_runs_program() {
+    // LD A,$41; INC A; LD ($C000),A
+    let mut gameboy = gameboy_with_program(&[0x3E, 0x41, 0x3C, 0xEA, 0x00, 0xC0]);
+
+    assert_eq!(gameboy.step(), Ok(2));
+    assert_eq!(gameboy.step(), Ok(1));
+    assert_eq!(gameboy.step(), Ok(4));
+
+    assert_eq!(gameboy.registers().a, 0x42);
+    assert_eq!(gameboy.flags().zero, false);
+    assert_eq!(gameboy.read_byte(0xC000), 0x42);
+    assert_eq!(gameboy.cycles(), 7);
+}
+
+#[test]
+fn test_run_frame_keeps_frames_aligned() {
+    // JP $0100
+    let mut gameboy = gameboy_with_program(&[0xC3, 0x00, 0x01]);
+
+    gameboy.run_frame().unwrap();
+    assert!(gameboy.cycles() >= CYCLES_PER_FRAME);
+    assert!(gameboy.cycles() < CYCLES_PER_FRAME + 4);
+
+    gameboy.run_frame().unwrap();
+    assert!(gameboy.cycles() >= 2 * CYCLES_PER_FRAME);
+    assert!(gameboy.cycles() < 2 * CYCLES_PER_FRAME + 4);
+}
+
+#[test]
+fn test_framebuffer_covers_the_screen() {
+    let gameboy = gameboy_with_program(&[]);
+
+    assert_eq!(gameboy.framebuffer().len(), SCREEN_WIDTH * SCREEN_HEIGHT);
+}
+
+#[test]
+fn test_program_reads_buttons() {
+    // LD A,$20; LDH ($00),A; LDH A,($00); LD ($C000),A; JR -2
+    let mut gameboy = gameboy_with_program(&[
+        0x3E, 0x20, 0xE0, 0x00, 0xF0, 0x00, 0xEA, 0x00, 0xC0, 0x18, 0xFE,
+    ]);
+
+    gameboy.set_buttons(Buttons {
+        left: true,
+        start: true,
+        ..Buttons::default()
+    });
+    gameboy.run_frame().unwrap();
+
+    assert_eq!(gameboy.read_byte(0xC000), 0xED);
+}
+
+#[test]
+fn test_button_press_wakes_halted_program_through_joypad_interrupt() {
+    // LD A,$10; LDH ($00),A; LDH ($FF),A; EI; HALT; JR -3
+    let mut rom = rom_with_program(&[0x3E, 0x10, 0xE0, 0x00, 0xE0, 0xFF, 0xFB, 0x76, 0x18, 0xFD]);
+    // LD A,$99; LD ($C000),A; RETI
+    let handler = [0x3E, 0x99, 0xEA, 0x00, 0xC0, 0xD9];
+    rom[0x0060..0x0060 + handler.len()].copy_from_slice(&handler);
+
+    let mut gameboy = GameBoy::new();
+    gameboy.load_rom(rom).unwrap();
+
+    gameboy.run_frame().unwrap();
+    assert_eq!(gameboy.read_byte(0xC000), 0x00);
+    assert_eq!(gameboy.registers().pc, 0x0108);
+
+    gameboy.set_buttons(Buttons {
+        a: true,
+        ..Buttons::default()
+    });
+    gameboy.run_frame().unwrap();
+    assert_eq!(gameboy.read_byte(0xC000), 0x99);
+}
+
+#[test]
+fn test_illegal_opcode_is_reported_with_its_address() {
+    let mut gameboy = gameboy_with_program(&[0x00, 0xFD]);
+
+    gameboy.step().unwrap();
+
+    assert_eq!(
+        gameboy.run_frame(),
+        Err(ExecutionError::IllegalOpcode {
+            pc: 0x0101,
+            opcode: 0xFD
+        })
+    );
+}
+
+#[test]
+fn test_hardware_error_policy_freezes_without_reporting() {
+    let mut gameboy = GameBoy::new();
+
+    gameboy.set_error_policy(ErrorPolicy::Hardware);
+    gameboy.load_rom(rom_with_program(&[0xFD, 0x3C])).unwrap();
+    gameboy.run_frame().unwrap();
+    gameboy.run_frame().unwrap();
+
+    assert_eq!(gameboy.registers().pc, 0x0101);
+    assert_eq!(gameboy.registers().a, 0x01);
+}
+
+#[test]
+fn test_load_rom_rejects_bad_header_and_keeps_running_cartridge() {
+    let mut gameboy = gameboy_with_program(&[0x3C]);
+    gameboy.step().unwrap();
+
+    let mut rom = rom_with_program(&[]);
+    rom[0x014D] ^= 0xFF;
+
+    assert_eq!(
+        gameboy.load_rom(rom),
+        Err(LoadError::BadHeaderChecksum {
+            expected: !header_checksum(&rom_with_program(&[])),
+            computed: header_checksum(&rom_with_program(&[])),
+        })
+    );
+    assert_eq!(gameboy.registers().pc, 0x0101);
+    assert_eq!(gameboy.header().unwrap().cartridge_type.code, 0x00);
+}
+
+#[test]
+fn test_program_switches_mbc1_rom_banks() {
+    // LD A,$05; LD ($2000),A; LD A,($4000); LD ($C000),A
+    let mut rom = rom_with_program(&[
+        0x3E, 0x05, 0xEA, 0x00, 0x20, 0xFA, 0x00, 0x40, 0xEA, 0x00, 0xC0,
+    ]);
+    rom.resize(8 * 0x4000, 0x00);
+    for bank in 1..8 {
+        rom[bank * 0x4000] = bank as u8;
+    }
+    rom[0x0147] = 0x01;
+    rom[0x0148] = 0x02;
+    rom[0x014D] = header_checksum(&rom);
+
+    let mut gameboy = GameBoy::new();
+    gameboy.load_rom(rom).unwrap();
+    assert_eq!(gameboy.read_byte(0x4000), 0x01);
+
+    for _ in 0..4 {
+        gameboy.step().unwrap();
+    }
+
+    assert_eq!(gameboy.read_byte(0xC000), 0x05);
+}
+
+#[test]
+fn test_program_uses_mbc2_ram() {
+    // LD A,$0A; LD ($0000),A; LD A,$35; LD ($A201),A; LD A,($A001); LD ($C000),A
+    let mut rom = rom_with_program(&[
+        0x3E, 0x0A, 0xEA, 0x00, 0x00, 0x3E, 0x35, 0xEA, 0x01, 0xA2, 0xFA, 0x01, 0xA0, 0xEA, 0x00,
+        0xC0,
+    ]);
+    rom[0x0147] = 0x06;
+    rom[0x014D] = header_checksum(&rom);
+
+    let mut gameboy = GameBoy::new();
+    gameboy.load_rom(rom).unwrap();
+    for _ in 0..6 {
+        gameboy.step().unwrap();
+    }
+
+    assert_eq!(gameboy.read_byte(0xC000), 0xF5);
+}
+
+#[test]
+fn test_program_reads_mbc3_clock_driven_by_emulated_cycles() {
+    // LD A,$0A; LD ($0000),A; LD A,$08; LD ($4000),A
+    // loop: XOR A; LD ($6000),A; INC A; LD ($6000),A; LD A,($A000); OR A; JR Z,loop
+    // LD ($C000),A; JR -2
+    let mut rom = rom_with_program(&[
+        0x3E, 0x0A, 0xEA, 0x00, 0x00, 0x3E, 0x08, 0xEA, 0x00, 0x40, 0xAF, 0xEA, 0x00, 0x60, 0x3C,
+        0xEA, 0x00, 0x60, 0xFA, 0x00, 0xA0, 0xB7, 0x28, 0xF2, 0xEA, 0x00, 0xC0, 0x18, 0xFE,
+    ]);
+    rom[0x0147] = 0x10;
+    rom[0x0149] = 0x02;
+    rom[0x014D] = header_checksum(&rom);
+
+    let mut gameboy = GameBoy::new();
+    gameboy.load_rom(rom).unwrap();
+    while gameboy.cycles() < 1_000_000 {
+        gameboy.run_frame().unwrap();
+    }
+    assert_eq!(gameboy.read_byte(0xC000), 0x00);
+
+    while gameboy.cycles() < 1_100_000 {
+        gameboy.run_frame().unwrap();
+    }
+    assert_eq!(gameboy.read_byte(0xC000), 0x01);
+}
+
+#[test]
+fn test_rumble_cartridge_reports_motor_to_callback() {
+    // LD A,$08; LD ($4000),A; LD A,$09; LD ($4000),A; XOR A; LD ($4000),A
+    let mut rom = rom_with_program(&[
+        0x3E, 0x08, 0xEA, 0x00, 0x40, 0x3E, 0x09, 0xEA, 0x00, 0x40, 0xAF, 0xEA, 0x00, 0x40,
+    ]);
+    rom[0x0147] = 0x1C;
+    rom[0x014D] = header_checksum(&rom);
+
+    let switches = Rc::new(RefCell::new(Vec::new()));
+    let mut gameboy = GameBoy::new();
+    let recorded = switches.clone();
+    gameboy.set_rumble_callback(move |rumble| recorded.borrow_mut().push(rumble));
+    gameboy.load_rom(rom).unwrap();
+
+    gameboy.step().unwrap();
+    gameboy.step().unwrap();
+    assert_eq!(gameboy.rumble(), true);
+    for _ in 0..4 {
+        gameboy.step().unwrap();
+    }
+
+    assert_eq!(gameboy.rumble(), false);
+    assert_eq!(*switches.borrow(), vec![true, false]);
+}
+
+/// A clock the test moves forward by hand.
+struct ManualClock(Rc<Cell<Duration>>);
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        self.0.get()
+    }
+}
+
+#[test]
+fn test_program_reads_mbc3_clock_from_injected_clock() {
+    // LD A,$0A; LD ($0000),A
+    // loop: XOR A; LD ($6000),A; INC A; LD ($6000),A
+    // LD A,$08; LD ($4000),A; LD A,($A000); LD ($C000),A
+    // LD A,$09; LD ($4000),A; LD A,($A000); LD ($C001),A; JR loop
+    let mut rom = rom_with_program(&[
+        0x3E, 0x0A, 0xEA, 0x00, 0x00, 0xAF, 0xEA, 0x00, 0x60, 0x3C, 0xEA, 0x00, 0x60, 0x3E, 0x08,
+        0xEA, 0x00, 0x40, 0xFA, 0x00, 0xA0, 0xEA, 0x00, 0xC0, 0x3E, 0x09, 0xEA, 0x00, 0x40, 0xFA,
+        0x00, 0xA0, 0xEA, 0x01, 0xC0, 0x18, 0xE0,
+    ]);
+    rom[0x0147] = 0x10;
+    rom[0x0149] = 0x02;
+    rom[0x014D] = header_checksum(&rom);
+
+    let time = Rc::new(Cell::new(Duration::from_secs(0)));
+    let mut gameboy = GameBoy::new();
+    gameboy
+        .load_rom_with_clock(rom, Box::new(ManualClock(time.clone())))
+        .unwrap();
+
+    // Emulated time alone does not move an injected clock.
+    for _ in 0..120 {
+        gameboy.run_frame().unwrap();
+    }
+    assert_eq!(gameboy.read_byte(0xC000), 0);
+    assert_eq!(gameboy.read_byte(0xC001), 0);
+
+    time.set(Duration::from_secs(60 * 60 + 2 * 60 + 5));
+    gameboy.run_frame().unwrap();
+    assert_eq!(gameboy.read_byte(0xC000), 5);
+    assert_eq!(gameboy.read_byte(0xC001), 2);
+}